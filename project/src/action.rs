@@ -0,0 +1,335 @@
+use std::{path::PathBuf, time::Duration};
+
+use tokio::sync::mpsc;
+
+use crate::{
+    app::{ColumnBlock, ColumnProvider},
+    event::{Event, GlobalEvent, GlobalGenericEventData},
+    playlist_file,
+    providers::{
+        journal::{Journal, JournalEntry},
+        provider_error::{ProviderError, ProviderResult},
+        provider_traits::APIProvider,
+        registry::ProviderId,
+    },
+    reorder::{self, SortKey},
+    request,
+    types::music_types::{PlaylistDetails, PlaylistIdWrapper, RSyncPlaylistItem, RSyncSong},
+};
+
+/// Provider call asked for by a key handler, the [`ActionExecutor`] runs it in the background.
+#[derive(Clone, Debug)]
+pub enum Action {
+    SearchSongs {
+        playlist_id: PlaylistIdWrapper,
+        query: String,
+    },
+    AddSong {
+        playlist_id: PlaylistIdWrapper,
+        song_id: String,
+    },
+    RemoveSongs {
+        playlist_id: PlaylistIdWrapper,
+        song_ids: Vec<String>,
+    },
+    /// Puts the songs into `order`, the song list already shows it.
+    MoveSongs {
+        playlist_id: PlaylistIdWrapper,
+        order: Vec<usize>,
+    },
+    /// Sorts the songs as the service lists them, including the ones not loaded yet.
+    SortSongs {
+        playlist_id: PlaylistIdWrapper,
+        key: SortKey,
+    },
+    CreatePlaylist(String),
+    UpdatePlaylist {
+        playlist: RSyncPlaylistItem,
+        details: PlaylistDetails,
+    },
+    DeletePlaylist(RSyncPlaylistItem),
+    ExportPlaylist {
+        playlist: RSyncPlaylistItem,
+        path: PathBuf,
+    },
+    /// Reverts the entry, it goes back into the journal if that fails.
+    Undo(JournalEntry),
+}
+
+impl Action {
+    /// Block of the column whose content the action changes, it shows a loading state
+    /// until the action finished and is reloaded afterwards.
+    pub fn block(&self) -> Option<ColumnBlock> {
+        match self {
+            Action::AddSong { .. } | Action::RemoveSongs { .. } | Action::SortSongs { .. } => {
+                Some(ColumnBlock::SongSelector)
+            }
+            Action::CreatePlaylist(_)
+            | Action::UpdatePlaylist { .. }
+            | Action::DeletePlaylist(_) => Some(ColumnBlock::PlaylistSelector),
+            Action::Undo(entry) => match entry {
+                JournalEntry::AddSongs { .. }
+                | JournalEntry::RemoveSongs { .. }
+                | JournalEntry::MoveSongs { .. } => Some(ColumnBlock::SongSelector),
+                JournalEntry::CreatePlaylist { .. }
+                | JournalEntry::UpdatePlaylist { .. }
+                | JournalEntry::DeletePlaylist { .. } => Some(ColumnBlock::PlaylistSelector),
+            },
+            // searches and exports wait in a popup, moves are shown before they are applied
+            Action::SearchSongs { .. }
+            | Action::MoveSongs { .. }
+            | Action::ExportPlaylist { .. } => None,
+        }
+    }
+
+    /// Text of the loading popup for actions without a block to show the loading state in.
+    pub fn loading_message(&self) -> Option<&'static str> {
+        match self {
+            Action::SearchSongs { .. } => Some("Searching for songs. Please wait"),
+            Action::ExportPlaylist { .. } => Some("Exporting playlist. Please wait"),
+            _ => None,
+        }
+    }
+}
+
+/// What a successful [`Action`] hands back to the app.
+#[derive(Clone, Debug)]
+pub enum ActionOutput {
+    /// Search results to pick the song that is added to the playlist.
+    SongsFound {
+        playlist_id: PlaylistIdWrapper,
+        songs: Vec<RSyncSong>,
+    },
+    /// Playlists or songs changed, a dry run only recorded the changes.
+    Changed,
+    /// Moves of the song list were applied remotely, the list is up to date.
+    SongsMoved,
+    /// Number of moves that sorted the playlist.
+    SongsSorted(usize),
+    Message {
+        title: String,
+        message: String,
+    },
+}
+
+/// Finished [`Action`] of a column.
+#[derive(Clone, Debug)]
+pub struct ActionResult {
+    /// Block to reload, see [`Action::block`].
+    pub block: Option<ColumnBlock>,
+    pub output: ProviderResult<ActionOutput>,
+}
+
+/// Runs the actions of one column on a tokio task so the UI loop never waits for a provider,
+/// every result comes back as an [`Event::DataReceived`] with
+/// [`GlobalGenericEventData::ActionFinished`].
+///
+/// The actions run one after another in the order they were dispatched. Quick moves of the
+/// same playlist are merged into one, see [`MOVE_BATCH_WINDOW`].
+#[derive(Clone, Debug)]
+pub struct ActionExecutor {
+    actions: mpsc::UnboundedSender<(u128, Action)>,
+}
+
+impl ActionExecutor {
+    /// Starts the task running the actions with `provider`, it stops once every clone of
+    /// the executor is dropped.
+    pub fn start<P: APIProvider>(
+        provider_id: ProviderId,
+        mut provider: ColumnProvider<P>,
+        journal: Journal,
+        sender: mpsc::UnboundedSender<Event>,
+    ) -> Self {
+        let (actions, mut receiver) = mpsc::unbounded_channel::<(u128, Action)>();
+        tokio::spawn(async move {
+            // action that ended a batch of moves, it runs next
+            let mut next = None;
+            loop {
+                let (request_id, action) = match next.take() {
+                    Some(action) => action,
+                    None => match receiver.recv().await {
+                        Some(action) => action,
+                        None => break,
+                    },
+                };
+                let (request_id, action) = match action {
+                    Action::MoveSongs { playlist_id, order } => {
+                        let (merged, after) =
+                            batch_moves(&mut receiver, request_id, playlist_id, order).await;
+                        next = after;
+                        merged
+                    }
+                    action => (request_id, action),
+                };
+                let block = action.block();
+                // a failed move leaves the song list in an order the service does not have
+                let failed_block = match action {
+                    Action::MoveSongs { .. } => Some(ColumnBlock::SongSelector),
+                    _ => block,
+                };
+                let output = run(&mut provider, &journal, action).await;
+                let result = ActionResult {
+                    block: match output.is_ok() {
+                        true => block,
+                        false => failed_block,
+                    },
+                    output,
+                };
+                let _ = sender.send(Event::DataReceived(
+                    request_id,
+                    GlobalEvent::Generic(GlobalGenericEventData::ActionFinished(
+                        provider_id.clone(),
+                        result,
+                    )),
+                ));
+            }
+        });
+        Self { actions }
+    }
+
+    pub fn dispatch(&self, action: Action) {
+        let _ = self.actions.send((request::next_request_id(), action));
+    }
+}
+
+/// Wait for a further move of the same playlist before the moves are sent, every move
+/// lists the whole playlist to translate its positions.
+const MOVE_BATCH_WINDOW: Duration = Duration::from_millis(400);
+
+/// Merges the moves of the playlist that follow within [`MOVE_BATCH_WINDOW`] of each other.
+///
+/// Returns the merged move with the request id of the last one, and the action that ended
+/// the batch if it was no move of the same playlist.
+async fn batch_moves(
+    receiver: &mut mpsc::UnboundedReceiver<(u128, Action)>,
+    mut request_id: u128,
+    playlist_id: PlaylistIdWrapper,
+    mut order: Vec<usize>,
+) -> ((u128, Action), Option<(u128, Action)>) {
+    loop {
+        match tokio::time::timeout(MOVE_BATCH_WINDOW, receiver.recv()).await {
+            Ok(Some((
+                next_request_id,
+                Action::MoveSongs {
+                    playlist_id: next_playlist_id,
+                    order: next_order,
+                },
+            ))) if next_playlist_id == playlist_id && next_order.len() == order.len() => {
+                // the next order refers to the positions the previous one left
+                order = next_order.iter().map(|position| order[*position]).collect();
+                request_id = next_request_id;
+            }
+            next => {
+                let merged = (request_id, Action::MoveSongs { playlist_id, order });
+                return (merged, next.ok().flatten());
+            }
+        }
+    }
+}
+
+async fn run<P: APIProvider>(
+    provider: &mut ColumnProvider<P>,
+    journal: &Journal,
+    action: Action,
+) -> ProviderResult<ActionOutput> {
+    match action {
+        Action::SearchSongs { playlist_id, query } => {
+            let songs = provider.search(query, 10).await?;
+            Ok(ActionOutput::SongsFound { playlist_id, songs })
+        }
+        Action::AddSong {
+            playlist_id,
+            song_id,
+        } => {
+            provider
+                .add_playlist_song(playlist_id, vec![song_id])
+                .await?
+                .into_result()?;
+            Ok(ActionOutput::Changed)
+        }
+        Action::RemoveSongs {
+            playlist_id,
+            song_ids,
+        } => {
+            provider.rem_playlist_song(playlist_id, song_ids).await?;
+            Ok(ActionOutput::Changed)
+        }
+        Action::MoveSongs { playlist_id, order } => {
+            reorder::reorder(provider, playlist_id, &order).await?;
+            Ok(ActionOutput::SongsMoved)
+        }
+        Action::SortSongs { playlist_id, key } => {
+            let songs = provider.get_playlist_songs(playlist_id.clone()).await?;
+            let order = reorder::sorted_order(&songs, key);
+            let moves = reorder::reorder(provider, playlist_id, &order).await?;
+            Ok(ActionOutput::SongsSorted(moves))
+        }
+        Action::CreatePlaylist(name) => {
+            provider.create_playlist(name).await?;
+            Ok(ActionOutput::Changed)
+        }
+        Action::UpdatePlaylist { playlist, details } => {
+            provider.update_playlist(playlist, details).await?;
+            Ok(ActionOutput::Changed)
+        }
+        Action::DeletePlaylist(playlist) => {
+            provider.delete_playlist(playlist.id).await?;
+            Ok(ActionOutput::Changed)
+        }
+        Action::ExportPlaylist { playlist, path } => {
+            let songs = provider.get_playlist_songs(playlist.id).await?;
+            playlist_file::export(&playlist.name, &songs, &path).map_err(|err| {
+                ProviderError::Other(format!("Failed to export playlist: {}", err))
+            })?;
+            Ok(ActionOutput::Message {
+                title: "Playlist exported".into(),
+                message: format!("{} songs written to {}", songs.len(), path.display()),
+            })
+        }
+        Action::Undo(entry) => match provider.inner_mut().undo(&entry).await {
+            Ok(message) => Ok(ActionOutput::Message {
+                title: "Undone".into(),
+                message,
+            }),
+            Err(error) => {
+                // keep the entry so the undo can be retried
+                journal.record(entry);
+                Err(error)
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn song_move(order: Vec<usize>) -> Action {
+        Action::MoveSongs {
+            playlist_id: PlaylistIdWrapper::Id("road-trip".into()),
+            order,
+        }
+    }
+
+    #[tokio::test]
+    async fn quick_moves_are_merged() {
+        let (actions, mut receiver) = mpsc::unbounded_channel();
+        // the last of three songs moved up twice
+        actions.send((2, song_move(vec![1, 0, 2]))).unwrap();
+        actions
+            .send((3, Action::CreatePlaylist("Evening".into())))
+            .unwrap();
+
+        let ((request_id, merged), next) = batch_moves(
+            &mut receiver,
+            1,
+            PlaylistIdWrapper::Id("road-trip".into()),
+            vec![0, 2, 1],
+        )
+        .await;
+
+        assert_eq!(request_id, 2);
+        assert!(matches!(merged, Action::MoveSongs { order, .. } if order == [2, 0, 1]));
+        assert!(matches!(next, Some((3, Action::CreatePlaylist(_)))));
+    }
+}
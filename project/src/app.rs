@@ -1,32 +1,58 @@
-use std::error;
+use std::{env, error, path::Path};
 
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use tokio::sync::mpsc;
 
-use crate::{event::{Event, GlobalEvent, GlobalGenericEventData, TransferUpdateEventData}, providers::{spotify_provider::SpotifyProvider, youtube_provider::YoutubeProvider}, widgets::{popups::{add_playlist::AddPlaylistPopup, add_song::AddSongPopup, add_song_selection::AddSongSelectionPopup, message_popup::MessagePopup, popup::{GenericPopup, PlatformPopup, PopupEvent, PopupTyped}}, spotify_column::SpotifyColumn, youtube_column::YoutubeColumn}};
+use crate::{action::{Action, ActionExecutor, ActionOutput, ActionResult}, event::{Event, GlobalEvent, GlobalEventData, GlobalGenericEventData, TransferUpdateEventData}, jobs::{JobId, JobManager, JobSpec, JobState}, providers::{dry_run::{DryRun, DryRunProvider}, journal::{Journal, JournaledProvider}, provider_error::{ProviderError, ProviderResult}, registry::{AnyProvider, ProviderId, ProviderRegistry}}, playlist_file, request, sync::{self, SyncPair, SyncSide, SyncState}, transfer::TransferReporter, types::music_types::{PlaylistIdWrapper, RSyncSong}, widgets::{popups::{add_playlist::AddPlaylistPopup, add_song::AddSongPopup, add_song_selection::AddSongSelectionPopup, confirm::{ConfirmAction, ConfirmPopup}, dry_run::DryRunPopup, loading_popup::LoadingPopup, message_popup::MessagePopup, playlist_details::PlaylistDetailsPopup, playlist_file::{PlaylistFileAction, PlaylistFilePopup}, popup::{GenericPopup, PlatformPopup, PopupEvent, PopupTyped}, sort_songs::SortSongsPopup, transfer_report::TransferReportPopup, transfer_review::TransferReviewPopup}, jobs_panel::JobsPanel, provider_column::ProviderColumn}};
 use crate::providers::provider_traits::APIProvider;
 
 /// Application result type.
 pub type AppResult<T> = std::result::Result<T, Box<dyn error::Error>>;
 
+/// Block inside of a column.
 #[derive(Debug, PartialEq, Clone, Copy)]
-pub enum ActiveBlock {
-    SpotifyPlaylistSelector,
-    YoutubePlaylistSelector,
-    SpotifySongSelector,
-    YoutubeSongSelector,
+pub enum ColumnBlock {
+    PlaylistSelector,
+    SongSelector,
 }
 
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct ActiveBlock {
+    /// Index into [`App::columns`].
+    pub column: usize,
+    pub block: ColumnBlock,
+}
+
+/// Transfers of more songs than this ask for a confirmation, unless `RSYNC_CONFIRM_THRESHOLD` is set.
+const DEFAULT_CONFIRM_THRESHOLD: usize = 25;
+
+/// Provider of a column, writes are journaled unless the dry run only records them.
+pub type ColumnProvider<P> = DryRunProvider<JournaledProvider<P>>;
+
 /// Application.
 #[derive(Debug)]
-pub struct App {
+pub struct App<P: APIProvider = AnyProvider> {
     // Is the application running?
     pub running: bool,
     // currently active block widget
     pub active_view: ActiveBlock,
 
-    pub spotify_column: SpotifyColumn,
-    pub youtube_column: YoutubeColumn,
+    /// One column per registered provider, in the registration order.
+    pub columns: Vec<ProviderColumn<ColumnProvider<P>>>,
+
+    /// While enabled, the providers only record what they would change.
+    pub dry_run: DryRun,
+
+    /// Executed mutations of all providers, [u] reverts the last one.
+    pub journal: Journal,
+
+    /// Transfers of more songs ask for a confirmation first.
+    pub confirm_threshold: usize,
+
+    /// Transfers between the columns, queued and run in the background.
+    pub jobs: JobManager,
+
+    pub jobs_panel: JobsPanel,
 
     pub popup: Option<PopupTyped>,
 
@@ -34,23 +60,49 @@ pub struct App {
 }
 
 impl App {
-    /// Constructs a new instance of [`App`].
-    pub async fn new(global_event_sender: mpsc::UnboundedSender<Event>) -> Self {
+    /// Constructs a new instance of [`App`] connected to the live services.
+    pub async fn new(global_event_sender: mpsc::UnboundedSender<Event>) -> ProviderResult<Self> {
+        Ok(App::with_registry(ProviderRegistry::live().await?, global_event_sender))
+    }
+}
+
+impl<P: APIProvider> App<P> {
+    /// Constructs a new instance of [`App`] with a column for every registered provider, e.g. [`MockProvider`](crate::providers::mock_provider::MockProvider) during development.
+    pub fn with_registry(registry: ProviderRegistry<P>, global_event_sender: mpsc::UnboundedSender<Event>) -> Self {
+        App::with_journal(registry, Journal::load(&Journal::default_path()), global_event_sender)
+    }
+
+    /// Constructs a new instance of [`App`] that records the mutations into `journal`, e.g. an in-memory one in tests.
+    pub fn with_journal(registry: ProviderRegistry<P>, journal: Journal, global_event_sender: mpsc::UnboundedSender<Event>) -> Self {
+        let dry_run = DryRun::default();
+        let columns = registry.into_iter().map(|registered| {
+            let provider = JournaledProvider::new(registered.provider, registered.id.clone(), journal.clone());
+            let provider = DryRunProvider::new(provider, &registered.name, dry_run.clone());
+            let executor = ActionExecutor::start(registered.id.clone(), provider.clone(), journal.clone(), global_event_sender.clone());
+            ProviderColumn::new(registered.id, registered.name, provider, executor, global_event_sender.clone())
+        }).collect();
         let mut app = Self {
             running: true,
-            active_view: ActiveBlock::SpotifyPlaylistSelector,
-            spotify_column: SpotifyColumn::new(SpotifyProvider::new().await, global_event_sender.clone()),
-            youtube_column: YoutubeColumn::new(YoutubeProvider::new().await, global_event_sender.clone()),
+            active_view: ActiveBlock { column: 0, block: ColumnBlock::PlaylistSelector },
+            columns,
+            dry_run,
+            journal,
+            confirm_threshold: env::var("RSYNC_CONFIRM_THRESHOLD").ok().and_then(|threshold| threshold.parse().ok()).unwrap_or(DEFAULT_CONFIRM_THRESHOLD),
+            jobs: JobManager::default(),
+            jobs_panel: JobsPanel::new(),
 
             popup: None,
             global_event_sender,
         };
-        app.spotify_column.playlist_selector.active = true;
+        app.set_block_active(app.active_view, true);
         app
     }
 
-    /// Handles the tick event of the terminal.
-    pub fn tick(&self) {
+    /// Handles the tick event of the terminal, deferred jobs are checked again after the quota reset.
+    pub fn tick(&mut self) {
+        if self.jobs.has_due_deferred() {
+            self.start_jobs();
+        }
     }
 
     /// Set running to false to quit the application.
@@ -62,40 +114,234 @@ impl App {
         match data {
             GlobalEvent::Generic(global_event_data) => {
                 match global_event_data {
-                    GlobalGenericEventData::TransferUpdate(transfer_update_event_data) => {
+                    GlobalGenericEventData::TransferUpdate(_, update) if self.jobs.has_request(request_id) => self.handle_job_update(request_id, update),
+                    GlobalGenericEventData::TransferUpdate(target, transfer_update_event_data) => {
                         let title = "Transfering".into();
                         match transfer_update_event_data {
                             TransferUpdateEventData::Searching => {
                                 self.popup = Some(PopupTyped::Generic(GenericPopup::Message(MessagePopup::new(title, "Searching for songs. Please wait".into()))))
                             },
+                            // only jobs report their progress and review their matches
+                            TransferUpdateEventData::Progress { .. } |
+                            TransferUpdateEventData::Review(_) => {},
                             TransferUpdateEventData::Updating => {
 
                                 self.popup = Some(PopupTyped::Generic(GenericPopup::Message(MessagePopup::new(title, "Updating playlist. Please wait".into()))))
                             },
-                            TransferUpdateEventData::Finished => {
-                                self.popup = None;
-                                match self.active_view {
-                                    ActiveBlock::SpotifyPlaylistSelector |
-                                    ActiveBlock::SpotifySongSelector => {
-                                        self.youtube_column.refresh_songs();
-                                    },
-                                    ActiveBlock::YoutubePlaylistSelector |
-                                    ActiveBlock::YoutubeSongSelector => {
-                                        self.spotify_column.refresh_songs();
-                                    },
+                            TransferUpdateEventData::Finished(report) => {
+                                if !self.show_dry_run_changes() {
+                                    self.popup = Some(PopupTyped::Generic(GenericPopup::TransferReport(Box::new(TransferReportPopup::new(report)))));
+                                }
+                                let active_block = self.active_view.block;
+                                if let Some(column) = self.column_mut(&target) {
+                                    match active_block {
+                                        ColumnBlock::PlaylistSelector => column.refresh_playlists(),
+                                        ColumnBlock::SongSelector => column.refresh_songs(),
+                                    }
                                 }
                             },
                         }
                     },
+                    GlobalGenericEventData::SyncFinished(summary) => {
+                        self.popup = Some(PopupTyped::Generic(GenericPopup::Message(MessagePopup::new("Sync finished".into(), summary.message()))));
+                        for column in self.columns.iter_mut() {
+                            column.refresh_songs();
+                        }
+                    },
+                    GlobalGenericEventData::ActionFinished(provider_id, result) => self.handle_action_result(provider_id, result),
+                    GlobalGenericEventData::Error(error) if self.jobs.has_request(request_id) => {
+                        if self.jobs.fail(request_id, error) {
+                            self.start_jobs();
+                        }
+                    },
+                    GlobalGenericEventData::Error(error) => self.show_error(&error),
+                }
+            },
+            GlobalEvent::Provider(provider_id, global_event_data) => {
+                if let GlobalEventData::Error(error) = &global_event_data {
+                    self.show_error(error);
+                }
+                if let Some(column) = self.column_mut(&provider_id) {
+                    column.handle_received_data(request_id, global_event_data)
+                }
+            },
+        }
+    }
+
+    /// Applies the progress of a job, the next queued jobs start once it ended or waits for a review.
+    fn handle_job_update(&mut self, request_id: u128, update: TransferUpdateEventData) {
+        let Some(job) = self.jobs.update(request_id, update) else {
+            return;
+        };
+        if job.is_running() {
+            return;
+        }
+        if let JobState::Finished(_) = job.state {
+            let target = job.target.clone();
+            let playlist_id = match &job.spec {
+                JobSpec::AddSongs { playlist_id, .. } => Some(playlist_id.clone()),
+                JobSpec::MirrorPlaylist { .. } => None,
+            };
+            self.show_dry_run_changes();
+            if let Some(column) = self.column_mut(&target) {
+                // the user may be browsing elsewhere, only the changed list is reloaded
+                match playlist_id {
+                    Some(playlist_id) => {
+                        if column.playlist_selector.get_selected().first().is_some_and(|playlist| playlist.id == playlist_id) {
+                            column.refresh_songs();
+                        }
+                    },
+                    None => column.refresh_playlists(),
+                }
+            }
+        }
+        self.start_jobs();
+    }
+
+    /// Queues a transfer into the column at `index`, the jobs panel shows its progress.
+    fn enqueue_job(&mut self, index: usize, title: String, spec: JobSpec) {
+        self.jobs.enqueue(self.columns[index].id.clone(), title, spec);
+        self.jobs_panel.visible = true;
+        self.start_jobs();
+    }
+
+    /// Starts the queued jobs whose target provider has no running job.
+    fn start_jobs(&mut self) {
+        let provider = |provider_id: &ProviderId| self.columns.iter().find(|column| &column.id == provider_id).map(|column| column.provider.clone());
+        self.jobs.start_queued(provider, &self.global_event_sender);
+    }
+
+    /// Opens the review of a job waiting for it, or the report or error of an ended job.
+    fn open_job(&mut self, id: JobId) {
+        let Some(job) = self.jobs.get(id) else {
+            return;
+        };
+        match &job.state {
+            JobState::Review(plan) => {
+                self.popup = Some(PopupTyped::Platform(job.target.clone(), PlatformPopup::TransferReview(Box::new(TransferReviewPopup::new(id, plan.clone())))));
+            },
+            JobState::Finished(report) => {
+                self.popup = Some(PopupTyped::Generic(GenericPopup::TransferReport(Box::new(TransferReportPopup::new(report.clone())))));
+            },
+            JobState::Failed(error) => {
+                let error = error.clone();
+                self.show_error(&error);
+            },
+            _ => {},
+        }
+    }
+
+    /// Shows the jobs panel and moves the keys from the active column to it.
+    fn focus_jobs_panel(&mut self, active: bool) {
+        self.jobs_panel.active = active;
+        if active {
+            self.jobs_panel.visible = true;
+        }
+        self.set_block_active(self.active_view, !active);
+    }
+
+    /// Handles a key of the focused jobs panel, [tab] and [esc] go back to the columns.
+    fn handle_jobs_panel_key(&mut self, key_event: KeyEvent) {
+        if self.jobs_panel.handle_key_events(key_event) {
+            return;
+        }
+        let job = self.jobs_panel.cursor_job();
+        match (key_event.code, job) {
+            (KeyCode::Char('j'), _) => {
+                self.focus_jobs_panel(false);
+                self.jobs_panel.visible = false;
+            },
+            (KeyCode::Tab | KeyCode::Esc, _) => self.focus_jobs_panel(false),
+            (KeyCode::Enter, Some(id)) => self.open_job(id),
+            (KeyCode::Char('c'), Some(id)) => {
+                match self.jobs.cancel(id) {
+                    Ok(()) => self.start_jobs(),
+                    Err(error) => self.show_error(&error),
+                }
+            },
+            (KeyCode::Char('r'), Some(id)) => {
+                match self.jobs.retry(id) {
+                    Ok(()) => self.start_jobs(),
+                    Err(error) => self.show_error(&error),
+                }
+            },
+            (KeyCode::Char('x') | KeyCode::Delete, Some(id)) => self.jobs.remove(id),
+            _ => {},
+        }
+    }
+
+    /// Shows the outcome of a background action and reloads the block it changed.
+    fn handle_action_result(&mut self, provider_id: ProviderId, result: ActionResult) {
+        if let Some(PopupTyped::Generic(GenericPopup::Loading(_))) = self.popup {
+            self.popup = None;
+        }
+        if let Some(column) = self.column_mut(&provider_id) {
+            match result.block {
+                Some(ColumnBlock::PlaylistSelector) => column.refresh_playlists(),
+                Some(ColumnBlock::SongSelector) => column.refresh_songs(),
+                None => (),
+            }
+        }
+        match result.output {
+            Ok(ActionOutput::SongsFound { playlist_id, songs }) => {
+                self.popup = Some(PopupTyped::Platform(provider_id, PlatformPopup::AddSongSelect(AddSongSelectionPopup::new(songs, playlist_id))));
+            },
+            Ok(ActionOutput::Changed) | Ok(ActionOutput::SongsMoved) => {
+                self.show_dry_run_changes();
+            },
+            Ok(ActionOutput::SongsSorted(moves)) => {
+                if !self.show_dry_run_changes() {
+                    self.show_message("Songs sorted", format!("{} songs moved", moves));
                 }
             },
-            GlobalEvent::Spotify(global_event_data) => self.spotify_column.handle_received_data(request_id, global_event_data),
-            GlobalEvent::Youtube(global_event_data) => self.youtube_column.handle_received_data(request_id, global_event_data),
+            Ok(ActionOutput::Message { title, message }) => self.show_message(&title, message),
+            Err(error) => self.show_error(&error),
+        }
+    }
+
+    /// Runs `action` on the provider of the column at `index` without waiting for it, see [`ActionExecutor`].
+    fn dispatch(&mut self, index: usize, action: Action) {
+        if let Some(message) = action.loading_message() {
+            self.popup = Some(PopupTyped::Generic(GenericPopup::Loading(LoadingPopup::new(message.into()))));
         }
+        self.columns[index].dispatch(action);
     }
 
-    pub async fn handle_key_events(&mut self, key_event: KeyEvent) {
-        self.check_close_key(key_event);
+    fn column_mut(&mut self, id: &ProviderId) -> Option<&mut ProviderColumn<ColumnProvider<P>>> {
+        self.columns.iter_mut().find(|column| &column.id == id)
+    }
+
+    fn active_column(&mut self) -> &mut ProviderColumn<ColumnProvider<P>> {
+        &mut self.columns[self.active_view.column]
+    }
+
+    pub fn show_error(&mut self, error: &ProviderError) {
+        self.popup = Some(PopupTyped::Generic(GenericPopup::Message(MessagePopup::new(error.title().into(), error.message().into()))));
+    }
+
+    /// Shows the writes recorded since the last call, returns `false` outside of a dry run.
+    fn show_dry_run_changes(&mut self) -> bool {
+        if !self.dry_run.is_enabled() {
+            return false;
+        }
+        // searches are sent during a dry run as well, so their quota is gone
+        let notes = self.columns.iter().filter_map(|column| {
+            let quota = column.provider.quota()?;
+            Some(format!("Searches still count against the {} quota, {} units are left today", column.name, quota.summary()))
+        }).collect();
+        self.popup = Some(PopupTyped::Generic(GenericPopup::DryRun(DryRunPopup::new(&self.dry_run.take()).with_notes(notes))));
+        true
+    }
+
+    fn show_message(&mut self, title: &str, message: String) {
+        self.popup = Some(PopupTyped::Generic(GenericPopup::Message(MessagePopup::new(title.into(), message))));
+    }
+
+    pub fn handle_key_events(&mut self, key_event: KeyEvent) {
+        if key_event.modifiers == KeyModifiers::CONTROL && matches!(key_event.code, KeyCode::Char('c') | KeyCode::Char('C')) {
+            self.quit();
+            return;
+        }
         if let Some(ref mut popup) = self.popup {
             match popup.handle_key_events(key_event) {
                 PopupEvent::PopupClose => {
@@ -108,60 +354,116 @@ impl App {
                     self.selective_refresh();
                     self.popup = None;
                 },
+                PopupEvent::Submit => {
+                    // the sort dialog stays open until a key is chosen
+                    if let Some(PopupTyped::Platform(_, PlatformPopup::SortSongs(popup))) = &mut self.popup {
+                        if popup.key().is_none() {
+                            return;
+                        }
+                    }
+                    match self.popup.take() {
+                        Some(PopupTyped::Platform(_, PlatformPopup::TransferReview(popup))) => {
+                            let job = popup.job;
+                            self.jobs.commit_review(job, popup.into_plan());
+                            self.start_jobs();
+                        },
+                        Some(PopupTyped::Platform(provider_id, PlatformPopup::PlaylistDetails(popup))) => {
+                            let details = popup.details();
+                            if let Some(column) = self.column_mut(&provider_id) {
+                                column.dispatch(Action::UpdatePlaylist { playlist: popup.playlist, details });
+                            }
+                        },
+                        Some(PopupTyped::Platform(provider_id, PlatformPopup::SortSongs(mut popup))) => {
+                            if let (Some(key), Some(column)) = (popup.key(), self.column_mut(&provider_id)) {
+                                column.dispatch(Action::SortSongs { playlist_id: popup.playlist_id, key });
+                            }
+                        },
+                        popup => self.popup = popup,
+                    }
+                },
+                PopupEvent::Confirmed => {
+                    if let Some(PopupTyped::Platform(provider_id, PlatformPopup::Confirm(popup))) = self.popup.take() {
+                        if let Some(index) = self.columns.iter().position(|column| column.id == provider_id) {
+                            self.run_confirmed(index, popup.action);
+                        }
+                    }
+                },
+                PopupEvent::Cancelled => {
+                    self.popup = None;
+                },
                 PopupEvent::PopupCloseData(received_data) => {
                     match &popup {
-                        PopupTyped::Spotify(popup) => {
+                        PopupTyped::Platform(provider_id, popup) => {
+                            let provider_id = provider_id.clone();
+                            let Some(index) = self.columns.iter().position(|column| column.id == provider_id) else {
+                                self.popup = None;
+                                return;
+                            };
                             match popup {
                                 PlatformPopup::AddSong(popup) => {
-                                    let found_songs = self.spotify_column.provider.search(received_data, 10).await;
-                                    self.popup = Some(PopupTyped::Spotify(PlatformPopup::AddSongSelect(AddSongSelectionPopup::new(found_songs, popup.playlist_id.clone()))));
+                                    let playlist_id = popup.playlist_id.clone();
+                                    self.dispatch(index, Action::SearchSongs { playlist_id, query: received_data });
                                 },
                                 PlatformPopup::AddSongSelect(popup) => {
-                                    self.spotify_column.provider.add_playlist_song(popup.playlist_id.clone(), Vec::from([received_data])).await;
-                                    self.selective_refresh();
+                                    let playlist_id = popup.playlist_id.clone();
                                     self.popup = None;
+                                    self.dispatch(index, Action::AddSong { playlist_id, song_id: received_data });
                                 },
                                 PlatformPopup::AddPlaylist(_) => {
-                                    self.spotify_column.provider.create_playlist(received_data).await;
-                                    self.spotify_column.refresh_playlists();
                                     self.popup = None;
+                                    self.dispatch(index, Action::CreatePlaylist(received_data));
                                 },
-                            }
-                        },
-                        PopupTyped::Youtube(popup) => {
-                            match popup {
-                                PlatformPopup::AddSong(popup) => {
-                                    let found_songs = self.youtube_column.provider.search(received_data, 10).await;
-                                    self.popup = Some(PopupTyped::Youtube(PlatformPopup::AddSongSelect(AddSongSelectionPopup::new(found_songs, popup.playlist_id.clone()))));
-                                },
-                                PlatformPopup::AddSongSelect(popup) => {
-                                    self.youtube_column.provider.add_playlist_song(popup.playlist_id.clone(), Vec::from([received_data])).await;
-                                    self.selective_refresh();
-                                    self.popup = None;
-                                },
-                                PlatformPopup::AddPlaylist(_) => {
-                                    self.youtube_column.provider.create_playlist(received_data).await;
-                                    self.youtube_column.refresh_playlists();
-                                    self.popup = None;
+                                PlatformPopup::PlaylistFile(popup) => {
+                                    let path = Path::new(&received_data);
+                                    match &popup.action {
+                                        PlaylistFileAction::Export(playlist) => {
+                                            let playlist = playlist.clone();
+                                            self.dispatch(index, Action::ExportPlaylist { playlist, path: path.to_path_buf() });
+                                        },
+                                        PlaylistFileAction::Import(playlist_id) => {
+                                            let playlist_id = playlist_id.clone();
+                                            match playlist_file::import(path) {
+                                                Ok(songs) if songs.is_empty() => self.show_message("Error", format!("No songs found in {}", path.display())),
+                                                Ok(songs) => {
+                                                    self.popup = None;
+                                                    let count = songs.len();
+                                                    let labels = song_labels(&songs);
+                                                    let message = format!("Import {} songs from {} into {}?", count, path.display(), self.selected_playlist_name(index));
+                                                    let popup = ConfirmPopup::new("Import playlist", message, ConfirmAction::AddSongs { playlist_id, songs }).with_items(&labels);
+                                                    self.run_or_confirm(index, count, popup);
+                                                },
+                                                Err(err) => self.show_message("Error", format!("Failed to import playlist: {}", err)),
+                                            }
+                                        },
+                                    }
                                 },
+                                PlatformPopup::TransferReview(_) |
+                                PlatformPopup::Confirm(_) |
+                                PlatformPopup::PlaylistDetails(_) |
+                                PlatformPopup::SortSongs(_) => self.show_message("Error", "The dialog returned data it does not take".into()),
                             }
                         },
                         PopupTyped::Generic(popup) => {
                             match popup {
-                                GenericPopup::Message(_) => panic!("Not returning any data!"),
+                                GenericPopup::Message(_) |
+                                GenericPopup::TransferReport(_) |
+                                GenericPopup::DryRun(_) |
+                                GenericPopup::Loading(_) => self.show_message("Error", "The dialog returned data it does not take".into()),
                             }
                         }
                     }
                 },
             }
+        } else if self.jobs_panel.active {
+            self.handle_jobs_panel_key(key_event);
         } else {
-            match self.active_view {
-                ActiveBlock::SpotifyPlaylistSelector |
-                ActiveBlock::SpotifySongSelector => self.spotify_column.handle_key_events(key_event, self.active_view),
-                ActiveBlock::YoutubePlaylistSelector |
-                ActiveBlock::YoutubeSongSelector => self.youtube_column.handle_key_events(key_event, self.active_view),
+            let active_block = self.active_view.block;
+            // keys used by the active list, e.g. typed into its filter, never reach the app
+            if self.active_column().handle_key_events(key_event, active_block) {
+                return;
             }
-    
+            self.check_close_key(key_event);
+
             match key_event.code {
                 KeyCode::Tab => {
                     self.active_view_switch();
@@ -175,53 +477,50 @@ impl App {
                 KeyCode::Char('h') => {
                     self.selective_refresh();
                 }
-                KeyCode::Delete => {self.handle_item_removing().await;}
+                KeyCode::Delete => {self.handle_item_removing();}
+                KeyCode::Char('l') => {
+                    self.link_selected_playlists();
+                }
+                KeyCode::Char('y') => {
+                    self.sync_linked_playlists();
+                }
+                KeyCode::Char('e') => {
+                    self.handle_playlist_export();
+                }
+                KeyCode::Char('d') => {
+                    let message = match self.dry_run.toggle() {
+                        true => "Dry run enabled, changes are only recorded and shown. Searches still use the quota of the service",
+                        false => "Dry run disabled, changes are applied again",
+                    };
+                    self.show_message("Dry run", message.into());
+                }
+                KeyCode::Char('i') => {
+                    self.handle_playlist_import();
+                }
+                KeyCode::Char('m') => {
+                    self.handle_playlist_edit();
+                }
+                KeyCode::Char('u') => {
+                    self.undo_last_mutation();
+                }
+                KeyCode::Char('S') => {
+                    self.handle_song_sort();
+                }
+                KeyCode::Char('j') => {
+                    self.focus_jobs_panel(true);
+                }
+                KeyCode::Up | KeyCode::Down if key_event.modifiers.contains(KeyModifiers::SHIFT) => {
+                    self.handle_song_move(key_event.code == KeyCode::Up);
+                }
                 KeyCode::Left => {
-                    match self.active_view {
-                        ActiveBlock::YoutubePlaylistSelector => {
-                            self.popup = Some(PopupTyped::Generic(GenericPopup::Message(MessagePopup::new("Error".into(), "Not implemented. Please select playlists and move songs.\nYou can use Ctrl-A to move all songs".into()))))
-                        },
-                        ActiveBlock::YoutubeSongSelector => {
-                            let selected_songs = self.youtube_column.song_selector.get_selected();                            
-                            match (selected_songs.is_empty(), self.spotify_column.playlist_selector.get_selected().first()) {
-                                (false, Some(playlist)) => {
-                                    let p_id = playlist.id.clone();
-                                    self.spotify_column.add_found_songs(p_id, selected_songs).await;
-                                },
-                                (true, None) |
-                                (false, None) => {
-                                    self.popup = Some(PopupTyped::Generic(GenericPopup::Message(MessagePopup::new("Error".into(), "You must choose a spotify playlist".into()))))
-                                },
-                                (true, Some(_)) => {
-                                    self.popup = Some(PopupTyped::Generic(GenericPopup::Message(MessagePopup::new("Error".into(), "You must choose a songs from youtube playlist (use enter)".into()))))
-                                }
-                            }
-                        },
-                        _ => ()
+                    if let Some(target) = self.active_view.column.checked_sub(1) {
+                        self.transfer_to(target);
                     }
                 },
                 KeyCode::Right => {
-                    match self.active_view {
-                        ActiveBlock::SpotifyPlaylistSelector => {
-                            self.popup = Some(PopupTyped::Generic(GenericPopup::Message(MessagePopup::new("Error".into(), "Not implemented. Please select playlists and move songs.\nYou can use Ctrl-A to move all songs".into()))))
-                        },
-                        ActiveBlock::SpotifySongSelector => {
-                            let selected_songs = self.spotify_column.song_selector.get_selected();
-                            match (selected_songs.is_empty(), self.youtube_column.playlist_selector.get_selected().first()) {
-                                (false, Some(playlist)) => {
-                                    let p_id = playlist.id.clone();
-                                    self.youtube_column.add_found_songs(p_id, selected_songs).await;
-                                },
-                                (true, None) |
-                                (false, None) => {
-                                    self.popup = Some(PopupTyped::Generic(GenericPopup::Message(MessagePopup::new("Error".into(), "You must choose a youtube playlist".into()))))
-                                },
-                                (true, Some(_)) => {
-                                    self.popup = Some(PopupTyped::Generic(GenericPopup::Message(MessagePopup::new("Error".into(), "You must choose a songs from spotify playlist (use enter)".into()))))
-                                }
-                            }
-                        },
-                        _ => ()
+                    let target = self.active_view.column + 1;
+                    if target < self.columns.len() {
+                        self.transfer_to(target);
                     }
                 }
                 _ => {}
@@ -229,73 +528,221 @@ impl App {
         }
     }
 
-    pub fn handle_item_adding(&mut self) {
-        self.popup = match self.active_view {
-            ActiveBlock::SpotifyPlaylistSelector => {
-                Some(PopupTyped::Spotify(PlatformPopup::AddPlaylist(AddPlaylistPopup::new())))
-            },
-            ActiveBlock::YoutubePlaylistSelector => {
-                Some(PopupTyped::Youtube(PlatformPopup::AddPlaylist(AddPlaylistPopup::new())))
-            },
-            ActiveBlock::SpotifySongSelector => {
-                match self.spotify_column.playlist_selector.get_selected().first() {
+    /// Transfers the selected playlist or songs of the active column to the `target` column.
+    pub fn transfer_to(&mut self, target: usize) {
+        let source = self.active_view.column;
+        match self.active_view.block {
+            ColumnBlock::PlaylistSelector => {
+                match self.columns[source].playlist_selector.get_selected().first() {
                     Some(playlist) => {
-                        match playlist.owned {
-                            true => Some(PopupTyped::Spotify(PlatformPopup::AddSong(AddSongPopup::new(playlist.id.clone())))),
-                            false => Some(PopupTyped::Generic(GenericPopup::Message(MessagePopup::new("Error".into(), "Missing permissions to modify playlist".to_string())))),
-                        }
+                        let playlist = (*playlist).clone();
+                        let count = playlist.tracks as usize;
+                        let message = format!("Copy {} songs of {} into {}? A playlist with the same name is reused.", count, playlist.name, self.columns[target].name);
+                        let action = ConfirmAction::MirrorPlaylist { source: self.columns[source].id.clone(), playlist };
+                        self.run_or_confirm(target, count, ConfirmPopup::new("Transfer playlist", message, action));
+                    },
+                    None => {
+                        let message = format!("You must choose a {} playlist (use enter)", self.columns[source].name);
+                        self.show_message("Error", message)
+                    }
+                }
+            },
+            ColumnBlock::SongSelector => {
+                let selected_songs: Vec<RSyncSong> = self.columns[source].song_selector.get_selected().into_iter().cloned().collect();
+                match (selected_songs.is_empty(), self.columns[target].playlist_selector.get_selected().first()) {
+                    (false, Some(playlist)) => {
+                        let (playlist_id, playlist_name) = (playlist.id.clone(), playlist.name.clone());
+                        let count = selected_songs.len();
+                        let labels = song_labels(&selected_songs);
+                        let message = format!("Transfer {} songs into {} of {}?", count, playlist_name, self.columns[target].name);
+                        let action = ConfirmAction::AddSongs { playlist_id, songs: selected_songs };
+                        self.run_or_confirm(target, count, ConfirmPopup::new("Transfer songs", message, action).with_items(&labels));
                     },
-                    None => Some(PopupTyped::Generic(GenericPopup::Message(MessagePopup::new("Error".into(), "You must choose a spotify playlist".into())))),
+                    (true, None) |
+                    (false, None) => {
+                        let message = format!("You must choose a {} playlist", self.columns[target].name);
+                        self.show_message("Error", message)
+                    },
+                    (true, Some(_)) => {
+                        let message = format!("You must choose a songs from {} playlist (use enter)", self.columns[source].name);
+                        self.show_message("Error", message)
+                    }
                 }
             },
-            ActiveBlock::YoutubeSongSelector => {
-                match self.youtube_column.playlist_selector.get_selected().first() {
+        }
+    }
+
+    pub fn handle_item_adding(&mut self) {
+        let column = &mut self.columns[self.active_view.column];
+        let provider_id = column.id.clone();
+        self.popup = match self.active_view.block {
+            ColumnBlock::PlaylistSelector => {
+                Some(PopupTyped::Platform(provider_id, PlatformPopup::AddPlaylist(AddPlaylistPopup::new())))
+            },
+            ColumnBlock::SongSelector => {
+                let name = column.name.clone();
+                match column.playlist_selector.get_selected().first() {
                     Some(playlist) => {
                         match playlist.owned {
-                            true => Some(PopupTyped::Youtube(PlatformPopup::AddSong(AddSongPopup::new(playlist.id.clone())))),
+                            true => Some(PopupTyped::Platform(provider_id, PlatformPopup::AddSong(AddSongPopup::new(playlist.id.clone())))),
                             false => Some(PopupTyped::Generic(GenericPopup::Message(MessagePopup::new("Error".into(), "Missing permissions to modify playlist".to_string())))),
                         }
                     },
-                    None => Some(PopupTyped::Generic(GenericPopup::Message(MessagePopup::new("Error".into(), "You must choose a spotify playlist".into())))),
+                    None => Some(PopupTyped::Generic(GenericPopup::Message(MessagePopup::new("Error".into(), format!("You must choose a {} playlist", name))))),
                 }
             },
         }
     }
 
-    pub async fn handle_item_removing(&mut self) {
-        match self.active_view {
-            ActiveBlock::SpotifyPlaylistSelector |
-            ActiveBlock::YoutubePlaylistSelector => {
-                self.popup = Some(PopupTyped::Generic(GenericPopup::Message(MessagePopup::new("Error".into(), "Deleting of playlists not implemented for my own sanity".to_string()))));
+    /// Asks where to export the selected playlist of the active column.
+    pub fn handle_playlist_export(&mut self) {
+        let column = &mut self.columns[self.active_view.column];
+        let provider_id = column.id.clone();
+        let name = column.name.clone();
+        self.popup = match column.playlist_selector.get_selected().first() {
+            Some(playlist) => Some(PopupTyped::Platform(provider_id, PlatformPopup::PlaylistFile(PlaylistFilePopup::export((*playlist).clone())))),
+            None => Some(PopupTyped::Generic(GenericPopup::Message(MessagePopup::new("Error".into(), format!("You must choose a {} playlist (use enter)", name))))),
+        }
+    }
+
+    /// Asks for a playlist file whose songs are transferred into the selected playlist of the active column.
+    pub fn handle_playlist_import(&mut self) {
+        let column = &mut self.columns[self.active_view.column];
+        let provider_id = column.id.clone();
+        let name = column.name.clone();
+        self.popup = match column.playlist_selector.get_selected().first() {
+            Some(playlist) => {
+                match playlist.owned {
+                    true => Some(PopupTyped::Platform(provider_id, PlatformPopup::PlaylistFile(PlaylistFilePopup::import(playlist.id.clone())))),
+                    false => Some(PopupTyped::Generic(GenericPopup::Message(MessagePopup::new("Error".into(), "Missing permissions to modify playlist".to_string())))),
+                }
             },
-            ActiveBlock::SpotifySongSelector => {
-                let selected_songs = self.spotify_column.song_selector.get_selected();
-                if !selected_songs.is_empty() {
-                    if let Some(playlist) = self.spotify_column.playlist_selector.get_selected().first() {
-                        if playlist.owned {
-                            let song_ids = selected_songs.iter().map(|item| item.id.clone()).collect::<Vec<String>>();
-                            self.spotify_column.provider.rem_playlist_song(playlist.id.clone(), song_ids).await;
-                            self.spotify_column.song_selector.clear_selected();
-                            self.spotify_column.refresh_songs();
-                        }
-                    }
+            None => Some(PopupTyped::Generic(GenericPopup::Message(MessagePopup::new("Error".into(), format!("You must choose a {} playlist (use enter)", name))))),
+        }
+    }
+
+    /// Runs the action of the popup right away, or shows the popup when it affects more than [`App::confirm_threshold`] songs.
+    fn run_or_confirm(&mut self, index: usize, count: usize, popup: ConfirmPopup) {
+        match count > self.confirm_threshold {
+            true => self.popup = Some(PopupTyped::Platform(self.columns[index].id.clone(), PlatformPopup::Confirm(popup))),
+            false => self.run_confirmed(index, popup.action),
+        }
+    }
+
+    /// Runs a confirmed action on the column at `index`.
+    fn run_confirmed(&mut self, index: usize, action: ConfirmAction) {
+        match action {
+            ConfirmAction::RemoveSongs { playlist_id, song_ids } => {
+                let column = &mut self.columns[index];
+                column.song_selector.clear_selected();
+                column.dispatch(Action::RemoveSongs { playlist_id, song_ids });
+            },
+            ConfirmAction::AddSongs { playlist_id, songs } => {
+                let title = format!("{} songs -> {} of {}", songs.len(), self.selected_playlist_name(index), self.columns[index].name);
+                self.enqueue_job(index, title, JobSpec::AddSongs { playlist_id, songs });
+            },
+            ConfirmAction::MirrorPlaylist { source, playlist } => {
+                let title = format!("{} -> {}", playlist.name, self.columns[index].name);
+                self.enqueue_job(index, title, JobSpec::MirrorPlaylist { source, playlist });
+            },
+            ConfirmAction::DeletePlaylist(playlist) => {
+                let column = &mut self.columns[index];
+                column.playlist_selector.clear_selected();
+                column.set_songs(None);
+                column.dispatch(Action::DeletePlaylist(playlist));
+            },
+        }
+    }
+
+    fn selected_playlist_name(&mut self, index: usize) -> String {
+        match self.columns[index].playlist_selector.get_selected().first() {
+            Some(playlist) => playlist.name.clone(),
+            None => "the playlist".into(),
+        }
+    }
+
+    /// Opens the details of the selected playlist of the active column for editing.
+    pub fn handle_playlist_edit(&mut self) {
+        let column = self.active_column();
+        let provider_id = column.id.clone();
+        let name = column.name.clone();
+        self.popup = match column.playlist_selector.get_selected().first() {
+            Some(playlist) if playlist.owned && playlist.id != PlaylistIdWrapper::Liked => {
+                Some(PopupTyped::Platform(provider_id, PlatformPopup::PlaylistDetails(Box::new(PlaylistDetailsPopup::new((*playlist).clone())))))
+            },
+            Some(_) => Some(PopupTyped::Generic(GenericPopup::Message(MessagePopup::new("Error".into(), "Missing permissions to modify playlist".to_string())))),
+            None => Some(PopupTyped::Generic(GenericPopup::Message(MessagePopup::new("Error".into(), format!("You must choose a {} playlist (use enter)", name))))),
+        }
+    }
+
+    /// Moves the selected songs, or the one under the cursor, of the active column one place up or down.
+    ///
+    /// The list is changed right away, it is reloaded if the provider fails to apply the move.
+    pub fn handle_song_move(&mut self, up: bool) {
+        if self.active_view.block != ColumnBlock::SongSelector {
+            return;
+        }
+        let column = self.active_column();
+        let Some((playlist_id, owned)) = column.playlist_selector.get_selected().first().map(|playlist| (playlist.id.clone(), playlist.owned)) else {
+            return;
+        };
+        if !owned {
+            self.show_message("Error", "Missing permissions to modify playlist".into());
+            return;
+        }
+        let Some(order) = column.song_selector.move_selected(up) else {
+            return;
+        };
+        column.dispatch(Action::MoveSongs { playlist_id, order });
+    }
+
+    /// Asks by what the songs of the selected playlist of the active column are sorted.
+    pub fn handle_song_sort(&mut self) {
+        let column = self.active_column();
+        let provider_id = column.id.clone();
+        let name = column.name.clone();
+        self.popup = match column.playlist_selector.get_selected().first() {
+            Some(playlist) if playlist.id == PlaylistIdWrapper::Liked => Some(PopupTyped::Generic(GenericPopup::Message(MessagePopup::new("Error".into(), "Favorites are ordered by the date they were liked".to_string())))),
+            Some(playlist) => {
+                match playlist.owned {
+                    true => Some(PopupTyped::Platform(provider_id, PlatformPopup::SortSongs(SortSongsPopup::new(playlist.id.clone())))),
+                    false => Some(PopupTyped::Generic(GenericPopup::Message(MessagePopup::new("Error".into(), "Missing permissions to modify playlist".to_string())))),
                 }
             },
-            ActiveBlock::YoutubeSongSelector => {
-                let selected_songs = self.youtube_column.song_selector.get_selected();
-                if !selected_songs.is_empty() {
-                    if let Some(playlist) = self.youtube_column.playlist_selector.get_selected().first() {
+            None => Some(PopupTyped::Generic(GenericPopup::Message(MessagePopup::new("Error".into(), format!("You must choose a {} playlist (use enter)", name))))),
+        }
+    }
+
+    /// Asks before removing the selected songs or deleting the selected playlist of the active column.
+    pub fn handle_item_removing(&mut self) {
+        match self.active_view.block {
+            ColumnBlock::PlaylistSelector => {
+                let column = self.active_column();
+                let provider_id = column.id.clone();
+                let name = column.name.clone();
+                let Some(playlist) = column.playlist_selector.get_selected().first().map(|playlist| (*playlist).clone()) else {
+                    return;
+                };
+                if !playlist.owned || playlist.id == PlaylistIdWrapper::Liked {
+                    self.show_message("Error", "Missing permissions to delete playlist".into());
+                    return;
+                }
+                let message = format!("Delete {} of {} with {} songs? Undo brings it back, as a copy with a new id where the service can not restore it.", playlist.name, name, playlist.tracks);
+                let popup = ConfirmPopup::new("Delete playlist", message, ConfirmAction::DeletePlaylist(playlist));
+                self.popup = Some(PopupTyped::Platform(provider_id, PlatformPopup::Confirm(popup)));
+            },
+            ColumnBlock::SongSelector => {
+                let column = self.active_column();
+                let provider_id = column.id.clone();
+                let name = column.name.clone();
+                let selected: Vec<RSyncSong> = column.song_selector.get_selected().into_iter().cloned().collect();
+                if !selected.is_empty() {
+                    if let Some(playlist) = column.playlist_selector.get_selected().first() {
                         if playlist.owned {
-                            let song_ids = selected_songs.iter().map(|item| {
-                                match &item.r#type {
-                                    crate::types::music_types::RSyncSongProviderData::Youtube(data) => data.playlist_id.as_ref().unwrap().clone(),
-                                    crate::types::music_types::RSyncSongProviderData::Spotify => panic!("Spotify song in youtube playlist"),
-                                }
-                            }).collect::<Vec<String>>();
-                            
-                            self.youtube_column.provider.rem_playlist_song(playlist.id.clone(), song_ids).await;
-                            self.youtube_column.song_selector.clear_selected();
-                            self.youtube_column.refresh_songs();
+                            let message = format!("Remove {} songs from {} of {}?", selected.len(), playlist.name, name);
+                            let action = ConfirmAction::RemoveSongs { playlist_id: playlist.id.clone(), song_ids: selected.iter().map(|song| song.removal_id()).collect() };
+                            let popup = ConfirmPopup::new("Remove songs", message, action).with_items(&song_labels(&selected));
+                            self.popup = Some(PopupTyped::Platform(provider_id, PlatformPopup::Confirm(popup)));
                         }
                     }
                 }
@@ -303,50 +750,169 @@ impl App {
         }
     }
 
+    /// Reverts the most recent journaled mutation, the journal survives restarts.
+    pub fn undo_last_mutation(&mut self) {
+        if self.dry_run.is_enabled() {
+            self.show_message("Error", "Undo is not available in dry run, nothing is journaled".into());
+            return;
+        }
+        let Some(entry) = self.journal.pop() else {
+            self.show_message("Undo", "Nothing to undo".into());
+            return;
+        };
+        let Some(column) = self.column_mut(entry.provider_id()) else {
+            let message = format!("Provider {} is not available", entry.provider_id().0);
+            self.journal.record(entry);
+            self.show_message("Error", message);
+            return;
+        };
+        column.dispatch(Action::Undo(entry));
+    }
+
+    /// Links the selected playlist of the active column with the selected playlist of its neighbour for syncing.
+    pub fn link_selected_playlists(&mut self) {
+        if self.columns.len() < 2 {
+            return;
+        }
+        let first = self.active_view.column.min(self.columns.len() - 2);
+        let sides = [first, first + 1].map(|index| {
+            let column = &mut self.columns[index];
+            let name = column.name.clone();
+            let side = column.playlist_selector.get_selected().first().map(|playlist| (*playlist).clone()).map(|playlist| (SyncSide::new(column.id.clone(), playlist.id, playlist.name), playlist.owned));
+            (name, side)
+        });
+        let [(first_name, first_side), (second_name, second_side)] = sides;
+        let (first_side, second_side) = match (first_side, second_side) {
+            (Some(first_side), Some(second_side)) => (first_side, second_side),
+            _ => {
+                self.show_message("Error", format!("You must choose a {} and a {} playlist (use enter)", first_name, second_name));
+                return;
+            }
+        };
+        if !first_side.1 || !second_side.1 {
+            self.show_message("Error", "Missing permissions to modify playlist".into());
+            return;
+        }
+
+        let state_path = SyncState::default_path();
+        let mut state = SyncState::load(&state_path);
+        let message = format!("{} <-> {}\nPress [y] to sync", first_side.0.name, second_side.0.name);
+        state.link(SyncPair::new(first_side.0, second_side.0));
+        self.popup = Some(PopupTyped::Generic(GenericPopup::Message(match state.save(&state_path) {
+            Ok(()) => MessagePopup::new("Playlists linked".into(), message),
+            Err(err) => MessagePopup::new("Error".into(), format!("Failed to save sync state: {}", err)),
+        })));
+    }
+
+    /// Syncs every linked playlist pair in the background.
+    pub fn sync_linked_playlists(&mut self) {
+        if self.dry_run.is_enabled() {
+            self.show_message("Error", "Syncing is not available in dry run, it would store links of songs that were never added".into());
+            return;
+        }
+        let providers: Vec<(ProviderId, ColumnProvider<P>)> = self.columns.iter().map(|column| (column.id.clone(), column.provider.clone())).collect();
+        let Some((first_id, _)) = providers.first() else {
+            return;
+        };
+        let request_id = request::next_request_id();
+        let reporter = TransferReporter::new(self.global_event_sender.clone(), request_id, first_id.clone());
+
+        tokio::spawn(async move {
+            let state_path = SyncState::default_path();
+            match sync::sync_all(providers, &state_path, &reporter).await {
+                Ok(summary) => reporter.send(GlobalGenericEventData::SyncFinished(summary)),
+                Err(err) => reporter.send(GlobalGenericEventData::Error(err)),
+            }
+        });
+    }
+
     pub fn selective_refresh(&mut self) {
-        match self.active_view {
-            ActiveBlock::SpotifyPlaylistSelector => self.spotify_column.refresh_playlists(),
-            ActiveBlock::YoutubePlaylistSelector => self.youtube_column.refresh_playlists(),
-            ActiveBlock::SpotifySongSelector => self.spotify_column.refresh_songs(),
-            ActiveBlock::YoutubeSongSelector => self.youtube_column.refresh_songs(),
+        match self.active_view.block {
+            ColumnBlock::PlaylistSelector => self.active_column().refresh_playlists(),
+            ColumnBlock::SongSelector => self.active_column().refresh_songs(),
         }
     }
 
-    pub fn active_view_switch(&mut self) {
-        self.active_view = match self.active_view {
-            ActiveBlock::SpotifyPlaylistSelector => {
-                self.spotify_column.playlist_selector.active = false;
-                self.youtube_column.playlist_selector.active = true;
-                ActiveBlock::YoutubePlaylistSelector
-            },
-            ActiveBlock::YoutubePlaylistSelector => {
-                self.youtube_column.playlist_selector.active = false;
-                self.spotify_column.song_selector.active = true;
-                ActiveBlock::SpotifySongSelector
-            },
-            ActiveBlock::SpotifySongSelector => {
-                self.spotify_column.song_selector.active = false;
-                self.youtube_column.song_selector.active = true;
-                ActiveBlock::YoutubeSongSelector
-            },
-            ActiveBlock::YoutubeSongSelector => {
-                self.youtube_column.song_selector.active = false;   
-                self.spotify_column.playlist_selector.active = true;
-                ActiveBlock::SpotifyPlaylistSelector
-            },
+    fn set_block_active(&mut self, active_block: ActiveBlock, active: bool) {
+        let Some(column) = self.columns.get_mut(active_block.column) else {
+            return;
+        };
+        match active_block.block {
+            ColumnBlock::PlaylistSelector => column.playlist_selector.active = active,
+            ColumnBlock::SongSelector => column.song_selector.active = active,
         }
     }
 
+    /// Moves through the playlist selectors of all columns, then through their song selectors.
+    pub fn active_view_switch(&mut self) {
+        self.set_block_active(self.active_view, false);
+        let next_column = self.active_view.column + 1;
+        self.active_view = match (next_column < self.columns.len(), self.active_view.block) {
+            (true, block) => ActiveBlock { column: next_column, block },
+            (false, ColumnBlock::PlaylistSelector) => ActiveBlock { column: 0, block: ColumnBlock::SongSelector },
+            (false, ColumnBlock::SongSelector) => ActiveBlock { column: 0, block: ColumnBlock::PlaylistSelector },
+        };
+        self.set_block_active(self.active_view, true);
+    }
+
+    /// Quits on [esc] and [q] once neither a popup nor the active list used the key.
     pub fn check_close_key(&mut self, key_event: KeyEvent) {
-        match key_event.code {
-            KeyCode::Esc | KeyCode::Char('q') => self.quit(),
-            KeyCode::Char('c') | 
-            KeyCode::Char('C') => {
-                if key_event.modifiers == KeyModifiers::CONTROL {
-                    self.quit();
-                }
+        if let KeyCode::Esc | KeyCode::Char('q') = key_event.code {
+            self.quit();
+        }
+    }
+}
+
+/// Songs as `name (artists)` for confirmations.
+fn song_labels(songs: &[RSyncSong]) -> Vec<String> {
+    songs.iter().map(|song| format!("{} ({})", song.name, song.artists)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::providers::{journal::JournalEntry, mock_provider::MockProvider};
+
+    use super::*;
+
+    const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/mock_library.json");
+
+    /// Hands the data of the providers to the app until they stop sending.
+    async fn settle(app: &mut App<MockProvider>, receiver: &mut mpsc::UnboundedReceiver<Event>) {
+        while let Ok(Some(event)) = tokio::time::timeout(Duration::from_millis(200), receiver.recv()).await {
+            if let Event::DataReceived(request_id, data) = event {
+                app.handle_received_data(request_id, data);
             }
-            _ => {}
         }
     }
+
+    fn press(app: &mut App<MockProvider>, codes: &[KeyCode]) {
+        for code in codes {
+            app.handle_key_events(KeyEvent::from(*code));
+        }
+    }
+
+    #[tokio::test]
+    async fn removes_selected_song_after_confirmation() {
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let mock = MockProvider::from_fixture(FIXTURE).unwrap();
+        let registry = ProviderRegistry::new().register("mock", "Mock", mock.clone());
+        let journal = Journal::default();
+        let mut app = App::with_journal(registry, journal.clone(), sender);
+        settle(&mut app, &mut receiver).await;
+
+        // Favorites come first, then the playlists of the fixture
+        press(&mut app, &[KeyCode::Down, KeyCode::Down, KeyCode::Enter]);
+        settle(&mut app, &mut receiver).await;
+        press(&mut app, &[KeyCode::Tab, KeyCode::Down, KeyCode::Down, KeyCode::Enter, KeyCode::Delete]);
+        assert!(matches!(app.popup, Some(PopupTyped::Platform(_, PlatformPopup::Confirm(_)))));
+        press(&mut app, &[KeyCode::Char('y')]);
+        settle(&mut app, &mut receiver).await;
+
+        let road_trip = mock.snapshot().playlists.into_iter().find(|playlist| playlist.id == "road-trip").unwrap();
+        let song_ids: Vec<String> = road_trip.songs.into_iter().map(|song| song.id).collect();
+        assert_eq!(song_ids, ["song-1", "song-3"]);
+        assert!(matches!(journal.pop(), Some(JournalEntry::RemoveSongs { .. })));
+    }
 }
@@ -0,0 +1,232 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use tokio::sync::mpsc;
+
+use crate::{
+    app::AppResult,
+    playlist_file::{self, PlaylistFormat},
+    providers::{
+        dry_run::{self, DryRun, DryRunProvider},
+        provider_error::ProviderError,
+        provider_traits::APIProvider,
+        registry::{AnyProvider, ProviderId},
+    },
+    report::ReportFormat,
+    transfer::{self, TransferReporter},
+    types::music_types::{PlaylistIdWrapper, RSyncPlaylistItem},
+};
+
+const USAGE: &str = "Usage: project [--dry-run] [command]
+
+Without a command the terminal interface is started. With --dry-run the changes
+are only shown, in the terminal interface until it is turned off with [d].
+
+Commands:
+  list-playlists --provider <provider>
+  list-songs <provider>:<playlist>
+  export <provider>:<playlist> --format <m3u8|xspf|csv|json> [--output <path>]
+  transfer --from <provider>:<playlist> --to <provider>:<playlist> [--report <json|csv>]
+  help
+
+Providers are spotify, youtube and local. Playlists are given by id or name,
+`liked` stands for the liked songs.";
+
+/// Parsed command line, `--name value` options and positional arguments.
+///
+/// The global `--dry-run` flag is taken out by `main` before.
+#[derive(Debug, Default)]
+struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, String>,
+}
+
+impl Args {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut parsed = Args::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.strip_prefix("--") {
+                Some(option) => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| format!("Missing value of --{}", option))?;
+                    parsed.options.insert(option.to_string(), value.clone());
+                }
+                None => parsed.positional.push(arg.clone()),
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn option(&self, name: &str) -> Result<&str, String> {
+        self.options
+            .get(name)
+            .map(String::as_str)
+            .ok_or_else(|| format!("Missing --{}", name))
+    }
+
+    /// Playlist given as the first positional argument.
+    fn playlist(&self) -> Result<&str, String> {
+        self.positional
+            .get(1)
+            .map(String::as_str)
+            .ok_or_else(|| "Missing <provider>:<playlist>".to_string())
+    }
+}
+
+/// Splits `spotify:<playlist>` into the provider and the playlist part.
+fn playlist_reference(reference: &str) -> Result<(ProviderId, &str), String> {
+    match reference.split_once(':') {
+        Some((provider, playlist)) if !provider.is_empty() && !playlist.is_empty() => {
+            Ok((provider.into(), playlist))
+        }
+        _ => Err(format!(
+            "Invalid playlist {}, expected <provider>:<playlist>",
+            reference
+        )),
+    }
+}
+
+/// Finds a playlist of the provider by its id or name.
+async fn find_playlist<P: APIProvider>(
+    provider: &mut P,
+    playlist: &str,
+) -> Result<RSyncPlaylistItem, ProviderError> {
+    let playlists = provider.get_playlists().await?;
+    let wanted = match playlist {
+        "liked" => PlaylistIdWrapper::Liked,
+        id => PlaylistIdWrapper::Id(id.to_string()),
+    };
+    playlists
+        .iter()
+        .find(|item| item.id == wanted)
+        .or_else(|| playlists.iter().find(|item| item.name == playlist))
+        .cloned()
+        .ok_or_else(|| ProviderError::NotFound(format!("Playlist {}", playlist)))
+}
+
+/// Connects the provider of a `<provider>:<playlist>` reference and looks the playlist up.
+async fn connect_playlist(
+    reference: &str,
+) -> AppResult<(ProviderId, AnyProvider, RSyncPlaylistItem)> {
+    let (provider_id, playlist) = playlist_reference(reference)?;
+    let mut provider = AnyProvider::connect(&provider_id).await?;
+    let playlist = find_playlist(&mut provider, playlist).await?;
+    Ok((provider_id, provider, playlist))
+}
+
+fn playlist_id_label(id: &PlaylistIdWrapper) -> &str {
+    match id {
+        PlaylistIdWrapper::Id(id) => id,
+        PlaylistIdWrapper::Liked => "liked",
+    }
+}
+
+/// Runs a command without the terminal interface, the output goes to stdout.
+///
+/// With `dry_run` the writes of the command are only printed.
+pub async fn run(args: &[String], dry_run: bool) -> AppResult<()> {
+    let args = Args::parse(args)?;
+    match args.positional.first().map(String::as_str) {
+        Some("list-playlists") => {
+            let mut provider = AnyProvider::connect(&args.option("provider")?.into()).await?;
+            for playlist in provider.get_playlists().await? {
+                println!(
+                    "{}\t{}\t{}",
+                    playlist_id_label(&playlist.id),
+                    playlist.name,
+                    playlist.tracks
+                );
+            }
+        }
+        Some("list-songs") => {
+            let (_, mut provider, playlist) = connect_playlist(args.playlist()?).await?;
+            for song in provider.get_playlist_songs(playlist.id).await? {
+                println!("{}\t{}\t{}", song.id, song.name, song.artists);
+            }
+        }
+        Some("export") => {
+            let (_, mut provider, playlist) = connect_playlist(args.playlist()?).await?;
+            let path = match args.options.get("output") {
+                Some(path) => PathBuf::from(path),
+                None => {
+                    let format = match args.option("format")? {
+                        "m3u" | "m3u8" => PlaylistFormat::M3u8,
+                        "xspf" => PlaylistFormat::Xspf,
+                        "csv" => PlaylistFormat::Csv,
+                        "json" => PlaylistFormat::Json,
+                        format => return Err(format!("Unknown format {}", format).into()),
+                    };
+                    playlist_file::default_path(&playlist.name, format)
+                }
+            };
+            let songs = provider.get_playlist_songs(playlist.id).await?;
+            playlist_file::export(&playlist.name, &songs, &path)?;
+            println!("{} songs written to {}", songs.len(), path.display());
+        }
+        Some("transfer") => transfer(&args, dry_run).await?,
+        Some("help") | None => println!("{}", USAGE),
+        Some(command) => return Err(format!("Unknown command {}\n\n{}", command, USAGE).into()),
+    }
+    Ok(())
+}
+
+/// Transfers the songs of one playlist into another one.
+///
+/// Low confidence matches are never added, there is nobody to review them. With `--dry-run`
+/// the writes are only printed and no report is exported.
+async fn transfer(args: &Args, dry_run: bool) -> AppResult<()> {
+    let (_, mut source, source_playlist) = connect_playlist(args.option("from")?).await?;
+    let (target_id, target_playlist) = playlist_reference(args.option("to")?)?;
+    let dry_run = DryRun::new(dry_run);
+    let mut target = DryRunProvider::new(
+        AnyProvider::connect(&target_id).await?,
+        &target_id.0,
+        dry_run.clone(),
+    );
+    let target_playlist = find_playlist(&mut target, target_playlist).await?;
+    let report_format = match args.options.get("report").map(String::as_str) {
+        Some("json") => Some(ReportFormat::Json),
+        Some("csv") => Some(ReportFormat::Csv),
+        Some(format) => return Err(format!("Unknown report format {}", format).into()),
+        None => None,
+    };
+
+    // progress updates are meant for the app, nobody listens to them here
+    let (event_sender, _) = mpsc::unbounded_channel();
+    let reporter = TransferReporter::new(event_sender, 0, target_id);
+
+    let songs = source
+        .get_playlist_songs(source_playlist.id.clone())
+        .await?;
+    println!(
+        "{} songs from {} -> {}",
+        songs.len(),
+        source_playlist.name,
+        target_playlist.name
+    );
+    let plan = transfer::plan_songs(&mut target, target_playlist.id, songs, &reporter).await?;
+
+    let report = transfer::commit_plan(&mut target, plan, &reporter).await?;
+    if dry_run.is_enabled() {
+        for line in dry_run::diff_lines(&dry_run.take()) {
+            println!("{}", line);
+        }
+        if let Some(quota) = target.quota() {
+            println!(
+                "Searches still counted against the quota, {} units are left today",
+                quota.summary()
+            );
+        }
+        println!("Dry run, nothing was changed");
+        return Ok(());
+    }
+    for line in report.lines() {
+        println!("{}", line);
+    }
+    println!("{}", report.summary());
+    if let Some(format) = report_format {
+        println!("Report written to {}", report.export(format)?.display());
+    }
+    Ok(())
+}
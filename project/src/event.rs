@@ -5,39 +5,92 @@ use futures::{FutureExt, StreamExt};
 use tokio::sync::mpsc;
 
 use crate::{
+    action::ActionResult,
     app::AppResult,
+    providers::{paging::PageStream, provider_error::ProviderError, registry::ProviderId},
+    report::TransferReport,
+    sync::SyncSummary,
+    transfer::TransferPlan,
     types::music_types::{RSyncPlaylistItem, RSyncSong},
 };
 
 #[derive(Clone, Debug)]
 pub enum GlobalEventDataFullfilness<T> {
+    /// Next page of the listing, appended to the pages before.
     Partial(T),
-    Full(T),
+    /// Every page was sent.
+    Complete,
 }
 
 #[derive(Clone, Debug)]
 pub enum TransferUpdateEventData {
     Searching,
+    /// `done` of `total` songs of the current stage are searched or inserted.
+    Progress {
+        done: usize,
+        total: usize,
+    },
+    /// Some matches are uncertain, the user picks what gets added.
+    Review(TransferPlan),
     Updating,
-    Finished,
+    Finished(TransferReport),
 }
 
 #[derive(Clone, Debug)]
 pub enum GlobalGenericEventData {
-    TransferUpdate(TransferUpdateEventData),
+    /// Progress of a transfer into the column of the provider.
+    TransferUpdate(ProviderId, TransferUpdateEventData),
+    SyncFinished(SyncSummary),
+    /// Background action of the column of the provider finished.
+    ActionFinished(ProviderId, ActionResult),
+    Error(ProviderError),
 }
 
 #[derive(Clone, Debug)]
 pub enum GlobalEventData {
     Playlists(GlobalEventDataFullfilness<Vec<RSyncPlaylistItem>>),
     Songs(GlobalEventDataFullfilness<Vec<RSyncSong>>),
+    Error(ProviderError),
 }
 
 #[derive(Clone, Debug)]
 pub enum GlobalEvent {
     Generic(GlobalGenericEventData),
-    Spotify(GlobalEventData),
-    Youtube(GlobalEventData),
+    /// Data for the column of the provider.
+    Provider(ProviderId, GlobalEventData),
+}
+
+/// Sends the pages of a listing to the column of the provider that asked for them.
+#[derive(Clone, Debug)]
+pub struct PartialDataSender {
+    pub sender: mpsc::UnboundedSender<Event>,
+    pub request_id: u128,
+    pub provider_id: ProviderId,
+}
+
+impl PartialDataSender {
+    pub fn send(&self, data: GlobalEventData) {
+        let _ = self.sender.send(Event::DataReceived(
+            self.request_id,
+            GlobalEvent::Provider(self.provider_id.clone(), data),
+        ));
+    }
+
+    /// Sends every page as it arrives, then [`GlobalEventDataFullfilness::Complete`].
+    /// An error ends the listing.
+    pub async fn forward_pages<T>(
+        &self,
+        mut pages: PageStream<'_, T>,
+        data: fn(GlobalEventDataFullfilness<Vec<T>>) -> GlobalEventData,
+    ) {
+        while let Some(page) = pages.next().await {
+            match page {
+                Ok(items) => self.send(data(GlobalEventDataFullfilness::Partial(items))),
+                Err(err) => return self.send(GlobalEventData::Error(err)),
+            }
+        }
+        self.send(data(GlobalEventDataFullfilness::Complete));
+    }
 }
 
 /// Terminal events.
@@ -0,0 +1,474 @@
+use std::collections::HashSet;
+
+use chrono::NaiveDate;
+use tokio::{sync::mpsc, task::JoinHandle};
+
+use crate::{
+    event::{Event, TransferUpdateEventData},
+    providers::{
+        provider_error::{ProviderError, ProviderResult},
+        provider_traits::APIProvider,
+        quota::{self, QuotaCheck},
+        registry::ProviderId,
+    },
+    report::TransferReport,
+    request,
+    transfer::{self, TransferPlan, TransferReporter},
+    types::music_types::{PlaylistIdWrapper, RSyncPlaylistItem, RSyncSong},
+};
+
+pub type JobId = u64;
+
+/// What a job transfers, kept so a failed or cancelled job can be retried.
+#[derive(Clone, Debug)]
+pub enum JobSpec {
+    AddSongs {
+        playlist_id: PlaylistIdWrapper,
+        songs: Vec<RSyncSong>,
+    },
+    /// Copies `playlist` of the `source` provider.
+    MirrorPlaylist {
+        source: ProviderId,
+        playlist: RSyncPlaylistItem,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub enum JobState {
+    /// Waits until no other job transfers into the same provider.
+    Queued,
+    /// Waits for the daily reset of the quota of the target, the estimated `cost` units did
+    /// not fit into the quota of `day`.
+    Deferred {
+        cost: u32,
+        day: NaiveDate,
+    },
+    /// `total` is 0 until the songs to search are known.
+    Searching {
+        done: usize,
+        total: usize,
+    },
+    Inserting {
+        done: usize,
+        total: usize,
+    },
+    /// Uncertain matches wait for the user, committing the plan queues the job again.
+    Review(TransferPlan),
+    Finished(TransferReport),
+    Failed(ProviderError),
+    /// Songs inserted before the cancellation stay in the playlist.
+    Cancelled,
+}
+
+/// Transfer into the playlist of one provider, listed in the jobs panel.
+#[derive(Debug)]
+pub struct Job {
+    pub id: JobId,
+    /// Provider the songs are transferred into.
+    pub target: ProviderId,
+    pub title: String,
+    pub spec: JobSpec,
+    pub state: JobState,
+    /// Reviewed plan, the next run only inserts its songs.
+    plan: Option<TransferPlan>,
+    /// Request id of the current run, updates of earlier runs are ignored.
+    request_id: u128,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Job {
+    pub fn is_running(&self) -> bool {
+        matches!(
+            self.state,
+            JobState::Searching { .. } | JobState::Inserting { .. }
+        )
+    }
+
+    /// Finished, failed and cancelled jobs can be removed from the list.
+    pub fn is_done(&self) -> bool {
+        matches!(
+            self.state,
+            JobState::Finished(_) | JobState::Failed(_) | JobState::Cancelled
+        )
+    }
+
+    /// Quota units the next run is estimated to cost, see [`quota::transfer_cost`].
+    pub fn estimated_cost(&self) -> u32 {
+        match (&self.plan, &self.spec) {
+            (Some(plan), _) => quota::insert_cost(plan.songs.len()),
+            (None, JobSpec::AddSongs { songs, .. }) => quota::transfer_cost(songs.len()),
+            // the copy of the playlist is created first
+            (None, JobSpec::MirrorPlaylist { playlist, .. }) => {
+                quota::WRITE_COST + quota::transfer_cost(playlist.tracks as usize)
+            }
+        }
+    }
+
+    pub fn status(&self) -> String {
+        let progress = |done: usize, total: usize| match total {
+            0 => String::new(),
+            _ => format!(" {}/{}", done, total),
+        };
+        match &self.state {
+            JobState::Queued => "queued".into(),
+            JobState::Deferred { cost, .. } => {
+                format!("waits for the quota reset, needs {} units", cost)
+            }
+            JobState::Searching { done, total } => {
+                format!("searching{}", progress(*done, *total))
+            }
+            JobState::Inserting { done, total } => {
+                format!("inserting{}", progress(*done, *total))
+            }
+            JobState::Review(_) => "needs review [enter]".into(),
+            JobState::Finished(report) => report.summary(),
+            JobState::Failed(error) => format!("failed: {}", error.title()),
+            JobState::Cancelled => "cancelled".into(),
+        }
+    }
+}
+
+/// Queue of the transfers, runs one job per target provider at a time.
+#[derive(Debug, Default)]
+pub struct JobManager {
+    jobs: Vec<Job>,
+    next_id: JobId,
+}
+
+impl JobManager {
+    pub fn jobs(&self) -> &[Job] {
+        &self.jobs
+    }
+
+    pub fn get(&self, id: JobId) -> Option<&Job> {
+        self.jobs.iter().find(|job| job.id == id)
+    }
+
+    fn get_mut(&mut self, id: JobId) -> Option<&mut Job> {
+        self.jobs.iter_mut().find(|job| job.id == id)
+    }
+
+    fn by_request(&mut self, request_id: u128) -> Option<&mut Job> {
+        self.jobs
+            .iter_mut()
+            .find(|job| job.request_id == request_id && job.is_running())
+    }
+
+    /// Whether the request is a run of a job, ended runs included.
+    pub fn has_request(&self, request_id: u128) -> bool {
+        self.jobs.iter().any(|job| job.request_id == request_id)
+    }
+
+    pub fn enqueue(&mut self, target: ProviderId, title: String, spec: JobSpec) -> JobId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.jobs.push(Job {
+            id,
+            target,
+            title,
+            spec,
+            state: JobState::Queued,
+            plan: None,
+            request_id: 0,
+            handle: None,
+        });
+        id
+    }
+
+    /// Queued jobs that can start now, in the order they were queued.
+    ///
+    /// Deferred jobs are checked again once the quota was reset, until then they let the
+    /// later jobs of their target start.
+    pub fn startable(&self) -> Vec<JobId> {
+        let mut busy: HashSet<&ProviderId> = self
+            .jobs
+            .iter()
+            .filter(|job| job.is_running())
+            .map(|job| &job.target)
+            .collect();
+        let today = quota::quota_day();
+        self.jobs
+            .iter()
+            .filter(|job| {
+                let waiting = match job.state {
+                    JobState::Queued => true,
+                    JobState::Deferred { day, .. } => day != today,
+                    _ => false,
+                };
+                waiting && busy.insert(&job.target)
+            })
+            .map(|job| job.id)
+            .collect()
+    }
+
+    /// Whether a deferred job waits for a quota that was reset since.
+    pub fn has_due_deferred(&self) -> bool {
+        let today = quota::quota_day();
+        self.jobs
+            .iter()
+            .any(|job| matches!(job.state, JobState::Deferred { day, .. } if day != today))
+    }
+
+    /// Starts every job that can start, `provider` returns the provider of an id.
+    ///
+    /// A job the quota defers or fails leaves its target idle, so the next waiting job of
+    /// the target is tried in its place.
+    pub fn start_queued<P: APIProvider>(
+        &mut self,
+        provider: impl Fn(&ProviderId) -> Option<P>,
+        sender: &mpsc::UnboundedSender<Event>,
+    ) {
+        loop {
+            let mut skipped = false;
+            for id in self.startable() {
+                let Some(job) = self.get(id) else {
+                    continue;
+                };
+                let source = match &job.spec {
+                    JobSpec::MirrorPlaylist { source, .. } => provider(source),
+                    JobSpec::AddSongs { .. } => None,
+                };
+                let Some(target) = provider(&job.target) else {
+                    continue;
+                };
+                skipped |= !self.start(id, target, source, sender.clone());
+            }
+            if !skipped {
+                return;
+            }
+        }
+    }
+
+    /// Runs the job on a tokio task, `source` is the provider a mirrored playlist is read from.
+    ///
+    /// A job the quota of the target can not cover is deferred, or fails if it costs more
+    /// than the whole daily quota. Returns whether the job runs.
+    pub fn start<P: APIProvider>(
+        &mut self,
+        id: JobId,
+        mut target: P,
+        source: Option<P>,
+        sender: mpsc::UnboundedSender<Event>,
+    ) -> bool {
+        let request_id = request::next_request_id();
+        let Some(job) = self.get_mut(id) else {
+            return false;
+        };
+        if let Some(quota) = target.quota() {
+            let cost = job.estimated_cost();
+            match quota.check(cost) {
+                QuotaCheck::Fits => (),
+                QuotaCheck::Later => {
+                    job.state = JobState::Deferred {
+                        cost,
+                        day: quota::quota_day(),
+                    };
+                    return false;
+                }
+                QuotaCheck::Never => {
+                    job.state = JobState::Failed(ProviderError::RateLimited(format!(
+                        "The transfer needs about {} quota units, the daily quota is {}",
+                        cost,
+                        quota.limit()
+                    )));
+                    return false;
+                }
+            }
+        }
+        let reporter = TransferReporter::new(sender, request_id, job.target.clone());
+        let plan = job.plan.take();
+        let spec = job.spec.clone();
+        job.request_id = request_id;
+        job.state = match plan {
+            Some(_) => JobState::Inserting { done: 0, total: 0 },
+            None => JobState::Searching { done: 0, total: 0 },
+        };
+
+        job.handle = Some(tokio::spawn(async move {
+            let result = match (plan, spec) {
+                (Some(plan), _) => transfer::commit_plan(&mut target, plan, &reporter)
+                    .await
+                    .map(Some),
+                (None, JobSpec::AddSongs { playlist_id, songs }) => {
+                    transfer::add_songs(&mut target, playlist_id, songs, &reporter).await
+                }
+                (None, JobSpec::MirrorPlaylist { playlist, .. }) => match source {
+                    Some(mut source) => {
+                        transfer::mirror_playlist(&mut source, &mut target, playlist, &reporter)
+                            .await
+                    }
+                    None => Err(ProviderError::NotFound(
+                        "Provider of the playlist is not available".into(),
+                    )),
+                },
+            };
+            reporter.finish(result);
+        }));
+        true
+    }
+
+    /// Applies an update of a running job, returns `None` if the request belongs to no job.
+    pub fn update(&mut self, request_id: u128, update: TransferUpdateEventData) -> Option<&Job> {
+        let job = self.by_request(request_id)?;
+        match update {
+            TransferUpdateEventData::Searching => {
+                job.state = JobState::Searching { done: 0, total: 0 }
+            }
+            TransferUpdateEventData::Updating => {
+                job.state = JobState::Inserting { done: 0, total: 0 }
+            }
+            TransferUpdateEventData::Progress {
+                done: progress,
+                total: count,
+            } => match &mut job.state {
+                JobState::Searching { done, total } | JobState::Inserting { done, total } => {
+                    *done = progress;
+                    *total = count;
+                }
+                _ => (),
+            },
+            TransferUpdateEventData::Review(plan) => job.state = JobState::Review(plan),
+            TransferUpdateEventData::Finished(report) => job.state = JobState::Finished(report),
+        }
+        Some(job)
+    }
+
+    /// Marks the running job of the request as failed, returns `false` if there is none.
+    pub fn fail(&mut self, request_id: u128, error: ProviderError) -> bool {
+        match self.by_request(request_id) {
+            Some(job) => {
+                job.state = JobState::Failed(error);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Queues the reviewed plan of a job, only its chosen songs are inserted.
+    pub fn commit_review(&mut self, id: JobId, plan: TransferPlan) {
+        if let Some(job) = self.get_mut(id) {
+            if let JobState::Review(_) = job.state {
+                job.plan = Some(plan);
+                job.state = JobState::Queued;
+            }
+        }
+    }
+
+    /// Aborts a queued, running or reviewed job.
+    pub fn cancel(&mut self, id: JobId) -> ProviderResult<()> {
+        let job = self
+            .get_mut(id)
+            .ok_or_else(|| ProviderError::NotFound("The job does not exist anymore".into()))?;
+        if job.is_done() {
+            return Err(ProviderError::Other("The job already ended".into()));
+        }
+        if let Some(handle) = job.handle.take() {
+            handle.abort();
+        }
+        job.plan = None;
+        job.state = JobState::Cancelled;
+        Ok(())
+    }
+
+    /// Queues a failed or cancelled job again, it searches the songs from scratch.
+    pub fn retry(&mut self, id: JobId) -> ProviderResult<()> {
+        let job = self
+            .get_mut(id)
+            .ok_or_else(|| ProviderError::NotFound("The job does not exist anymore".into()))?;
+        match job.state {
+            JobState::Failed(_) | JobState::Cancelled => {
+                job.state = JobState::Queued;
+                Ok(())
+            }
+            _ => Err(ProviderError::Other(
+                "Only failed or cancelled jobs can be retried".into(),
+            )),
+        }
+    }
+
+    /// Removes an ended job from the list.
+    pub fn remove(&mut self, id: JobId) {
+        self.jobs.retain(|job| job.id != id || !job.is_done());
+    }
+
+    /// Counts of the jobs that did not end yet, `None` if there are none.
+    pub fn summary(&self) -> Option<String> {
+        let count =
+            |state: fn(&JobState) -> bool| self.jobs.iter().filter(|job| state(&job.state)).count();
+        let counts = [
+            (
+                count(|state| {
+                    matches!(
+                        state,
+                        JobState::Searching { .. } | JobState::Inserting { .. }
+                    )
+                }),
+                "running",
+            ),
+            (count(|state| matches!(state, JobState::Queued)), "queued"),
+            (
+                count(|state| matches!(state, JobState::Deferred { .. })),
+                "waiting for quota",
+            ),
+            (
+                count(|state| matches!(state, JobState::Review(_))),
+                "to review",
+            ),
+        ];
+        let parts: Vec<String> = counts
+            .iter()
+            .filter(|(count, _)| *count > 0)
+            .map(|(count, label)| format!("{} {}", count, label))
+            .collect();
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        providers::{mock_provider::MockProvider, quota::Quota},
+        types::music_types::RSyncSongProviderData,
+    };
+
+    const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/mock_library.json");
+
+    fn add_songs(count: usize) -> JobSpec {
+        let song = RSyncSong {
+            artists: "Queen".into(),
+            url: String::new(),
+            id: "song-6".into(),
+            name: "Don't Stop Me Now".into(),
+            metadata: Default::default(),
+            r#type: RSyncSongProviderData::Local,
+        };
+        JobSpec::AddSongs {
+            playlist_id: PlaylistIdWrapper::Id("evening".into()),
+            songs: vec![song; count],
+        }
+    }
+
+    #[tokio::test]
+    async fn deferred_job_lets_the_next_job_of_its_target_start() {
+        let quota = Quota::new(10_000);
+        quota.charge(5_000).unwrap();
+        let provider = MockProvider::from_fixture(FIXTURE)
+            .unwrap()
+            .with_quota(quota);
+        let target = ProviderId::from("mock");
+        let (sender, _receiver) = mpsc::unbounded_channel();
+
+        let mut jobs = JobManager::default();
+        let large = jobs.enqueue(target.clone(), "large".into(), add_songs(50));
+        let small = jobs.enqueue(target.clone(), "small".into(), add_songs(1));
+        jobs.start_queued(|id| (id == &target).then(|| provider.clone()), &sender);
+
+        assert!(matches!(
+            jobs.get(large).unwrap().state,
+            JobState::Deferred { .. }
+        ));
+        assert!(jobs.get(small).unwrap().is_running());
+        assert!(jobs.startable().is_empty());
+    }
+}
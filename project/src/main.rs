@@ -1,4 +1,4 @@
-use std::{fs::File, io};
+use std::{env, fs::File, io};
 
 use color_eyre::{eyre::Context, Result};
 use dotenv::dotenv;
@@ -8,14 +8,27 @@ use tracing::info;
 use crate::{
     app::{App, AppResult},
     event::{Event, EventHandler},
+    providers::{
+        mock_provider::MockProvider, provider_traits::APIProvider, registry::ProviderRegistry,
+    },
     tui::Tui,
 };
 
 use tracing_appender::{non_blocking, non_blocking::WorkerGuard};
 
+pub mod action;
 pub mod app;
+pub mod cli;
 pub mod event;
+pub mod jobs;
+pub mod matching;
+pub mod playlist_file;
 pub mod providers;
+pub mod reorder;
+pub mod report;
+pub mod request;
+pub mod sync;
+pub mod transfer;
 pub mod tui;
 pub mod types;
 pub mod ui;
@@ -26,6 +39,21 @@ async fn main() -> AppResult<()> {
     dotenv().ok();
     let _guard = init_tracing()?;
     info!("started");
+
+    // `--dry-run` is global, it applies to the commands and the terminal interface alike
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let dry_run = args.iter().any(|arg| arg == "--dry-run");
+    args.retain(|arg| arg != "--dry-run");
+
+    // Headless mode, a command runs without touching the terminal.
+    if !args.is_empty() {
+        if let Err(err) = cli::run(&args, dry_run).await {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     // Create an application.
 
     // Initialize the terminal user interface.
@@ -33,8 +61,31 @@ async fn main() -> AppResult<()> {
     let terminal = Terminal::new(backend)?;
     let events = EventHandler::new(250);
 
-    let mut app = App::new((&events).get_sender()).await;
-    let mut tui = Tui::new(terminal, events);
+    let sender = events.get_sender();
+    let tui = Tui::new(terminal, events);
+
+    // Offline mode, both columns are served from the fixture instead of the live services.
+    if let Some(fixture) = env::var("RSYNC_MOCK_FIXTURE")
+        .ok()
+        .filter(|f| !f.is_empty())
+    {
+        let registry = ProviderRegistry::new()
+            .register("spotify", "Spotify", MockProvider::from_fixture(&fixture)?)
+            .register("youtube", "Youtube", MockProvider::from_fixture(&fixture)?);
+        let app = App::with_registry(registry, sender);
+        app.dry_run.set(dry_run);
+        return run(app, tui).await;
+    }
+
+    let app = App::new(sender).await?;
+    app.dry_run.set(dry_run);
+    run(app, tui).await
+}
+
+async fn run<P: APIProvider>(
+    mut app: App<P>,
+    mut tui: Tui<CrosstermBackend<io::Stdout>>,
+) -> AppResult<()> {
     tui.init()?;
 
     // Start the main loop.
@@ -44,7 +95,7 @@ async fn main() -> AppResult<()> {
         // Handle events.
         match tui.events.next().await? {
             Event::Tick => app.tick(),
-            Event::Key(key_event) => app.handle_key_events(key_event).await,
+            Event::Key(key_event) => app.handle_key_events(key_event),
             Event::Mouse(_) => {}
             Event::Resize(_, _) => {}
             Event::DataReceived(request_id, data) => app.handle_received_data(request_id, data),
@@ -53,6 +104,7 @@ async fn main() -> AppResult<()> {
 
     // Exit the user interface.
     tui.exit()?;
+    app.journal.flush();
     Ok(())
 }
 
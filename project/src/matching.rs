@@ -0,0 +1,254 @@
+use std::collections::HashSet;
+
+use crate::types::music_types::RSyncSong;
+
+/// Matches below this confidence are not added without the user checking them.
+pub const MIN_CONFIDENCE: f32 = 0.7;
+
+/// How many search results are scored for every song.
+pub const CANDIDATE_LIMIT: u32 = 5;
+
+/// Words inside brackets that mark the bracket as noise, e.g. "(Official Video)".
+const NOISE_WORDS: [&str; 14] = [
+    "official",
+    "video",
+    "audio",
+    "lyric",
+    "lyrics",
+    "visualizer",
+    "visualiser",
+    "remaster",
+    "remastered",
+    "feat",
+    "ft",
+    "hd",
+    "hq",
+    "mv",
+];
+
+/// Result of searching one song in another provider.
+#[derive(Clone, Debug)]
+pub struct SongMatch {
+    /// The song that was searched for.
+    pub source: RSyncSong,
+    /// Best scoring search result, `None` when nothing was found.
+    pub found: Option<RSyncSong>,
+    /// Score of `found` between 0 and 1.
+    pub confidence: f32,
+    /// Every search result, best scoring first, for picking an alternative by hand.
+    pub candidates: Vec<RSyncSong>,
+}
+
+impl SongMatch {
+    pub fn is_confident(&self) -> bool {
+        self.found.is_some() && self.confidence >= MIN_CONFIDENCE
+    }
+
+    /// Found song, but only when it is good enough to be added without asking.
+    pub fn confident_song(&self) -> Option<&RSyncSong> {
+        self.found.as_ref().filter(|_| self.is_confident())
+    }
+}
+
+/// Query used to search `song` in another provider.
+pub fn search_query(song: &RSyncSong) -> String {
+    format!(
+        "{} {}",
+        normalise_title(&song.name),
+        normalise(song.primary_artist())
+    )
+}
+
+/// Lowercase words of `text` without punctuation.
+pub fn normalise(text: &str) -> String {
+    words(&text.to_lowercase()).join(" ")
+}
+
+fn words(text: &str) -> Vec<&str> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+fn is_noise(segment: &str) -> bool {
+    words(segment).iter().any(|word| NOISE_WORDS.contains(word))
+}
+
+/// Normalises a song title, bracketed noise like "(Official Video)" or "[Remastered 2011]",
+/// "- Topic" suffixes, feat. credits and "- Remastered" tails are removed.
+/// Meaningful brackets such as "(Live)" or "(Acoustic)" are kept.
+pub fn normalise_title(title: &str) -> String {
+    let lower = title.to_lowercase();
+
+    let mut cleaned = String::with_capacity(lower.len());
+    let mut rest = lower.as_str();
+    while let Some(start) = rest.find(['(', '[']) {
+        let close = if rest[start..].starts_with('(') {
+            ')'
+        } else {
+            ']'
+        };
+        let Some(len) = rest[start..].find(close) else {
+            break;
+        };
+        cleaned.push_str(&rest[..start]);
+        let segment = &rest[start + 1..start + len];
+        if !is_noise(segment) {
+            cleaned.push(' ');
+            cleaned.push_str(segment);
+        }
+        rest = &rest[start + len + 1..];
+    }
+    cleaned.push_str(rest);
+
+    let mut cleaned = cleaned.as_str();
+    for separator in [" feat. ", " feat ", " ft. ", " ft "] {
+        if let Some(index) = cleaned.find(separator) {
+            cleaned = &cleaned[..index];
+        }
+    }
+    cleaned = cleaned.strip_suffix(" - topic").unwrap_or(cleaned);
+    if let Some(index) = cleaned.rfind(" - ") {
+        if is_noise(&cleaned[index..]) {
+            cleaned = &cleaned[..index];
+        }
+    }
+
+    normalise(cleaned)
+}
+
+/// Dice coefficient of the word sets, 1 for the same words in any order.
+pub fn similarity(a: &str, b: &str) -> f32 {
+    let a: HashSet<&str> = words(a).into_iter().collect();
+    let b: HashSet<&str> = words(b).into_iter().collect();
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    2.0 * a.intersection(&b).count() as f32 / (a.len() + b.len()) as f32
+}
+
+/// 1 for durations within 3 seconds, falling to 0 at 30 seconds apart.
+fn duration_similarity(a: u32, b: u32) -> f32 {
+    let difference = a.abs_diff(b) as f32 / 1000.0;
+    (1.0 - (difference - 3.0).max(0.0) / 27.0).max(0.0)
+}
+
+/// Scores how likely `candidate` is the same recording as `source`.
+///
+/// Video titles often look like "Artist - Title", so the part after the dash is scored as well
+/// and the part before it counts as an artist.
+pub fn score(source: &RSyncSong, candidate: &RSyncSong) -> f32 {
+    if source.metadata.isrc.is_some() && source.metadata.isrc == candidate.metadata.isrc {
+        return 1.0;
+    }
+    let source_title = normalise_title(&source.name);
+    let source_artists = normalise(&source.artists);
+    let candidate_title = normalise_title(&candidate.name);
+    let candidate_artists = normalise(&candidate.artists.replace(" - Topic", ""));
+
+    let mut title_score = similarity(&source_title, &candidate_title);
+    let mut artist_score = similarity(&source_artists, &candidate_artists);
+    if let Some((artist_part, title_part)) = candidate.name.split_once(" - ") {
+        title_score = title_score.max(similarity(&source_title, &normalise_title(title_part)));
+        artist_score = artist_score.max(similarity(&source_artists, &normalise(artist_part)));
+    }
+
+    match (source.metadata.duration_ms, candidate.metadata.duration_ms) {
+        (Some(a), Some(b)) => {
+            0.6 * title_score + 0.25 * artist_score + 0.15 * duration_similarity(a, b)
+        }
+        _ => 0.65 * title_score + 0.35 * artist_score,
+    }
+}
+
+/// Picks the best scoring candidate for `source`.
+pub fn best_match(source: RSyncSong, candidates: Vec<RSyncSong>) -> SongMatch {
+    let mut scored: Vec<(f32, RSyncSong)> = candidates
+        .into_iter()
+        .map(|candidate| (score(&source, &candidate), candidate))
+        .collect();
+    scored.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+    let confidence = scored.first().map_or(0.0, |(confidence, _)| *confidence);
+    let candidates: Vec<RSyncSong> = scored.into_iter().map(|(_, candidate)| candidate).collect();
+    SongMatch {
+        source,
+        found: candidates.first().cloned(),
+        confidence,
+        candidates,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::music_types::{RSyncSongMetadata, RSyncSongProviderData};
+
+    fn song(name: &str, artists: &str, duration_ms: Option<u32>) -> RSyncSong {
+        RSyncSong {
+            artists: artists.to_string(),
+            url: String::new(),
+            id: name.to_string(),
+            name: name.to_string(),
+            metadata: RSyncSongMetadata {
+                duration_ms,
+                ..RSyncSongMetadata::default()
+            },
+            r#type: RSyncSongProviderData::Local,
+        }
+    }
+
+    #[test]
+    fn studio_recording_beats_live_version() {
+        let source = song("Yellow", "Coldplay", None);
+        let live = song("Yellow (Live at Glastonbury)", "Coldplay", None);
+        let video = song("Coldplay - Yellow (Official Video)", "Coldplay", None);
+
+        assert_eq!(normalise_title(&live.name), "yellow live at glastonbury");
+        assert!(score(&source, &live) < MIN_CONFIDENCE);
+
+        let found = best_match(source, vec![live, video]);
+        assert!(found.is_confident());
+        assert_eq!(
+            found.found.unwrap().id,
+            "Coldplay - Yellow (Official Video)"
+        );
+    }
+
+    #[test]
+    fn remasters_match_the_original() {
+        assert_eq!(normalise_title("Heroes - 2017 Remaster"), "heroes");
+        assert_eq!(normalise_title("Heroes [Remastered 2011]"), "heroes");
+
+        let source = song("Heroes", "David Bowie", Some(371_000));
+        let remaster = song("Heroes - 2017 Remaster", "David Bowie", Some(372_000));
+        assert_eq!(score(&source, &remaster), 1.0);
+    }
+
+    #[test]
+    fn featured_artists_are_ignored_in_titles() {
+        assert_eq!(normalise_title("Stay (feat. Justin Bieber)"), "stay");
+        assert_eq!(normalise_title("Stay feat. Justin Bieber"), "stay");
+        assert_eq!(normalise_title("Stay ft. Justin Bieber"), "stay");
+
+        let source = song("Stay", "The Kid LAROI, Justin Bieber", None);
+        let candidate = song("Stay (feat. Justin Bieber)", "The Kid LAROI", None);
+        assert!(best_match(source, vec![candidate]).is_confident());
+    }
+
+    #[test]
+    fn confidence_threshold() {
+        // same title by another artist, only the duration decides
+        let source = song("Hallelujah", "Leonard Cohen", Some(280_000));
+        let close = song("Hallelujah", "Jeff Buckley", Some(291_000));
+        let far = song("Hallelujah", "Jeff Buckley", Some(293_000));
+
+        let above = best_match(source.clone(), vec![close]);
+        assert!(above.confidence > MIN_CONFIDENCE && above.confidence < MIN_CONFIDENCE + 0.01);
+        assert!(above.is_confident());
+
+        let below = best_match(source, vec![far]);
+        assert!(below.confidence < MIN_CONFIDENCE && below.confidence > MIN_CONFIDENCE - 0.01);
+        assert!(!below.is_confident());
+        assert!(below.found.is_some() && below.confident_song().is_none());
+    }
+}
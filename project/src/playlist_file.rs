@@ -0,0 +1,354 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    report::csv_field,
+    types::music_types::{RSyncSong, RSyncSongMetadata, RSyncSongProviderData},
+};
+
+const CSV_HEADER: &str = "name,artists,album,duration_ms,isrc,url";
+
+/// File formats a playlist can be exported to and imported from, picked by the file extension.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PlaylistFormat {
+    M3u8,
+    Xspf,
+    Csv,
+    Json,
+}
+
+impl PlaylistFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_string_lossy().to_lowercase().as_str() {
+            "m3u" | "m3u8" => Some(PlaylistFormat::M3u8),
+            "xspf" => Some(PlaylistFormat::Xspf),
+            "csv" => Some(PlaylistFormat::Csv),
+            "json" => Some(PlaylistFormat::Json),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            PlaylistFormat::M3u8 => "m3u8",
+            PlaylistFormat::Xspf => "xspf",
+            PlaylistFormat::Csv => "csv",
+            PlaylistFormat::Json => "json",
+        }
+    }
+}
+
+/// Playlist as stored in the JSON format.
+#[derive(Debug, Serialize, Deserialize)]
+struct PlaylistFile {
+    name: String,
+    songs: Vec<RSyncSong>,
+}
+
+fn unknown_format(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!(
+            "Unknown playlist format of {}, use .m3u8, .xspf, .csv or .json",
+            path.display()
+        ),
+    )
+}
+
+/// Default export location of a playlist, the playlist name in the current directory.
+pub fn default_path(name: &str, format: PlaylistFormat) -> PathBuf {
+    let file_name: String = name
+        .chars()
+        .map(|c| match c.is_alphanumeric() || c == ' ' || c == '-' {
+            true => c,
+            false => '_',
+        })
+        .collect();
+    PathBuf::from(format!("{}.{}", file_name.trim(), format.extension()))
+}
+
+/// Writes `songs` to `path` in the format matching its extension.
+pub fn export(name: &str, songs: &[RSyncSong], path: &Path) -> io::Result<()> {
+    let data = match PlaylistFormat::from_path(path).ok_or_else(|| unknown_format(path))? {
+        PlaylistFormat::M3u8 => to_m3u8(name, songs),
+        PlaylistFormat::Xspf => to_xspf(name, songs),
+        PlaylistFormat::Csv => to_csv(songs),
+        PlaylistFormat::Json => serde_json::to_string_pretty(&PlaylistFile {
+            name: name.to_string(),
+            songs: songs.to_vec(),
+        })?,
+    };
+    fs::write(path, data)
+}
+
+/// Reads the songs of a playlist file, they are meant as a source list for a transfer.
+pub fn import(path: &Path) -> io::Result<Vec<RSyncSong>> {
+    let format = PlaylistFormat::from_path(path).ok_or_else(|| unknown_format(path))?;
+    let data = fs::read_to_string(path)?;
+    match format {
+        PlaylistFormat::M3u8 => Ok(from_m3u8(&data)),
+        PlaylistFormat::Xspf => Ok(from_xspf(&data)),
+        PlaylistFormat::Csv => Ok(from_csv(&data)),
+        PlaylistFormat::Json => Ok(serde_json::from_str::<PlaylistFile>(&data)?.songs),
+    }
+}
+
+/// Song read from a file, the url doubles as the id.
+fn imported_song(
+    name: String,
+    artists: String,
+    url: String,
+    metadata: RSyncSongMetadata,
+) -> RSyncSong {
+    let metadata = RSyncSongMetadata {
+        artists: artists
+            .split(',')
+            .map(|artist| artist.trim().to_string())
+            .filter(|artist| !artist.is_empty())
+            .collect(),
+        ..metadata
+    };
+    RSyncSong {
+        id: url.clone(),
+        artists,
+        url,
+        name,
+        metadata,
+        r#type: RSyncSongProviderData::Local,
+    }
+}
+
+fn to_m3u8(name: &str, songs: &[RSyncSong]) -> String {
+    let mut data = format!("#EXTM3U\n#PLAYLIST:{}\n", name);
+    for song in songs {
+        let seconds = song
+            .metadata
+            .duration_ms
+            .map(|duration| (duration / 1000) as i64)
+            .unwrap_or(-1);
+        data.push_str(&format!(
+            "#EXTINF:{},{} - {}\n{}\n",
+            seconds, song.artists, song.name, song.url
+        ));
+    }
+    data
+}
+
+fn from_m3u8(data: &str) -> Vec<RSyncSong> {
+    let mut songs = Vec::new();
+    let mut extinf: Option<(Option<u32>, String)> = None;
+    for line in data.lines().map(str::trim) {
+        if let Some(info) = line.strip_prefix("#EXTINF:") {
+            let (seconds, title) = info.split_once(',').unwrap_or((info, ""));
+            let duration_ms = seconds
+                .trim()
+                .parse::<i64>()
+                .ok()
+                .filter(|seconds| *seconds >= 0)
+                .map(|seconds| seconds as u32 * 1000);
+            extinf = Some((duration_ms, title.to_string()));
+        } else if !line.is_empty() && !line.starts_with('#') {
+            let (duration_ms, title) = extinf.take().unwrap_or_default();
+            let (artists, name) = match title.split_once(" - ") {
+                Some((artists, name)) => (artists.to_string(), name.to_string()),
+                None if !title.is_empty() => (String::new(), title),
+                None => (String::new(), file_stem(line)),
+            };
+            let metadata = RSyncSongMetadata {
+                duration_ms,
+                ..Default::default()
+            };
+            songs.push(imported_song(name, artists, line.to_string(), metadata));
+        }
+    }
+    songs
+}
+
+fn file_stem(location: &str) -> String {
+    Path::new(location)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| location.to_string())
+}
+
+fn to_xspf(name: &str, songs: &[RSyncSong]) -> String {
+    let mut data = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\">\n",
+    );
+    data.push_str(&format!(
+        "  <title>{}</title>\n  <trackList>\n",
+        xml_escape(name)
+    ));
+    for song in songs {
+        data.push_str("    <track>\n");
+        data.push_str(&format!(
+            "      <location>{}</location>\n",
+            xml_escape(&song.url)
+        ));
+        data.push_str(&format!(
+            "      <title>{}</title>\n",
+            xml_escape(&song.name)
+        ));
+        data.push_str(&format!(
+            "      <creator>{}</creator>\n",
+            xml_escape(&song.artists)
+        ));
+        if let Some(album) = &song.metadata.album {
+            data.push_str(&format!("      <album>{}</album>\n", xml_escape(album)));
+        }
+        if let Some(duration) = song.metadata.duration_ms {
+            data.push_str(&format!("      <duration>{}</duration>\n", duration));
+        }
+        if let Some(isrc) = &song.metadata.isrc {
+            data.push_str(&format!(
+                "      <identifier>isrc:{}</identifier>\n",
+                xml_escape(isrc)
+            ));
+        }
+        data.push_str("    </track>\n");
+    }
+    data.push_str("  </trackList>\n</playlist>\n");
+    data
+}
+
+/// Reads the tracks of an XSPF playlist, only the elements written by [`to_xspf`] are used.
+fn from_xspf(data: &str) -> Vec<RSyncSong> {
+    let mut songs = Vec::new();
+    let mut rest = data;
+    while let Some(track) = xml_element(rest, "track") {
+        rest = &rest[rest
+            .find("</track>")
+            .map(|end| end + "</track>".len())
+            .unwrap_or(rest.len())..];
+        let text = |tag: &str| xml_element(track, tag).map(xml_unescape);
+        let url = text("location").unwrap_or_default();
+        let name = text("title").unwrap_or_else(|| file_stem(&url));
+        let metadata = RSyncSongMetadata {
+            isrc: text("identifier")
+                .and_then(|identifier| identifier.strip_prefix("isrc:").map(str::to_string)),
+            duration_ms: text("duration").and_then(|duration| duration.trim().parse().ok()),
+            album: text("album"),
+            artists: Vec::new(),
+            added_at: None,
+        };
+        songs.push(imported_song(
+            name,
+            text("creator").unwrap_or_default(),
+            url,
+            metadata,
+        ));
+    }
+    songs
+}
+
+/// Content of the first `<tag>` element in `data`.
+fn xml_element<'a>(data: &'a str, tag: &str) -> Option<&'a str> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let start = data.find(&open)? + open.len();
+    let end = data[start..].find(&close)? + start;
+    Some(&data[start..end])
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn xml_unescape(text: &str) -> String {
+    text.trim()
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn to_csv(songs: &[RSyncSong]) -> String {
+    let mut csv = format!("{}\n", CSV_HEADER);
+    for song in songs {
+        let row = [
+            song.name.clone(),
+            song.artists.clone(),
+            song.metadata.album.clone().unwrap_or_default(),
+            song.metadata
+                .duration_ms
+                .map(|duration| duration.to_string())
+                .unwrap_or_default(),
+            song.metadata.isrc.clone().unwrap_or_default(),
+            song.url.clone(),
+        ];
+        csv.push_str(
+            &row.iter()
+                .map(|field| csv_field(field))
+                .collect::<Vec<String>>()
+                .join(","),
+        );
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Reads a CSV with the columns of [`CSV_HEADER`], the header row is required.
+fn from_csv(data: &str) -> Vec<RSyncSong> {
+    let mut lines = data.lines();
+    let header = csv_record(lines.next().unwrap_or_default());
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|field| field.eq_ignore_ascii_case(name))
+    };
+    let columns = ["name", "artists", "album", "duration_ms", "isrc", "url"].map(column);
+    lines
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let record = csv_record(line);
+            let [name, artists, album, duration_ms, isrc, url] = columns.map(|column| {
+                column
+                    .and_then(|index| record.get(index))
+                    .filter(|field| !field.is_empty())
+                    .cloned()
+            });
+            let metadata = RSyncSongMetadata {
+                isrc,
+                duration_ms: duration_ms.and_then(|duration| duration.parse().ok()),
+                album,
+                artists: Vec::new(),
+                added_at: None,
+            };
+            imported_song(
+                name.unwrap_or_default(),
+                artists.unwrap_or_default(),
+                url.unwrap_or_default(),
+                metadata,
+            )
+        })
+        .collect()
+}
+
+/// Splits one CSV line, quoted fields may contain commas and doubled quotes.
+fn csv_record(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            ('"', _) => quoted = !quoted,
+            (',', false) => fields.push(std::mem::take(&mut field)),
+            (c, _) => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
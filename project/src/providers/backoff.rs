@@ -0,0 +1,39 @@
+use std::{future::Future, time::Duration};
+
+use tracing::warn;
+
+/// Attempts of a request before its error is returned.
+const MAX_ATTEMPTS: u32 = 5;
+/// Wait before the second attempt, it doubles with every further attempt.
+const FIRST_DELAY: Duration = Duration::from_millis(500);
+
+/// Sends the request built by `call` until it succeeds, waiting longer after every attempt.
+///
+/// Only the errors `is_transient` accepts are retried, e.g. rate limits and server errors,
+/// any other error is returned at once.
+pub async fn retry<T, E, F, Fut>(is_transient: impl Fn(&E) -> bool, mut call: F) -> Result<T, E>
+where
+    E: std::fmt::Display,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let mut delay = FIRST_DELAY;
+    let mut attempt = 1;
+    loop {
+        match call().await {
+            Err(err) if attempt < MAX_ATTEMPTS && is_transient(&err) => {
+                warn!(
+                    "Request failed ({}), attempt {} of {} in {:?}",
+                    err,
+                    attempt + 1,
+                    MAX_ATTEMPTS,
+                    delay
+                );
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
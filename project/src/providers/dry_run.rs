@@ -0,0 +1,410 @@
+use std::{
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use futures::{stream, StreamExt, TryStreamExt};
+
+use crate::{
+    matching::SongMatch,
+    reorder::SongMove,
+    types::music_types::{
+        PlaylistDetails, PlaylistIdWrapper, RSyncPlaylistItem, RSyncPlaylistItemProviderData,
+        RSyncSong,
+    },
+};
+
+use super::{
+    journal::{RecentMap, KNOWN_PLAYLISTS_LIMIT, KNOWN_SONGS_LIMIT},
+    paging::PageStream,
+    provider_error::{ProviderError, ProviderResult},
+    provider_traits::{APIProvider, AddOutcome, InsertProgress},
+    quota::Quota,
+};
+
+/// Prefix of the ids of playlists "created" during a dry run.
+const DRY_RUN_PLAYLIST_PREFIX: &str = "dry-run:";
+
+/// Write a provider would have made, songs and playlists are stored as readable labels.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Mutation {
+    CreatePlaylist {
+        provider: String,
+        name: String,
+    },
+    UpdatePlaylist {
+        provider: String,
+        name: String,
+        details: PlaylistDetails,
+    },
+    DeletePlaylist {
+        provider: String,
+        name: String,
+    },
+    AddSongs {
+        provider: String,
+        playlist: String,
+        songs: Vec<String>,
+    },
+    RemoveSongs {
+        provider: String,
+        playlist: String,
+        songs: Vec<String>,
+    },
+    MoveSongs {
+        provider: String,
+        playlist: String,
+        moves: usize,
+    },
+}
+
+/// Dry run switch shared by every wrapped provider, with the mutations recorded so far.
+#[derive(Clone, Debug, Default)]
+pub struct DryRun {
+    enabled: Arc<AtomicBool>,
+    mutations: Arc<Mutex<Vec<Mutation>>>,
+}
+
+impl DryRun {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled: Arc::new(AtomicBool::new(enabled)),
+            mutations: Arc::default(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Turns the switch on or off, recorded mutations are dropped.
+    pub fn set(&self, enabled: bool) {
+        self.mutations.lock().unwrap().clear();
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Flips the switch and returns the new state, recorded mutations are dropped.
+    pub fn toggle(&self) -> bool {
+        self.mutations.lock().unwrap().clear();
+        !self.enabled.fetch_xor(true, Ordering::Relaxed)
+    }
+
+    fn record(&self, mutation: Mutation) {
+        self.mutations.lock().unwrap().push(mutation);
+    }
+
+    /// Returns the recorded mutations and starts a new recording.
+    pub fn take(&self) -> Vec<Mutation> {
+        std::mem::take(&mut *self.mutations.lock().unwrap())
+    }
+}
+
+/// Recorded mutations as a diff, `+` for added songs, `-` for removed ones and `~` for moves.
+pub fn diff_lines(mutations: &[Mutation]) -> Vec<String> {
+    if mutations.is_empty() {
+        return vec!["Nothing would be changed".into()];
+    }
+    let mut lines = Vec::new();
+    let playlist_lines: Vec<String> = mutations
+        .iter()
+        .filter_map(|mutation| match mutation {
+            Mutation::CreatePlaylist { provider, name } => {
+                Some(format!("  + {} ({})", name, provider))
+            }
+            Mutation::UpdatePlaylist {
+                provider,
+                name,
+                details,
+            } => Some(format!(
+                "  ~ {} ({}) -> {}, {}, {}",
+                name,
+                provider,
+                details.name,
+                details.description.as_deref().unwrap_or("no description"),
+                match details.public {
+                    true => "public",
+                    false => "private",
+                }
+            )),
+            Mutation::DeletePlaylist { provider, name } => {
+                Some(format!("  - {} ({})", name, provider))
+            }
+            _ => None,
+        })
+        .collect();
+    if !playlist_lines.is_empty() {
+        lines.push("Playlists:".into());
+        lines.extend(playlist_lines);
+    }
+    for mutation in mutations {
+        let (sign, provider, playlist, songs) = match mutation {
+            Mutation::CreatePlaylist { .. }
+            | Mutation::UpdatePlaylist { .. }
+            | Mutation::DeletePlaylist { .. } => continue,
+            Mutation::MoveSongs {
+                provider,
+                playlist,
+                moves,
+            } => {
+                lines.push(format!("{} / {}:", provider, playlist));
+                lines.push(format!("  ~ {} songs moved", moves));
+                continue;
+            }
+            Mutation::AddSongs {
+                provider,
+                playlist,
+                songs,
+            } => ('+', provider, playlist, songs),
+            Mutation::RemoveSongs {
+                provider,
+                playlist,
+                songs,
+            } => ('-', provider, playlist, songs),
+        };
+        lines.push(format!("{} / {}:", provider, playlist));
+        for song in songs {
+            lines.push(format!("  {} {}", sign, song));
+        }
+    }
+    lines
+}
+
+/// Provider layer that records writes instead of making them while the dry run is enabled.
+///
+/// Reads are passed through, the songs and playlists they returned last are remembered so
+/// the recorded mutations can name them instead of showing raw ids.
+#[derive(Clone, Debug)]
+pub struct DryRunProvider<P: APIProvider> {
+    inner: P,
+    /// Provider name used in the recorded mutations.
+    name: String,
+    dry_run: DryRun,
+    song_labels: Arc<Mutex<RecentMap<String, String>>>,
+    playlist_labels: Arc<Mutex<RecentMap<PlaylistIdWrapper, String>>>,
+}
+
+impl<P: APIProvider> DryRunProvider<P> {
+    pub fn new(inner: P, name: &str, dry_run: DryRun) -> Self {
+        Self {
+            inner,
+            name: name.to_string(),
+            dry_run,
+            song_labels: Arc::new(Mutex::new(RecentMap::new(KNOWN_SONGS_LIMIT))),
+            playlist_labels: Arc::new(Mutex::new(RecentMap::new(KNOWN_PLAYLISTS_LIMIT))),
+        }
+    }
+
+    /// Provider the writes go to, bypassing the dry run.
+    pub fn inner_mut(&mut self) -> &mut P {
+        &mut self.inner
+    }
+
+    fn remember_songs<'a>(
+        labels: &Mutex<RecentMap<String, String>>,
+        songs: impl IntoIterator<Item = &'a RSyncSong>,
+    ) {
+        let mut labels = labels.lock().unwrap();
+        for song in songs {
+            let label = format!("{} ({})", song.name, song.artists);
+            labels.insert(song.removal_id(), label.clone());
+            labels.insert(song.id.clone(), label);
+        }
+    }
+
+    fn song_labels(&self, song_ids: &[String]) -> Vec<String> {
+        let labels = self.song_labels.lock().unwrap();
+        song_ids
+            .iter()
+            .map(|id| labels.get(id).cloned().unwrap_or_else(|| id.clone()))
+            .collect()
+    }
+
+    fn playlist_label(&self, playlist_id: &PlaylistIdWrapper) -> String {
+        match self.playlist_labels.lock().unwrap().get(playlist_id) {
+            Some(name) => name.clone(),
+            None => match playlist_id {
+                PlaylistIdWrapper::Id(id) => id.clone(),
+                PlaylistIdWrapper::Liked => "Liked songs".into(),
+            },
+        }
+    }
+
+    fn is_dry_run_playlist(playlist_id: &PlaylistIdWrapper) -> bool {
+        matches!(playlist_id, PlaylistIdWrapper::Id(id) if id.starts_with(DRY_RUN_PLAYLIST_PREFIX))
+    }
+}
+
+impl<P: APIProvider> APIProvider for DryRunProvider<P> {
+    fn playlist_pages(&mut self) -> PageStream<'_, RSyncPlaylistItem> {
+        let labels = &self.playlist_labels;
+        self.inner
+            .playlist_pages()
+            .inspect_ok(move |playlists| {
+                let mut labels = labels.lock().unwrap();
+                for playlist in playlists.iter() {
+                    labels.insert(playlist.id.clone(), playlist.name.clone());
+                }
+            })
+            .boxed()
+    }
+
+    fn playlist_song_pages(&mut self, playlist_id: PlaylistIdWrapper) -> PageStream<'_, RSyncSong> {
+        if Self::is_dry_run_playlist(&playlist_id) {
+            return stream::empty().boxed();
+        }
+        let labels = &self.song_labels;
+        self.inner
+            .playlist_song_pages(playlist_id)
+            .inspect_ok(move |songs| Self::remember_songs(labels, songs.iter()))
+            .boxed()
+    }
+
+    fn playlist_data(&self) -> RSyncPlaylistItemProviderData {
+        self.inner.playlist_data()
+    }
+
+    async fn create_playlist(
+        &mut self,
+        playlist_name: String,
+    ) -> ProviderResult<RSyncPlaylistItem> {
+        if !self.dry_run.is_enabled() {
+            return self.inner.create_playlist(playlist_name).await;
+        }
+        self.dry_run.record(Mutation::CreatePlaylist {
+            provider: self.name.clone(),
+            name: playlist_name.clone(),
+        });
+        let id = PlaylistIdWrapper::Id(format!("{}{}", DRY_RUN_PLAYLIST_PREFIX, playlist_name));
+        self.playlist_labels
+            .lock()
+            .unwrap()
+            .insert(id.clone(), playlist_name.clone());
+        Ok(RSyncPlaylistItem {
+            collaborative: false,
+            description: None,
+            url: String::new(),
+            id,
+            name: playlist_name,
+            owned: true,
+            public: false,
+            tracks: 0,
+            r#type: self.inner.playlist_data(),
+        })
+    }
+
+    async fn update_playlist(
+        &mut self,
+        playlist: RSyncPlaylistItem,
+        details: PlaylistDetails,
+    ) -> ProviderResult<RSyncPlaylistItem> {
+        if !self.dry_run.is_enabled() {
+            return self.inner.update_playlist(playlist, details).await;
+        }
+        self.dry_run.record(Mutation::UpdatePlaylist {
+            provider: self.name.clone(),
+            name: playlist.name.clone(),
+            details: details.clone(),
+        });
+        Ok(playlist.with_details(details))
+    }
+
+    async fn delete_playlist(&mut self, playlist_id: PlaylistIdWrapper) -> ProviderResult<()> {
+        if !self.dry_run.is_enabled() {
+            return self.inner.delete_playlist(playlist_id).await;
+        }
+        self.dry_run.record(Mutation::DeletePlaylist {
+            provider: self.name.clone(),
+            name: self.playlist_label(&playlist_id),
+        });
+        Ok(())
+    }
+
+    async fn restore_playlist(
+        &mut self,
+        playlist: RSyncPlaylistItem,
+    ) -> ProviderResult<Option<RSyncPlaylistItem>> {
+        // only undo restores, and nothing is journaled during a dry run
+        if self.dry_run.is_enabled() {
+            return Err(ProviderError::Other(
+                "Deleted playlists are not restored in dry run".into(),
+            ));
+        }
+        self.inner.restore_playlist(playlist).await
+    }
+
+    async fn add_playlist_song_with_progress(
+        &mut self,
+        playlist_id: PlaylistIdWrapper,
+        song_id: Vec<String>,
+        progress: InsertProgress<'_>,
+    ) -> ProviderResult<AddOutcome> {
+        if !self.dry_run.is_enabled() {
+            return self
+                .inner
+                .add_playlist_song_with_progress(playlist_id, song_id, progress)
+                .await;
+        }
+        self.dry_run.record(Mutation::AddSongs {
+            provider: self.name.clone(),
+            playlist: self.playlist_label(&playlist_id),
+            songs: self.song_labels(&song_id),
+        });
+        Ok(AddOutcome::all(song_id))
+    }
+
+    async fn rem_playlist_song(
+        &mut self,
+        playlist_id: PlaylistIdWrapper,
+        song_ids: Vec<String>,
+    ) -> ProviderResult<()> {
+        if !self.dry_run.is_enabled() {
+            return self.inner.rem_playlist_song(playlist_id, song_ids).await;
+        }
+        self.dry_run.record(Mutation::RemoveSongs {
+            provider: self.name.clone(),
+            playlist: self.playlist_label(&playlist_id),
+            songs: self.song_labels(&song_ids),
+        });
+        Ok(())
+    }
+
+    async fn move_playlist_songs(
+        &mut self,
+        playlist_id: PlaylistIdWrapper,
+        moves: Vec<SongMove>,
+    ) -> ProviderResult<()> {
+        if !self.dry_run.is_enabled() {
+            return self.inner.move_playlist_songs(playlist_id, moves).await;
+        }
+        self.dry_run.record(Mutation::MoveSongs {
+            provider: self.name.clone(),
+            playlist: self.playlist_label(&playlist_id),
+            moves: moves.len(),
+        });
+        Ok(())
+    }
+
+    async fn search(&mut self, query: String, limit: u32) -> ProviderResult<Vec<RSyncSong>> {
+        let songs = self.inner.search(query, limit).await?;
+        Self::remember_songs(&self.song_labels, songs.iter());
+        Ok(songs)
+    }
+
+    async fn search_list(&mut self, items: Vec<RSyncSong>) -> ProviderResult<Vec<SongMatch>> {
+        let matches = self.inner.search_list(items).await?;
+        Self::remember_songs(
+            &self.song_labels,
+            matches
+                .iter()
+                .flat_map(|song_match| song_match.found.iter().chain(song_match.candidates.iter())),
+        );
+        Ok(matches)
+    }
+
+    fn quota(&self) -> Option<Quota> {
+        self.inner.quota()
+    }
+}
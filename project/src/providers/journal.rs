@@ -0,0 +1,593 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    env, fs,
+    hash::Hash,
+    path::{Path, PathBuf},
+    sync::{mpsc, Arc, Mutex},
+    thread,
+};
+
+use futures::{StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{
+    matching::SongMatch,
+    reorder::SongMove,
+    types::music_types::{
+        PlaylistDetails, PlaylistIdWrapper, RSyncPlaylistItem, RSyncPlaylistItemProviderData,
+        RSyncSong,
+    },
+};
+
+use super::{
+    paging::PageStream,
+    provider_error::{ProviderError, ProviderResult},
+    provider_traits::{APIProvider, AddOutcome, InsertProgress},
+    quota::Quota,
+    registry::ProviderId,
+};
+
+/// Number of operations kept in the journal, older ones can not be undone anymore.
+pub const JOURNAL_LIMIT: usize = 50;
+
+/// Ids of the songs a provider layer remembers, e.g. for journaling removals, every song
+/// is known by two ids.
+pub(super) const KNOWN_SONGS_LIMIT: usize = 20_000;
+/// Playlists a provider layer remembers, e.g. for journaling deletions.
+pub(super) const KNOWN_PLAYLISTS_LIMIT: usize = 1_000;
+
+/// Map that forgets the entry inserted longest ago once it holds more than `limit` entries.
+#[derive(Debug)]
+pub(super) struct RecentMap<K, V> {
+    limit: usize,
+    /// Values with the stamp of their latest insert.
+    entries: HashMap<K, (u64, V)>,
+    /// Keys in the order they were inserted, a key inserted again is stale at its old place.
+    order: VecDeque<(u64, K)>,
+    next_stamp: u64,
+}
+
+impl<K: Clone + Eq + Hash, V> RecentMap<K, V> {
+    pub(super) fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            next_stamp: 0,
+        }
+    }
+
+    fn is_current(entries: &HashMap<K, (u64, V)>, stamp: u64, key: &K) -> bool {
+        entries
+            .get(key)
+            .is_some_and(|(inserted, _)| *inserted == stamp)
+    }
+
+    pub(super) fn insert(&mut self, key: K, value: V) {
+        let stamp = self.next_stamp;
+        self.next_stamp += 1;
+        self.order.push_back((stamp, key.clone()));
+        self.entries.insert(key, (stamp, value));
+        if self.entries.len() > self.limit {
+            while let Some((stamp, key)) = self.order.pop_front() {
+                if Self::is_current(&self.entries, stamp, &key) {
+                    self.entries.remove(&key);
+                    break;
+                }
+            }
+        }
+        if self.order.len() > 2 * self.limit {
+            let entries = &self.entries;
+            self.order
+                .retain(|(stamp, key)| Self::is_current(entries, *stamp, key));
+        }
+    }
+
+    pub(super) fn get(&self, key: &K) -> Option<&V> {
+        self.entries.get(key).map(|(_, value)| value)
+    }
+}
+
+/// Song of a journal entry, `id` is what adds it back to a playlist.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct JournalSong {
+    pub id: String,
+    pub label: String,
+}
+
+impl From<&RSyncSong> for JournalSong {
+    fn from(song: &RSyncSong) -> Self {
+        Self {
+            id: song.id.clone(),
+            label: format!("{} ({})", song.name, song.artists),
+        }
+    }
+}
+
+/// Executed playlist mutation with what is needed to invert it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum JournalEntry {
+    AddSongs {
+        provider_id: ProviderId,
+        playlist_id: PlaylistIdWrapper,
+        songs: Vec<JournalSong>,
+    },
+    /// Undoing adds the songs back to the end of the playlist, their position is lost.
+    RemoveSongs {
+        provider_id: ProviderId,
+        playlist_id: PlaylistIdWrapper,
+        songs: Vec<JournalSong>,
+    },
+    CreatePlaylist {
+        provider_id: ProviderId,
+        playlist: RSyncPlaylistItem,
+    },
+    /// `playlist` is the updated playlist, `previous` the details before the update.
+    UpdatePlaylist {
+        provider_id: ProviderId,
+        playlist: RSyncPlaylistItem,
+        previous: PlaylistDetails,
+    },
+    /// Undoing restores the playlist if the service keeps deleted ones, otherwise it creates
+    /// a new playlist with the same details and songs.
+    DeletePlaylist {
+        provider_id: ProviderId,
+        playlist: RSyncPlaylistItem,
+        songs: Vec<JournalSong>,
+    },
+    /// Undoing applies the inverse moves in the reverse order.
+    MoveSongs {
+        provider_id: ProviderId,
+        playlist_id: PlaylistIdWrapper,
+        moves: Vec<SongMove>,
+    },
+}
+
+impl JournalEntry {
+    pub fn provider_id(&self) -> &ProviderId {
+        match self {
+            JournalEntry::AddSongs { provider_id, .. }
+            | JournalEntry::RemoveSongs { provider_id, .. }
+            | JournalEntry::CreatePlaylist { provider_id, .. }
+            | JournalEntry::UpdatePlaylist { provider_id, .. }
+            | JournalEntry::DeletePlaylist { provider_id, .. }
+            | JournalEntry::MoveSongs { provider_id, .. } => provider_id,
+        }
+    }
+}
+
+/// Request to the thread writing the journal file.
+#[derive(Debug)]
+enum WriterMessage {
+    Save(String),
+    /// Answers once the earlier saves are written.
+    Flush(mpsc::Sender<()>),
+}
+
+/// Writes the journal on its own thread, so neither the lock of the entries nor the async
+/// tasks wait for the disk.
+fn spawn_writer(path: PathBuf) -> mpsc::Sender<WriterMessage> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for message in receiver {
+            match message {
+                WriterMessage::Save(data) => {
+                    if let Err(err) = fs::write(&path, data) {
+                        warn!("Failed to save journal {}: {}", path.display(), err);
+                    }
+                }
+                WriterMessage::Flush(done) => {
+                    let _ = done.send(());
+                }
+            }
+        }
+    });
+    sender
+}
+
+/// Last executed mutations of every provider, written to disk after each change.
+#[derive(Clone, Debug, Default)]
+pub struct Journal {
+    entries: Arc<Mutex<VecDeque<JournalEntry>>>,
+    /// `None` keeps the journal in memory only.
+    writer: Option<mpsc::Sender<WriterMessage>>,
+}
+
+impl Journal {
+    /// Location of the journal, `RSYNC_JOURNAL` or `journal.json` in the current directory.
+    pub fn default_path() -> PathBuf {
+        env::var_os("RSYNC_JOURNAL")
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("journal.json"))
+    }
+
+    /// Loads the journal from `path`, a missing or invalid file starts an empty one.
+    pub fn load(path: &Path) -> Self {
+        let entries = match fs::read_to_string(path) {
+            Ok(data) => serde_json::from_str(&data).unwrap_or_else(|err| {
+                warn!("Ignoring invalid journal {}: {}", path.display(), err);
+                VecDeque::new()
+            }),
+            Err(_) => VecDeque::new(),
+        };
+        Self {
+            entries: Arc::new(Mutex::new(entries)),
+            writer: Some(spawn_writer(path.to_path_buf())),
+        }
+    }
+
+    /// Hands the entries to the writer thread, the saves are written in their order.
+    fn save(&self, entries: &VecDeque<JournalEntry>) {
+        let Some(writer) = &self.writer else {
+            return;
+        };
+        match serde_json::to_string_pretty(entries) {
+            Ok(data) => {
+                let _ = writer.send(WriterMessage::Save(data));
+            }
+            Err(err) => warn!("Failed to serialize journal: {}", err),
+        }
+    }
+
+    /// Waits until every change is on disk, e.g. before the app exits.
+    pub fn flush(&self) {
+        let Some(writer) = &self.writer else {
+            return;
+        };
+        let (done, written) = mpsc::channel();
+        if writer.send(WriterMessage::Flush(done)).is_ok() {
+            let _ = written.recv();
+        }
+    }
+
+    fn update(&self, change: impl FnOnce(&mut VecDeque<JournalEntry>)) {
+        let mut entries = self.entries.lock().unwrap();
+        change(&mut entries);
+        while entries.len() > JOURNAL_LIMIT {
+            entries.pop_front();
+        }
+        self.save(&entries);
+    }
+
+    pub fn record(&self, entry: JournalEntry) {
+        self.update(|entries| entries.push_back(entry));
+    }
+
+    /// Takes the most recent entry out of the journal.
+    pub fn pop(&self) -> Option<JournalEntry> {
+        let mut entry = None;
+        self.update(|entries| entry = entries.pop_back());
+        entry
+    }
+}
+
+/// Provider layer that writes every successful mutation into the [`Journal`].
+///
+/// Songs returned by reads are remembered by their id and removal id, so a removal
+/// can be journaled with the song ids needed to add the songs back. Playlists are
+/// remembered for the same reason, a deleted playlist is recreated from them.
+#[derive(Clone, Debug)]
+pub struct JournaledProvider<P: APIProvider> {
+    inner: P,
+    provider_id: ProviderId,
+    journal: Journal,
+    songs: Arc<Mutex<RecentMap<String, JournalSong>>>,
+    playlists: Arc<Mutex<RecentMap<PlaylistIdWrapper, RSyncPlaylistItem>>>,
+}
+
+impl<P: APIProvider> JournaledProvider<P> {
+    pub fn new(inner: P, provider_id: ProviderId, journal: Journal) -> Self {
+        Self {
+            inner,
+            provider_id,
+            journal,
+            songs: Arc::new(Mutex::new(RecentMap::new(KNOWN_SONGS_LIMIT))),
+            playlists: Arc::new(Mutex::new(RecentMap::new(KNOWN_PLAYLISTS_LIMIT))),
+        }
+    }
+
+    fn remember_songs<'a>(
+        known: &Mutex<RecentMap<String, JournalSong>>,
+        songs: impl IntoIterator<Item = &'a RSyncSong>,
+    ) {
+        let mut known = known.lock().unwrap();
+        for song in songs {
+            known.insert(song.removal_id(), song.into());
+            known.insert(song.id.clone(), song.into());
+        }
+    }
+
+    /// Unknown ids are kept as they are, for most providers the removal id is the song id.
+    fn journal_songs(&self, song_ids: &[String]) -> Vec<JournalSong> {
+        let known = self.songs.lock().unwrap();
+        song_ids
+            .iter()
+            .map(|id| {
+                known.get(id).cloned().unwrap_or_else(|| JournalSong {
+                    id: id.clone(),
+                    label: id.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Applies the inverse of `entry` and describes it, the inverse is not journaled.
+    pub async fn undo(&mut self, entry: &JournalEntry) -> ProviderResult<String> {
+        match entry {
+            JournalEntry::AddSongs {
+                playlist_id, songs, ..
+            } => {
+                // added songs are appended, so the last entry of each id is the added one
+                let current = self.inner.get_playlist_songs(playlist_id.clone()).await?;
+                let mut removal_ids: Vec<String> = Vec::new();
+                for song in songs {
+                    let added = current.iter().rev().find(|current| {
+                        current.id == song.id && !removal_ids.contains(&current.removal_id())
+                    });
+                    if let Some(added) = added {
+                        removal_ids.push(added.removal_id());
+                    }
+                }
+                let count = removal_ids.len();
+                if !removal_ids.is_empty() {
+                    self.inner
+                        .rem_playlist_song(playlist_id.clone(), removal_ids)
+                        .await?;
+                }
+                Ok(format!("Removed {} of {} added songs", count, songs.len()))
+            }
+            JournalEntry::RemoveSongs {
+                playlist_id, songs, ..
+            } => {
+                let song_ids = songs.iter().map(|song| song.id.clone()).collect();
+                self.inner
+                    .add_playlist_song(playlist_id.clone(), song_ids)
+                    .await?
+                    .into_result()?;
+                let mut message = format!("Added {} removed songs back:", songs.len());
+                for song in songs {
+                    message.push_str(&format!("\n  {}", song.label));
+                }
+                Ok(message)
+            }
+            JournalEntry::CreatePlaylist { playlist, .. } => {
+                self.inner.delete_playlist(playlist.id.clone()).await?;
+                Ok(format!("Deleted created playlist {}", playlist.name))
+            }
+            JournalEntry::UpdatePlaylist {
+                playlist, previous, ..
+            } => {
+                self.inner
+                    .update_playlist(playlist.clone(), previous.clone())
+                    .await?;
+                Ok(format!("Restored details of {}", previous.name))
+            }
+            JournalEntry::DeletePlaylist {
+                playlist, songs, ..
+            } => {
+                if let Some(restored) = self.inner.restore_playlist(playlist.clone()).await? {
+                    return Ok(format!("Restored {}", restored.name));
+                }
+                let mut created = self.inner.create_playlist(playlist.name.clone()).await?;
+                if created.details() != playlist.details() {
+                    created = self
+                        .inner
+                        .update_playlist(created, playlist.details())
+                        .await?;
+                }
+                if !songs.is_empty() {
+                    let song_ids = songs.iter().map(|song| song.id.clone()).collect();
+                    self.inner
+                        .add_playlist_song(created.id, song_ids)
+                        .await?
+                        .into_result()?;
+                }
+                Ok(format!(
+                    "Recreated {} with {} songs, links to the deleted playlist are lost",
+                    playlist.name,
+                    songs.len()
+                ))
+            }
+            JournalEntry::MoveSongs {
+                playlist_id, moves, ..
+            } => {
+                let inverse = moves.iter().rev().map(SongMove::inverse).collect();
+                self.inner
+                    .move_playlist_songs(playlist_id.clone(), inverse)
+                    .await?;
+                Ok(format!("Reverted {} song moves", moves.len()))
+            }
+        }
+    }
+}
+
+impl<P: APIProvider> APIProvider for JournaledProvider<P> {
+    fn playlist_pages(&mut self) -> PageStream<'_, RSyncPlaylistItem> {
+        let known = &self.playlists;
+        self.inner
+            .playlist_pages()
+            .inspect_ok(move |playlists| {
+                let mut known = known.lock().unwrap();
+                for playlist in playlists.iter() {
+                    known.insert(playlist.id.clone(), playlist.clone());
+                }
+            })
+            .boxed()
+    }
+
+    fn playlist_song_pages(&mut self, playlist_id: PlaylistIdWrapper) -> PageStream<'_, RSyncSong> {
+        let known = &self.songs;
+        self.inner
+            .playlist_song_pages(playlist_id)
+            .inspect_ok(move |songs| Self::remember_songs(known, songs.iter()))
+            .boxed()
+    }
+
+    fn playlist_data(&self) -> RSyncPlaylistItemProviderData {
+        self.inner.playlist_data()
+    }
+
+    async fn create_playlist(
+        &mut self,
+        playlist_name: String,
+    ) -> ProviderResult<RSyncPlaylistItem> {
+        let playlist = self.inner.create_playlist(playlist_name).await?;
+        self.journal.record(JournalEntry::CreatePlaylist {
+            provider_id: self.provider_id.clone(),
+            playlist: playlist.clone(),
+        });
+        Ok(playlist)
+    }
+
+    async fn update_playlist(
+        &mut self,
+        playlist: RSyncPlaylistItem,
+        details: PlaylistDetails,
+    ) -> ProviderResult<RSyncPlaylistItem> {
+        let previous = playlist.details();
+        let updated = self.inner.update_playlist(playlist, details).await?;
+        self.journal.record(JournalEntry::UpdatePlaylist {
+            provider_id: self.provider_id.clone(),
+            playlist: updated.clone(),
+            previous,
+        });
+        Ok(updated)
+    }
+
+    async fn delete_playlist(&mut self, playlist_id: PlaylistIdWrapper) -> ProviderResult<()> {
+        let known = self.playlists.lock().unwrap().get(&playlist_id).cloned();
+        let playlist = known.ok_or_else(|| {
+            ProviderError::NotFound("Playlist is not loaded, refresh the playlists".into())
+        })?;
+        // the songs are needed to recreate the playlist
+        let songs = self.inner.get_playlist_songs(playlist_id.clone()).await?;
+        self.inner.delete_playlist(playlist_id).await?;
+        self.journal.record(JournalEntry::DeletePlaylist {
+            provider_id: self.provider_id.clone(),
+            playlist,
+            songs: songs.iter().map(JournalSong::from).collect(),
+        });
+        Ok(())
+    }
+
+    async fn restore_playlist(
+        &mut self,
+        playlist: RSyncPlaylistItem,
+    ) -> ProviderResult<Option<RSyncPlaylistItem>> {
+        self.inner.restore_playlist(playlist).await
+    }
+
+    async fn add_playlist_song_with_progress(
+        &mut self,
+        playlist_id: PlaylistIdWrapper,
+        song_id: Vec<String>,
+        progress: InsertProgress<'_>,
+    ) -> ProviderResult<AddOutcome> {
+        let outcome = self
+            .inner
+            .add_playlist_song_with_progress(playlist_id.clone(), song_id, progress)
+            .await?;
+        // only the inserted songs are removed again on undo
+        if !outcome.added.is_empty() {
+            self.journal.record(JournalEntry::AddSongs {
+                provider_id: self.provider_id.clone(),
+                playlist_id,
+                songs: self.journal_songs(&outcome.added),
+            });
+        }
+        Ok(outcome)
+    }
+
+    async fn rem_playlist_song(
+        &mut self,
+        playlist_id: PlaylistIdWrapper,
+        song_ids: Vec<String>,
+    ) -> ProviderResult<()> {
+        let songs = self.journal_songs(&song_ids);
+        self.inner
+            .rem_playlist_song(playlist_id.clone(), song_ids)
+            .await?;
+        self.journal.record(JournalEntry::RemoveSongs {
+            provider_id: self.provider_id.clone(),
+            playlist_id,
+            songs,
+        });
+        Ok(())
+    }
+
+    async fn move_playlist_songs(
+        &mut self,
+        playlist_id: PlaylistIdWrapper,
+        moves: Vec<SongMove>,
+    ) -> ProviderResult<()> {
+        self.inner
+            .move_playlist_songs(playlist_id.clone(), moves.clone())
+            .await?;
+        self.journal.record(JournalEntry::MoveSongs {
+            provider_id: self.provider_id.clone(),
+            playlist_id,
+            moves,
+        });
+        Ok(())
+    }
+
+    async fn search(&mut self, query: String, limit: u32) -> ProviderResult<Vec<RSyncSong>> {
+        let songs = self.inner.search(query, limit).await?;
+        Self::remember_songs(&self.songs, songs.iter());
+        Ok(songs)
+    }
+
+    async fn search_list(&mut self, items: Vec<RSyncSong>) -> ProviderResult<Vec<SongMatch>> {
+        let matches = self.inner.search_list(items).await?;
+        Self::remember_songs(
+            &self.songs,
+            matches
+                .iter()
+                .flat_map(|song_match| song_match.found.iter().chain(song_match.candidates.iter())),
+        );
+        Ok(matches)
+    }
+
+    fn quota(&self) -> Option<Quota> {
+        self.inner.quota()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recent_map_forgets_the_oldest_insert() {
+        let mut map = RecentMap::new(2);
+        map.insert("a", 1);
+        map.insert("b", 2);
+        map.insert("a", 3);
+        map.insert("c", 4);
+
+        assert_eq!(map.get(&"a"), Some(&3));
+        assert_eq!(map.get(&"b"), None);
+        assert_eq!(map.get(&"c"), Some(&4));
+        assert!(map.order.len() <= 4);
+    }
+
+    #[test]
+    fn flushed_journal_loads_again() {
+        let path = env::temp_dir().join(format!("journal-{}.json", std::process::id()));
+        let journal = Journal::load(&path);
+        journal.record(JournalEntry::MoveSongs {
+            provider_id: "mock".into(),
+            playlist_id: PlaylistIdWrapper::Liked,
+            moves: Vec::new(),
+        });
+        journal.flush();
+
+        let loaded = Journal::load(&path);
+        let entry = loaded.pop();
+        loaded.flush();
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(entry, Some(JournalEntry::MoveSongs { .. })));
+    }
+}
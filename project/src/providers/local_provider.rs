@@ -0,0 +1,586 @@
+use std::{
+    collections::HashMap,
+    env,
+    fmt::Debug,
+    fs::{self, File},
+    io,
+    path::{Component, Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use symphonia::core::{
+    formats::FormatOptions,
+    io::MediaSourceStream,
+    meta::{MetadataOptions, StandardTagKey, Tag},
+    probe::Hint,
+};
+use tracing::warn;
+
+use crate::{
+    matching::{self, SongMatch},
+    reorder::SongMove,
+    types::music_types::{
+        PlaylistDetails, PlaylistIdWrapper, RSyncPlaylistItem, RSyncPlaylistItemProviderData,
+        RSyncSong, RSyncSongMetadata, RSyncSongProviderData,
+    },
+};
+
+use super::{
+    paging::{self, PageStream},
+    provider_error::{ProviderError, ProviderResult},
+    provider_traits::{APIProvider, AddOutcome, InsertProgress},
+};
+
+/// Files read into the library index.
+const AUDIO_EXTENSIONS: [&str; 7] = ["mp3", "flac", "ogg", "opus", "m4a", "wav", "aiff"];
+const PLAYLIST_EXTENSION: &str = "m3u8";
+
+impl From<io::Error> for ProviderError {
+    fn from(err: io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::NotFound => ProviderError::NotFound(err.to_string()),
+            io::ErrorKind::PermissionDenied => ProviderError::Permission(err.to_string()),
+            _ => ProviderError::Other(err.to_string()),
+        }
+    }
+}
+
+/// Line of a playlist file pointing to a song, with the `#EXTINF` line above it.
+#[derive(Clone, Debug)]
+struct PlaylistEntry {
+    extinf: Option<String>,
+    location: String,
+}
+
+/// Provider backed by a directory of audio files.
+///
+/// Songs are identified by their path relative to the library root, playlists are the
+/// `.m3u8` files inside of it. Tags are read once per file, the index is refreshed with
+/// every playlist listing so new files show up without a restart.
+#[derive(Clone, Debug)]
+pub struct LocalProvider {
+    root: PathBuf,
+    index: Arc<Mutex<HashMap<String, RSyncSong>>>,
+}
+
+impl LocalProvider {
+    pub fn from_directory(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            index: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Provider for the library in `RSYNC_LOCAL_LIBRARY`.
+    pub async fn new() -> ProviderResult<Self> {
+        let root = env::var("RSYNC_LOCAL_LIBRARY")
+            .ok()
+            .filter(|root| !root.is_empty())
+            .ok_or_else(|| ProviderError::NotFound("RSYNC_LOCAL_LIBRARY is not set".into()))?;
+        if !Path::new(&root).is_dir() {
+            return Err(ProviderError::NotFound(format!(
+                "Local library {} is not a directory",
+                root
+            )));
+        }
+        let provider = Self::from_directory(root);
+        provider.refresh_index().await?;
+        Ok(provider)
+    }
+
+    /// Adds files that are not indexed yet and drops the ones that are gone.
+    pub async fn refresh_index(&self) -> ProviderResult<()> {
+        let root = self.root.clone();
+        let known: Vec<String> = self.index.lock().unwrap().keys().cloned().collect();
+        let (files, new_songs) = tokio::task::spawn_blocking(move || -> io::Result<_> {
+            let mut files = Vec::new();
+            collect_files(&root, &AUDIO_EXTENSIONS, &mut files)?;
+            let files: Vec<String> = files
+                .iter()
+                .filter_map(|path| song_id(&root, path))
+                .collect();
+            let new_songs: Vec<RSyncSong> = files
+                .iter()
+                .filter(|id| !known.contains(id))
+                .map(|id| read_song(&root, id))
+                .collect();
+            Ok((files, new_songs))
+        })
+        .await
+        .map_err(|err| ProviderError::Other(err.to_string()))??;
+
+        let mut index = self.index.lock().unwrap();
+        index.retain(|id, _| files.contains(id));
+        for song in new_songs {
+            index.insert(song.id.clone(), song);
+        }
+        Ok(())
+    }
+
+    fn playlist_path(&self, playlist_id: &PlaylistIdWrapper) -> ProviderResult<PathBuf> {
+        match playlist_id {
+            PlaylistIdWrapper::Id(id) => Ok(self.root.join(id)),
+            PlaylistIdWrapper::Liked => Err(ProviderError::NotFound(
+                "Local library has no liked songs".into(),
+            )),
+        }
+    }
+
+    fn to_playlist(&self, id: String, entries: usize) -> RSyncPlaylistItem {
+        let name = Path::new(&id)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| id.clone());
+        RSyncPlaylistItem {
+            collaborative: false,
+            description: Some(id.clone()),
+            url: format!("file://{}", self.root.join(&id).display()),
+            id: PlaylistIdWrapper::Id(id),
+            name,
+            owned: true,
+            public: false,
+            tracks: entries as u32,
+            r#type: RSyncPlaylistItemProviderData::Local,
+        }
+    }
+
+    /// Song of a playlist entry, entries pointing outside of the index keep what the playlist says.
+    fn entry_song(&self, playlist_id: &str, entry: &PlaylistEntry) -> RSyncSong {
+        let id = resolve_location(playlist_id, &entry.location);
+        if let Some(song) = id
+            .as_ref()
+            .and_then(|id| self.index.lock().unwrap().get(id).cloned())
+        {
+            return song;
+        }
+        let id = id.unwrap_or_else(|| entry.location.clone());
+        let (artists, name) = match entry
+            .extinf
+            .as_ref()
+            .and_then(|extinf| extinf.split_once(','))
+            .map(|(_, title)| title.to_string())
+        {
+            Some(title) => match title.split_once(" - ") {
+                Some((artists, name)) => (artists.to_string(), name.to_string()),
+                None => (String::new(), title),
+            },
+            None => (String::new(), file_title(&id)),
+        };
+        RSyncSong {
+            url: format!("file://{}", self.root.join(&id).display()),
+            metadata: RSyncSongMetadata {
+                artists: split_artists(&artists),
+                ..Default::default()
+            },
+            artists,
+            id,
+            name,
+            r#type: RSyncSongProviderData::Local,
+        }
+    }
+}
+
+impl APIProvider for LocalProvider {
+    /// The playlist files are read at once, they are all on disk.
+    fn playlist_pages(&mut self) -> PageStream<'_, RSyncPlaylistItem> {
+        paging::single_page(async move {
+            self.refresh_index().await?;
+            let mut files = Vec::new();
+            collect_files(&self.root, &[PLAYLIST_EXTENSION], &mut files)?;
+            let mut playlists = Vec::new();
+            for path in files {
+                if let Some(id) = song_id(&self.root, &path) {
+                    let entries = read_playlist(&path)?.len();
+                    playlists.push(self.to_playlist(id, entries));
+                }
+            }
+            playlists.sort_by(|a, b| a.name.cmp(&b.name));
+            Ok(playlists)
+        })
+    }
+
+    fn playlist_song_pages(&mut self, playlist_id: PlaylistIdWrapper) -> PageStream<'_, RSyncSong> {
+        paging::single_page(async move {
+            let path = self.playlist_path(&playlist_id)?;
+            let PlaylistIdWrapper::Id(id) = playlist_id else {
+                unreachable!("liked songs have no playlist file");
+            };
+            Ok(read_playlist(&path)?
+                .iter()
+                .map(|entry| self.entry_song(&id, entry))
+                .collect())
+        })
+    }
+
+    fn playlist_data(&self) -> RSyncPlaylistItemProviderData {
+        RSyncPlaylistItemProviderData::Local
+    }
+
+    async fn create_playlist(
+        &mut self,
+        playlist_name: String,
+    ) -> ProviderResult<RSyncPlaylistItem> {
+        let id = playlist_file_name(&playlist_name);
+        let path = self.root.join(&id);
+        if path.exists() {
+            return Err(ProviderError::Other(format!(
+                "Playlist {} already exists",
+                path.display()
+            )));
+        }
+        fs::write(&path, "#EXTM3U\n")?;
+        Ok(self.to_playlist(id, 0))
+    }
+
+    /// Renames the playlist file inside its directory, m3u8 has no description or visibility.
+    async fn update_playlist(
+        &mut self,
+        playlist: RSyncPlaylistItem,
+        details: PlaylistDetails,
+    ) -> ProviderResult<RSyncPlaylistItem> {
+        let path = self.playlist_path(&playlist.id)?;
+        let PlaylistIdWrapper::Id(id) = playlist.id else {
+            unreachable!("liked songs have no playlist file");
+        };
+        // staying in the same directory keeps the relative entries valid
+        let file_name = playlist_file_name(&details.name);
+        let new_id = match id.rsplit_once('/') {
+            Some((directory, _)) => format!("{}/{}", directory, file_name),
+            None => file_name,
+        };
+        if new_id != id {
+            let new_path = self.root.join(&new_id);
+            if new_path.exists() {
+                return Err(ProviderError::Other(format!(
+                    "Playlist {} already exists",
+                    new_path.display()
+                )));
+            }
+            fs::rename(&path, &new_path)?;
+        }
+        let entries = read_playlist(&self.root.join(&new_id))?.len();
+        Ok(self.to_playlist(new_id, entries))
+    }
+
+    async fn delete_playlist(&mut self, playlist_id: PlaylistIdWrapper) -> ProviderResult<()> {
+        fs::remove_file(self.playlist_path(&playlist_id)?)?;
+        Ok(())
+    }
+
+    async fn add_playlist_song_with_progress(
+        &mut self,
+        playlist_id: PlaylistIdWrapper,
+        song_id: Vec<String>,
+        _progress: InsertProgress<'_>,
+    ) -> ProviderResult<AddOutcome> {
+        let path = self.playlist_path(&playlist_id)?;
+        let PlaylistIdWrapper::Id(id) = playlist_id else {
+            unreachable!("liked songs have no playlist file");
+        };
+        let mut data = fs::read_to_string(&path)?;
+        if !data.is_empty() && !data.ends_with('\n') {
+            data.push('\n');
+        }
+        let index = self.index.lock().unwrap();
+        for song_id in song_id.iter() {
+            let song = index
+                .get(song_id)
+                .ok_or_else(|| ProviderError::NotFound(format!("Song {}", song_id)))?;
+            let seconds = song
+                .metadata
+                .duration_ms
+                .map(|duration| (duration / 1000) as i64)
+                .unwrap_or(-1);
+            let title = match song.artists.is_empty() {
+                true => song.name.clone(),
+                false => format!("{} - {}", song.artists, song.name),
+            };
+            data.push_str(&format!("#EXTINF:{},{}\n", seconds, title));
+            data.push_str(&relative_location(&id, song_id));
+            data.push('\n');
+        }
+        // the file is written at once, either every song is added or none
+        fs::write(&path, data)?;
+        Ok(AddOutcome::all(song_id))
+    }
+
+    async fn rem_playlist_song(
+        &mut self,
+        playlist_id: PlaylistIdWrapper,
+        song_ids: Vec<String>,
+    ) -> ProviderResult<()> {
+        let path = self.playlist_path(&playlist_id)?;
+        let PlaylistIdWrapper::Id(id) = playlist_id else {
+            unreachable!("liked songs have no playlist file");
+        };
+        let data = fs::read_to_string(&path)?;
+        let mut kept: Vec<&str> = Vec::new();
+        // #EXTINF and other directives belong to the next entry and go away with it
+        let mut pending: Vec<&str> = Vec::new();
+        for line in data.lines() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || (trimmed.starts_with('#') && trimmed != "#EXTM3U") {
+                pending.push(line);
+                continue;
+            }
+            if trimmed.starts_with('#') {
+                kept.push(line);
+                continue;
+            }
+            let entry_id = resolve_location(&id, trimmed).unwrap_or_else(|| trimmed.to_string());
+            if !song_ids.contains(&entry_id) {
+                kept.append(&mut pending);
+                kept.push(line);
+            }
+            pending.clear();
+        }
+        kept.append(&mut pending);
+        let mut data = kept.join("\n");
+        data.push('\n');
+        fs::write(&path, data)?;
+        Ok(())
+    }
+
+    async fn move_playlist_songs(
+        &mut self,
+        playlist_id: PlaylistIdWrapper,
+        moves: Vec<SongMove>,
+    ) -> ProviderResult<()> {
+        let path = self.playlist_path(&playlist_id)?;
+        let data = fs::read_to_string(&path)?;
+        let mut header: Vec<&str> = Vec::new();
+        // entries are moved together with the #EXTINF and other directives above them
+        let mut entries: Vec<Vec<&str>> = Vec::new();
+        let mut pending: Vec<&str> = Vec::new();
+        for line in data.lines() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || (trimmed.starts_with('#') && trimmed != "#EXTM3U") {
+                pending.push(line);
+            } else if trimmed.starts_with('#') {
+                header.push(line);
+            } else {
+                pending.push(line);
+                entries.push(std::mem::take(&mut pending));
+            }
+        }
+        for song_move in moves {
+            song_move.apply(&mut entries)?;
+        }
+        let lines: Vec<&str> = header
+            .into_iter()
+            .chain(entries.into_iter().flatten())
+            .chain(pending)
+            .collect();
+        let mut data = lines.join("\n");
+        data.push('\n');
+        fs::write(&path, data)?;
+        Ok(())
+    }
+
+    async fn search(&mut self, query: String, limit: u32) -> ProviderResult<Vec<RSyncSong>> {
+        let query = matching::normalise(&query);
+        let query_tokens: Vec<&str> = query.split_whitespace().collect();
+        let index = self.index.lock().unwrap();
+        let mut songs: Vec<RSyncSong> = index
+            .values()
+            .filter(|song| {
+                let text = matching::normalise(&format!(
+                    "{} {} {}",
+                    song.name,
+                    song.artists,
+                    song.metadata.album.clone().unwrap_or_default()
+                ));
+                let song_tokens: Vec<&str> = text.split_whitespace().collect();
+                query_tokens.iter().all(|token| song_tokens.contains(token))
+            })
+            .cloned()
+            .collect();
+        songs.sort_by(|a, b| a.id.cmp(&b.id));
+        songs.truncate(limit as usize);
+        Ok(songs)
+    }
+
+    async fn search_list(&mut self, items: Vec<RSyncSong>) -> ProviderResult<Vec<SongMatch>> {
+        let mut matches = Vec::new();
+        for item in items {
+            let found = self
+                .search(matching::search_query(&item), matching::CANDIDATE_LIMIT)
+                .await?;
+            matches.push(matching::best_match(item, found));
+        }
+        Ok(matches)
+    }
+}
+
+/// Collects files with one of `extensions` below `dir`, hidden entries are left out.
+fn collect_files(dir: &Path, extensions: &[&str], files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+        {
+            continue;
+        }
+        if path.is_dir() {
+            collect_files(&path, extensions, files)?;
+        } else if path.extension().is_some_and(|extension| {
+            extensions.contains(&extension.to_string_lossy().to_lowercase().as_str())
+        }) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// File name of a playlist, characters that are not allowed in paths are replaced.
+fn playlist_file_name(name: &str) -> String {
+    let file_name: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c => c,
+        })
+        .collect();
+    format!("{}.{}", file_name.trim(), PLAYLIST_EXTENSION)
+}
+
+/// Path relative to the library root with `/` separators, used as song and playlist id.
+fn song_id(root: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?;
+    Some(
+        relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/"),
+    )
+}
+
+/// Id of the song an entry of `playlist_id` points to, `None` for locations outside of the library.
+fn resolve_location(playlist_id: &str, location: &str) -> Option<String> {
+    let location = location.strip_prefix("file://").unwrap_or(location);
+    if Path::new(location).is_absolute() {
+        return None;
+    }
+    let mut parts: Vec<String> = Path::new(playlist_id)
+        .parent()
+        .map(|parent| {
+            parent
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+    for component in Path::new(&location.replace('\\', "/")).components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy().to_string()),
+            Component::ParentDir => {
+                parts.pop()?;
+            }
+            _ => (),
+        }
+    }
+    Some(parts.join("/"))
+}
+
+/// Location of a song written into `playlist_id`, relative to the directory of the playlist.
+fn relative_location(playlist_id: &str, song_id: &str) -> String {
+    let depth = playlist_id.matches('/').count();
+    format!("{}{}", "../".repeat(depth), song_id)
+}
+
+fn read_playlist(path: &Path) -> io::Result<Vec<PlaylistEntry>> {
+    let mut entries = Vec::new();
+    let mut extinf = None;
+    for line in fs::read_to_string(path)?.lines() {
+        let line = line.trim();
+        if let Some(info) = line.strip_prefix("#EXTINF:") {
+            extinf = Some(info.to_string());
+        } else if !line.is_empty() && !line.starts_with('#') {
+            entries.push(PlaylistEntry {
+                extinf: extinf.take(),
+                location: line.to_string(),
+            });
+        }
+    }
+    Ok(entries)
+}
+
+fn file_title(id: &str) -> String {
+    Path::new(id)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| id.to_string())
+}
+
+fn split_artists(artists: &str) -> Vec<String> {
+    artists
+        .split([';', ','])
+        .map(|artist| artist.trim().to_string())
+        .filter(|artist| !artist.is_empty())
+        .collect()
+}
+
+/// Reads the tags of a library file, files without tags are named after the file.
+fn read_song(root: &Path, id: &str) -> RSyncSong {
+    let path = root.join(id);
+    let (tags, duration_ms) = read_tags(&path).unwrap_or_else(|err| {
+        warn!("Failed to read tags of {}: {}", path.display(), err);
+        (Vec::new(), None)
+    });
+    let tag = |key: StandardTagKey| {
+        tags.iter()
+            .find(|tag| tag.std_key == Some(key))
+            .map(|tag| tag.value.to_string())
+    };
+    let artists: Vec<String> = tags
+        .iter()
+        .filter(|tag| tag.std_key == Some(StandardTagKey::Artist))
+        .flat_map(|tag| split_artists(&tag.value.to_string()))
+        .collect();
+    RSyncSong {
+        artists: artists.join(", "),
+        url: format!("file://{}", path.display()),
+        id: id.to_string(),
+        name: tag(StandardTagKey::TrackTitle).unwrap_or_else(|| file_title(id)),
+        metadata: RSyncSongMetadata {
+            isrc: tag(StandardTagKey::IdentIsrc),
+            duration_ms,
+            album: tag(StandardTagKey::Album),
+            artists,
+            added_at: None,
+        },
+        r#type: RSyncSongProviderData::Local,
+    }
+}
+
+fn read_tags(path: &Path) -> Result<(Vec<Tag>, Option<u32>), symphonia::core::errors::Error> {
+    let source = MediaSourceStream::new(Box::new(File::open(path)?), Default::default());
+    let mut hint = Hint::new();
+    if let Some(extension) = path.extension() {
+        hint.with_extension(&extension.to_string_lossy());
+    }
+    let mut probed = symphonia::default::get_probe().format(
+        &hint,
+        source,
+        &FormatOptions::default(),
+        &MetadataOptions::default(),
+    )?;
+
+    // ID3 tags are read while probing, container tags (vorbis comments, mp4 atoms) by the reader
+    let mut tags: Vec<Tag> = Vec::new();
+    if let Some(revision) = probed.metadata.get().as_ref().and_then(|m| m.current()) {
+        tags.extend(revision.tags().iter().cloned());
+    }
+    if let Some(revision) = probed.format.metadata().current() {
+        tags.extend(revision.tags().iter().cloned());
+    }
+
+    let duration_ms = probed.format.default_track().and_then(|track| {
+        let frames = track.codec_params.n_frames?;
+        let time = track.codec_params.time_base?.calc_time(frames);
+        Some((time.seconds * 1000 + (time.frac * 1000.0) as u64) as u32)
+    });
+    Ok((tags, duration_ms))
+}
@@ -0,0 +1,456 @@
+use std::{
+    env,
+    fmt::Debug,
+    fs, mem,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    matching::{self, SongMatch},
+    reorder::SongMove,
+    types::music_types::{
+        PlaylistDetails, PlaylistIdWrapper, RSyncPlaylistItem, RSyncPlaylistItemProviderData,
+        RSyncSong, RSyncSongMetadata, RSyncSongProviderData, RSyncSongProviderDataYoutube,
+    },
+};
+
+use super::{
+    paging::PageStream,
+    provider_error::{ProviderError, ProviderResult},
+    provider_traits::{APIProvider, AddOutcome, InsertProgress},
+    quota::Quota,
+};
+
+/// Items per page of the listings, small so that fixtures page like a real service.
+const PAGE_SIZE: usize = 20;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct MockSong {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub artists: String,
+    #[serde(default)]
+    pub duration_ms: Option<u32>,
+    #[serde(default)]
+    pub isrc: Option<String>,
+    #[serde(default)]
+    pub album: Option<String>,
+    #[serde(default)]
+    pub added_at: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct MockPlaylist {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub public: bool,
+    #[serde(default = "default_owned")]
+    pub owned: bool,
+    #[serde(default)]
+    pub songs: Vec<MockSong>,
+}
+
+fn default_owned() -> bool {
+    true
+}
+
+/// Content of a mock provider fixture file.
+///
+/// `catalog` holds songs that are only reachable through `search`, playlist and liked
+/// songs are searchable as well.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct MockLibrary {
+    #[serde(default)]
+    pub playlists: Vec<MockPlaylist>,
+    #[serde(default)]
+    pub liked: Vec<MockSong>,
+    #[serde(default)]
+    pub catalog: Vec<MockSong>,
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    library: MockLibrary,
+    next_id: u32,
+}
+
+/// Offline provider backed by an in-memory library.
+///
+/// Clones share the same library, so changes made from a spawned task are visible to
+/// the column that owns the provider. Songs inside playlists carry a playlist entry id
+/// the same way YouTube playlist items do, which makes removal work from either column.
+#[derive(Clone, Debug, Default)]
+pub struct MockProvider {
+    state: Arc<Mutex<MockState>>,
+    /// Budget the jobs check before a transfer, requests of the mock are not charged.
+    quota: Option<Quota>,
+}
+
+impl MockProvider {
+    pub fn from_library(library: MockLibrary) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(MockState {
+                library,
+                next_id: 0,
+            })),
+            quota: None,
+        }
+    }
+
+    pub fn with_quota(mut self, quota: Quota) -> Self {
+        self.quota = Some(quota);
+        self
+    }
+
+    pub fn from_json(data: &str) -> serde_json::Result<Self> {
+        Ok(Self::from_library(serde_json::from_str(data)?))
+    }
+
+    pub fn from_fixture(path: impl AsRef<Path>) -> ProviderResult<Self> {
+        let path = path.as_ref();
+        let data = fs::read_to_string(path).map_err(|err| {
+            ProviderError::NotFound(format!(
+                "Failed to read mock fixture {}: {}",
+                path.display(),
+                err
+            ))
+        })?;
+        Self::from_json(&data).map_err(|err| {
+            ProviderError::Other(format!("Invalid mock fixture {}: {}", path.display(), err))
+        })
+    }
+
+    /// Returns a copy of the current library, useful for asserting on the result of a transfer.
+    pub fn snapshot(&self) -> MockLibrary {
+        self.state.lock().unwrap().library.clone()
+    }
+
+    fn to_song(song: &MockSong, entry_id: Option<String>) -> RSyncSong {
+        RSyncSong {
+            artists: song.artists.clone(),
+            url: format!("mock://song/{}", song.id),
+            id: song.id.clone(),
+            name: song.name.clone(),
+            metadata: RSyncSongMetadata {
+                isrc: song.isrc.clone(),
+                duration_ms: song.duration_ms,
+                album: song.album.clone(),
+                artists: song
+                    .artists
+                    .split(',')
+                    .map(|artist| artist.trim().to_string())
+                    .filter(|artist| !artist.is_empty())
+                    .collect(),
+                added_at: song.added_at.clone(),
+            },
+            r#type: RSyncSongProviderData::Youtube(RSyncSongProviderDataYoutube {
+                playlist_id: entry_id,
+            }),
+        }
+    }
+
+    fn to_playlist(playlist: &MockPlaylist) -> RSyncPlaylistItem {
+        RSyncPlaylistItem {
+            collaborative: false,
+            description: playlist.description.clone(),
+            url: format!("mock://playlist/{}", playlist.id),
+            id: PlaylistIdWrapper::Id(playlist.id.clone()),
+            name: playlist.name.clone(),
+            owned: playlist.owned,
+            public: playlist.public,
+            tracks: playlist.songs.len() as u32,
+            r#type: RSyncPlaylistItemProviderData::Youtube,
+        }
+    }
+
+    fn entry_id(playlist_id: &str, song: &MockSong) -> String {
+        format!("{}:{}", playlist_id, song.id)
+    }
+
+    fn tokens(text: &str) -> Vec<String> {
+        text.split(|c: char| !c.is_alphanumeric())
+            .filter(|token| !token.is_empty())
+            .map(|token| token.to_lowercase())
+            .collect()
+    }
+
+    fn find_song(library: &MockLibrary, song_id: &str) -> Option<MockSong> {
+        library
+            .catalog
+            .iter()
+            .chain(library.liked.iter())
+            .chain(library.playlists.iter().flat_map(|p| p.songs.iter()))
+            .find(|song| song.id == song_id)
+            .cloned()
+    }
+}
+
+impl MockProvider {
+    /// Splits the listing into pages of [`PAGE_SIZE`] items, an error is the only page.
+    fn pages<T: Send + 'static>(items: ProviderResult<Vec<T>>) -> PageStream<'static, T> {
+        let pages: Vec<ProviderResult<Vec<T>>> = match items {
+            Ok(mut items) => {
+                let mut pages = Vec::new();
+                while !items.is_empty() {
+                    let rest = items.split_off(items.len().min(PAGE_SIZE));
+                    pages.push(Ok(mem::replace(&mut items, rest)));
+                }
+                pages
+            }
+            Err(error) => vec![Err(error)],
+        };
+        stream::iter(pages).boxed()
+    }
+
+    /// Provider loaded from `RSYNC_MOCK_FIXTURE`, empty when it is not set.
+    pub async fn new() -> ProviderResult<Self> {
+        match env::var("RSYNC_MOCK_FIXTURE")
+            .ok()
+            .filter(|f| !f.is_empty())
+        {
+            Some(path) => Self::from_fixture(path),
+            None => Ok(Self::default()),
+        }
+    }
+}
+
+impl APIProvider for MockProvider {
+    fn playlist_pages(&mut self) -> PageStream<'_, RSyncPlaylistItem> {
+        let state = self.state.lock().unwrap();
+        let mut playlists = vec![RSyncPlaylistItem {
+            collaborative: false,
+            description: Some("Favourite playlist".into()),
+            url: "mock://liked".into(),
+            id: PlaylistIdWrapper::Liked,
+            name: "Favorites".into(),
+            owned: true,
+            public: false,
+            tracks: state.library.liked.len() as u32,
+            r#type: RSyncPlaylistItemProviderData::Youtube,
+        }];
+        for playlist in state.library.playlists.iter() {
+            playlists.push(Self::to_playlist(playlist));
+        }
+        Self::pages(Ok(playlists))
+    }
+
+    fn playlist_song_pages(&mut self, playlist_id: PlaylistIdWrapper) -> PageStream<'_, RSyncSong> {
+        let state = self.state.lock().unwrap();
+        let songs = match playlist_id {
+            PlaylistIdWrapper::Liked => Ok(state
+                .library
+                .liked
+                .iter()
+                .map(|song| Self::to_song(song, Some(Self::entry_id("liked", song))))
+                .collect()),
+            PlaylistIdWrapper::Id(playlist_id) => state
+                .library
+                .playlists
+                .iter()
+                .find(|playlist| playlist.id == playlist_id)
+                .map(|playlist| {
+                    playlist
+                        .songs
+                        .iter()
+                        .map(|song| Self::to_song(song, Some(Self::entry_id(&playlist.id, song))))
+                        .collect()
+                })
+                .ok_or_else(|| ProviderError::NotFound(format!("Playlist {}", playlist_id))),
+        };
+        Self::pages(songs)
+    }
+
+    fn playlist_data(&self) -> RSyncPlaylistItemProviderData {
+        RSyncPlaylistItemProviderData::Youtube
+    }
+
+    async fn create_playlist(
+        &mut self,
+        playlist_name: String,
+    ) -> ProviderResult<RSyncPlaylistItem> {
+        let mut state = self.state.lock().unwrap();
+        state.next_id += 1;
+        let playlist = MockPlaylist {
+            id: format!("mock-playlist-{}", state.next_id),
+            name: playlist_name,
+            description: None,
+            public: false,
+            owned: true,
+            songs: Vec::new(),
+        };
+        state.library.playlists.push(playlist.clone());
+        Ok(Self::to_playlist(&playlist))
+    }
+
+    async fn update_playlist(
+        &mut self,
+        playlist: RSyncPlaylistItem,
+        details: PlaylistDetails,
+    ) -> ProviderResult<RSyncPlaylistItem> {
+        let PlaylistIdWrapper::Id(playlist_id) = playlist.id else {
+            return Err(ProviderError::Permission(
+                "Favorites can not be edited".into(),
+            ));
+        };
+        let mut state = self.state.lock().unwrap();
+        let playlist = state
+            .library
+            .playlists
+            .iter_mut()
+            .find(|playlist| playlist.id == playlist_id)
+            .ok_or_else(|| ProviderError::NotFound(format!("Playlist {}", playlist_id)))?;
+        playlist.name = details.name;
+        playlist.description = details.description;
+        playlist.public = details.public;
+        Ok(Self::to_playlist(playlist))
+    }
+
+    async fn delete_playlist(&mut self, playlist_id: PlaylistIdWrapper) -> ProviderResult<()> {
+        let PlaylistIdWrapper::Id(playlist_id) = playlist_id else {
+            return Err(ProviderError::Permission(
+                "Favorites can not be deleted".into(),
+            ));
+        };
+        let mut state = self.state.lock().unwrap();
+        let playlists = &mut state.library.playlists;
+        let count = playlists.len();
+        playlists.retain(|playlist| playlist.id != playlist_id);
+        match playlists.len() < count {
+            true => Ok(()),
+            false => Err(ProviderError::NotFound(format!("Playlist {}", playlist_id))),
+        }
+    }
+
+    async fn add_playlist_song_with_progress(
+        &mut self,
+        playlist_id: PlaylistIdWrapper,
+        song_id: Vec<String>,
+        _progress: InsertProgress<'_>,
+    ) -> ProviderResult<AddOutcome> {
+        let mut state = self.state.lock().unwrap();
+        let songs: Vec<MockSong> = song_id
+            .iter()
+            .filter_map(|id| Self::find_song(&state.library, id))
+            .collect();
+        let added = songs.iter().map(|song| song.id.clone()).collect();
+        match playlist_id {
+            PlaylistIdWrapper::Liked => state.library.liked.extend(songs),
+            PlaylistIdWrapper::Id(playlist_id) => state
+                .library
+                .playlists
+                .iter_mut()
+                .find(|playlist| playlist.id == playlist_id)
+                .ok_or_else(|| ProviderError::NotFound(format!("Playlist {}", playlist_id)))?
+                .songs
+                .extend(songs),
+        }
+        Ok(AddOutcome::all(added))
+    }
+
+    async fn rem_playlist_song(
+        &mut self,
+        playlist_id: PlaylistIdWrapper,
+        song_ids: Vec<String>,
+    ) -> ProviderResult<()> {
+        let mut state = self.state.lock().unwrap();
+        let (entry_prefix, songs) = match playlist_id {
+            PlaylistIdWrapper::Liked => ("liked".to_string(), &mut state.library.liked),
+            PlaylistIdWrapper::Id(playlist_id) => {
+                match state
+                    .library
+                    .playlists
+                    .iter_mut()
+                    .find(|playlist| playlist.id == playlist_id)
+                {
+                    Some(playlist) => (playlist.id.clone(), &mut playlist.songs),
+                    None => {
+                        return Err(ProviderError::NotFound(format!("Playlist {}", playlist_id)))
+                    }
+                }
+            }
+        };
+        songs.retain(|song| {
+            !song_ids.contains(&song.id) && !song_ids.contains(&Self::entry_id(&entry_prefix, song))
+        });
+        Ok(())
+    }
+
+    async fn move_playlist_songs(
+        &mut self,
+        playlist_id: PlaylistIdWrapper,
+        moves: Vec<SongMove>,
+    ) -> ProviderResult<()> {
+        let mut state = self.state.lock().unwrap();
+        let songs = match playlist_id {
+            PlaylistIdWrapper::Liked => &mut state.library.liked,
+            PlaylistIdWrapper::Id(playlist_id) => {
+                match state
+                    .library
+                    .playlists
+                    .iter_mut()
+                    .find(|playlist| playlist.id == playlist_id)
+                {
+                    Some(playlist) => &mut playlist.songs,
+                    None => {
+                        return Err(ProviderError::NotFound(format!("Playlist {}", playlist_id)))
+                    }
+                }
+            }
+        };
+        // validated up front, a failing move leaves the playlist untouched
+        let mut reordered = songs.clone();
+        for song_move in moves {
+            song_move.apply(&mut reordered)?;
+        }
+        *songs = reordered;
+        Ok(())
+    }
+
+    async fn search(&mut self, query: String, limit: u32) -> ProviderResult<Vec<RSyncSong>> {
+        let state = self.state.lock().unwrap();
+        let query_tokens = Self::tokens(&query);
+        let mut songs: Vec<RSyncSong> = Vec::new();
+        let candidates = state
+            .library
+            .catalog
+            .iter()
+            .chain(state.library.liked.iter())
+            .chain(state.library.playlists.iter().flat_map(|p| p.songs.iter()));
+        for song in candidates {
+            if songs.len() as u32 >= limit {
+                break;
+            }
+            if songs.iter().any(|found| found.id == song.id) {
+                continue;
+            }
+            let song_tokens = Self::tokens(&format!("{} {}", song.name, song.artists));
+            if query_tokens.iter().all(|token| song_tokens.contains(token)) {
+                songs.push(Self::to_song(song, None));
+            }
+        }
+        Ok(songs)
+    }
+
+    async fn search_list(&mut self, items: Vec<RSyncSong>) -> ProviderResult<Vec<SongMatch>> {
+        let mut matches = Vec::new();
+        for item in items {
+            let found = self
+                .search(matching::search_query(&item), matching::CANDIDATE_LIMIT)
+                .await?;
+            matches.push(matching::best_match(item, found));
+        }
+        Ok(matches)
+    }
+
+    fn quota(&self) -> Option<Quota> {
+        self.quota.clone()
+    }
+}
@@ -1,3 +1,12 @@
+pub mod backoff;
+pub mod dry_run;
+pub mod journal;
+pub mod local_provider;
+pub mod mock_provider;
+pub mod paging;
+pub mod provider_error;
 pub mod provider_traits;
+pub mod quota;
+pub mod registry;
 pub mod spotify_provider;
 pub mod youtube_provider;
@@ -0,0 +1,157 @@
+use std::future::Future;
+
+use futures::stream::{self, BoxStream, StreamExt};
+
+use super::provider_error::ProviderResult;
+
+/// Pages of a listing in the order the service returns them, see
+/// [`APIProvider::playlist_pages`](super::provider_traits::APIProvider::playlist_pages).
+///
+/// Dropping the stream stops the paging, no request is sent for the pages not polled.
+pub type PageStream<'a, T> = BoxStream<'a, ProviderResult<Vec<T>>>;
+
+/// Listing that is loaded at once, e.g. the files of the local library.
+pub fn single_page<'a, T: Send + 'a>(
+    page: impl Future<Output = ProviderResult<Vec<T>>> + Send + 'a,
+) -> PageStream<'a, T> {
+    stream::once(page).boxed()
+}
+
+/// Pages of an offset based listing.
+///
+/// `fetch` loads `limit` items starting at the offset and returns them with the total
+/// number of items, the stream ends after the page that reaches the total.
+pub fn offset_pages<'a, T, F, Fut>(limit: u32, mut fetch: F) -> PageStream<'a, T>
+where
+    T: Send + 'a,
+    F: FnMut(u32, u32) -> Fut + Send + 'a,
+    Fut: Future<Output = ProviderResult<(Vec<T>, u32)>> + Send + 'a,
+{
+    stream::try_unfold((0, None), move |(offset, total): (u32, Option<u32>)| {
+        let page = match total {
+            Some(total) if offset >= total => None,
+            _ => Some(fetch(offset, limit)),
+        };
+        async move {
+            let Some(page) = page else {
+                return Ok(None);
+            };
+            let (items, total) = page.await?;
+            Ok(Some((items, (offset + limit, Some(total)))))
+        }
+    })
+    .boxed()
+}
+
+/// Pages of a listing that hands out a token for the next page.
+///
+/// `fetch` loads the page of the token, `None` for the first one, and returns it with the
+/// token of the next page. The stream ends after the page without a next token.
+pub fn token_pages<'a, T, F, Fut>(mut fetch: F) -> PageStream<'a, T>
+where
+    T: Send + 'a,
+    F: FnMut(Option<String>) -> Fut + Send + 'a,
+    Fut: Future<Output = ProviderResult<(Vec<T>, Option<String>)>> + Send + 'a,
+{
+    stream::try_unfold(Some(None), move |token: Option<Option<String>>| {
+        let page = token.map(&mut fetch);
+        async move {
+            let Some(page) = page else {
+                return Ok(None);
+            };
+            let (items, next) = page.await?;
+            Ok(Some((items, next.map(Some))))
+        }
+    })
+    .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use futures::TryStreamExt;
+
+    use super::*;
+
+    /// Offset listing of `total` numbers, `calls` records the requested offsets.
+    fn numbers(total: u32, calls: &Mutex<Vec<u32>>) -> PageStream<'_, u32> {
+        offset_pages(20, move |offset, limit| {
+            calls.lock().unwrap().push(offset);
+            async move { Ok(((offset..total.min(offset + limit)).collect(), total)) }
+        })
+    }
+
+    #[tokio::test]
+    async fn offset_pages_end_with_the_short_page() {
+        let calls = Mutex::new(Vec::new());
+        let pages: Vec<Vec<u32>> = numbers(45, &calls).try_collect().await.unwrap();
+
+        let sizes: Vec<usize> = pages.iter().map(Vec::len).collect();
+        assert_eq!(sizes, [20, 20, 5]);
+        assert_eq!(pages.concat(), (0..45).collect::<Vec<_>>());
+        assert_eq!(*calls.lock().unwrap(), [0, 20, 40]);
+    }
+
+    #[tokio::test]
+    async fn offset_pages_stop_after_the_full_last_page() {
+        let calls = Mutex::new(Vec::new());
+        let pages: Vec<Vec<u32>> = numbers(40, &calls).try_collect().await.unwrap();
+
+        assert_eq!(pages.concat(), (0..40).collect::<Vec<_>>());
+        assert_eq!(*calls.lock().unwrap(), [0, 20]);
+    }
+
+    #[tokio::test]
+    async fn empty_offset_listing_is_one_empty_page() {
+        let calls = Mutex::new(Vec::new());
+        let pages: Vec<Vec<u32>> = numbers(0, &calls).try_collect().await.unwrap();
+
+        assert_eq!(pages, [Vec::<u32>::new()]);
+        assert_eq!(*calls.lock().unwrap(), [0]);
+    }
+
+    #[tokio::test]
+    async fn token_pages_end_without_a_next_token() {
+        let calls = Mutex::new(Vec::new());
+        let pages: Vec<Vec<&str>> = token_pages(|token: Option<String>| {
+            calls.lock().unwrap().push(token.clone());
+            async move {
+                Ok(match token.as_deref() {
+                    None => (vec!["a", "b"], Some("second".to_string())),
+                    Some("second") => (vec!["c"], Some("third".to_string())),
+                    _ => (vec![], None),
+                })
+            }
+        })
+        .try_collect()
+        .await
+        .unwrap();
+
+        assert_eq!(pages, [vec!["a", "b"], vec!["c"], vec![]]);
+        assert_eq!(
+            *calls.lock().unwrap(),
+            [None, Some("second".to_string()), Some("third".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn token_pages_with_a_single_page() {
+        let pages: Vec<Vec<u32>> = token_pages(|_| async { Ok((vec![1, 2], None)) })
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(pages, [vec![1, 2]]);
+    }
+
+    #[tokio::test]
+    async fn single_page_is_the_whole_listing() {
+        let pages: Vec<Vec<u32>> = single_page(async { Ok(vec![1, 2, 3]) })
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(pages, [vec![1, 2, 3]]);
+    }
+}
@@ -0,0 +1,68 @@
+use std::fmt::Display;
+
+/// Result type returned by every [`APIProvider`](super::provider_traits::APIProvider) call.
+pub type ProviderResult<T> = Result<T, ProviderError>;
+
+/// Failure of a provider call, grouped by what the user can do about it.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ProviderError {
+    /// Missing credentials, revoked or expired token.
+    Auth(String),
+    /// Too many requests or the daily quota is exhausted.
+    RateLimited(String),
+    /// Playlist or song does not exist (anymore).
+    NotFound(String),
+    /// The account is not allowed to modify the resource.
+    Permission(String),
+    /// The service answered, but without the data we rely on.
+    MalformedResponse(String),
+    /// Network failures and anything the service did not explain.
+    Other(String),
+}
+
+impl ProviderError {
+    /// Maps an HTTP status code of a failed request to the matching variant.
+    pub fn from_status(status: u16, message: String) -> Self {
+        match status {
+            401 => ProviderError::Auth(message),
+            403 => ProviderError::Permission(message),
+            404 => ProviderError::NotFound(message),
+            429 => ProviderError::RateLimited(message),
+            _ => ProviderError::Other(format!("HTTP {}: {}", status, message)),
+        }
+    }
+
+    pub fn malformed(what: &str) -> Self {
+        ProviderError::MalformedResponse(format!("Missing {} in response", what))
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            ProviderError::Auth(_) => "Authorization failed",
+            ProviderError::RateLimited(_) => "Rate limit reached",
+            ProviderError::NotFound(_) => "Not found",
+            ProviderError::Permission(_) => "Missing permissions",
+            ProviderError::MalformedResponse(_) => "Unexpected response",
+            ProviderError::Other(_) => "Request failed",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            ProviderError::Auth(message)
+            | ProviderError::RateLimited(message)
+            | ProviderError::NotFound(message)
+            | ProviderError::Permission(message)
+            | ProviderError::MalformedResponse(message)
+            | ProviderError::Other(message) => message,
+        }
+    }
+}
+
+impl Display for ProviderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.title(), self.message())
+    }
+}
+
+impl std::error::Error for ProviderError {}
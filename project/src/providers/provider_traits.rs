@@ -1,42 +1,179 @@
-use std::future::Future;
+use std::{fmt::Debug, future::Future};
 
-use tokio::sync::mpsc;
+use futures::TryStreamExt;
 
 use crate::{
-    event::Event,
-    types::music_types::{PlaylistIdWrapper, RSyncPlaylistItem, RSyncSong},
+    matching::SongMatch,
+    providers::{
+        paging::PageStream,
+        provider_error::{ProviderError, ProviderResult},
+        quota::Quota,
+    },
+    reorder::SongMove,
+    types::music_types::{
+        PlaylistDetails, PlaylistIdWrapper, RSyncPlaylistItem, RSyncPlaylistItemProviderData,
+        RSyncSong,
+    },
 };
 
+/// Songs a call of [`APIProvider::add_playlist_song`] inserted before it stopped.
+///
+/// Songs are sent in several requests, so a failure can leave the songs sent before it added.
+#[derive(Debug, Default)]
+pub struct AddOutcome {
+    /// Ids of the inserted songs, in the requested order.
+    pub added: Vec<String>,
+    /// Error that stopped the remaining songs, `None` if every song was inserted.
+    pub error: Option<ProviderError>,
+}
+
+impl AddOutcome {
+    /// Every requested song was inserted.
+    pub fn all(song_ids: Vec<String>) -> Self {
+        Self {
+            added: song_ids,
+            error: None,
+        }
+    }
+
+    /// Keeps the songs inserted before `error`, an error right away adds none.
+    pub fn partial(added: Vec<String>, error: ProviderError) -> Self {
+        Self {
+            added,
+            error: Some(error),
+        }
+    }
+
+    /// The error for callers that do not track single songs.
+    pub fn into_result(self) -> ProviderResult<()> {
+        match self.error {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Receives the number of songs an insert added so far.
+pub type InsertProgress<'a> = &'a (dyn Fn(usize) + Send + Sync);
+
+impl From<ProviderResult<AddOutcome>> for AddOutcome {
+    fn from(result: ProviderResult<AddOutcome>) -> Self {
+        result.unwrap_or_else(|err| Self::partial(Vec::new(), err))
+    }
+}
+
 pub trait APIProviderBuilder {
-    fn authorize(&mut self) -> impl Future<Output = impl APIProvider>;
+    fn authorize(&mut self) -> impl Future<Output = ProviderResult<impl APIProvider>>;
 }
 
-pub trait APIProvider {
-    fn new() -> impl Future<Output = impl APIProvider>;
+/// Common interface of every music service.
+///
+/// Providers are cloned into background tasks by the columns, so clones must share
+/// the underlying session and the returned futures must be `Send`. Every call is fallible,
+/// errors are reported to the user instead of tearing down the terminal.
+pub trait APIProvider: Clone + Debug + Send + 'static {
+    /// Playlists of the account page by page, Favorites come first.
+    fn playlist_pages(&mut self) -> PageStream<'_, RSyncPlaylistItem>;
+
+    /// Songs of a playlist page by page in playlist order.
+    fn playlist_song_pages(&mut self, playlist_id: PlaylistIdWrapper) -> PageStream<'_, RSyncSong>;
 
-    fn get_playlists(&mut self) -> impl Future<Output = Vec<RSyncPlaylistItem>>;
+    /// Every playlist of the account, see [`APIProvider::playlist_pages`].
+    fn get_playlists(
+        &mut self,
+    ) -> impl Future<Output = ProviderResult<Vec<RSyncPlaylistItem>>> + Send {
+        self.playlist_pages().try_concat()
+    }
 
+    /// Every song of a playlist, see [`APIProvider::playlist_song_pages`].
     fn get_playlist_songs(
         &mut self,
         playlist_id: PlaylistIdWrapper,
-        event_sender: Option<(mpsc::UnboundedSender<Event>, u128)>,
-    ) -> impl Future<Output = Vec<RSyncSong>>;
+    ) -> impl Future<Output = ProviderResult<Vec<RSyncSong>>> + Send {
+        self.playlist_song_pages(playlist_id).try_concat()
+    }
+
+    /// Provider data of the playlists of the service, e.g. for a playlist that was not created.
+    fn playlist_data(&self) -> RSyncPlaylistItemProviderData;
+
+    /// Creates a new private playlist and returns it.
+    fn create_playlist(
+        &mut self,
+        playlist_name: String,
+    ) -> impl Future<Output = ProviderResult<RSyncPlaylistItem>> + Send;
+
+    /// Changes the name, description and visibility of an owned playlist, returns the updated playlist.
+    fn update_playlist(
+        &mut self,
+        playlist: RSyncPlaylistItem,
+        details: PlaylistDetails,
+    ) -> impl Future<Output = ProviderResult<RSyncPlaylistItem>> + Send;
+
+    /// Deletes an owned playlist, on Spotify it is unfollowed.
+    fn delete_playlist(
+        &mut self,
+        playlist_id: PlaylistIdWrapper,
+    ) -> impl Future<Output = ProviderResult<()>> + Send;
 
-    fn create_playlist(&mut self, playlist_name: String) -> impl Future<Output = ()>;
+    /// Brings back a deleted playlist unchanged, `None` if the service can only recreate it.
+    ///
+    /// Spotify keeps unfollowed playlists, following one again restores it with its songs.
+    fn restore_playlist(
+        &mut self,
+        _playlist: RSyncPlaylistItem,
+    ) -> impl Future<Output = ProviderResult<Option<RSyncPlaylistItem>>> + Send {
+        async { Ok(None) }
+    }
 
+    /// Inserts the songs in the given order, an error returned instead of an outcome added none.
     fn add_playlist_song(
         &mut self,
         playlist_id: PlaylistIdWrapper,
         song_id: Vec<String>,
-    ) -> impl Future<Output = ()>;
+    ) -> impl Future<Output = ProviderResult<AddOutcome>> + Send {
+        async move {
+            self.add_playlist_song_with_progress(playlist_id, song_id, &|_| ())
+                .await
+        }
+    }
+
+    /// Same as [`APIProvider::add_playlist_song`], `progress` receives the count of the
+    /// inserted songs while they are inserted, a service that inserts them at once may not
+    /// report any.
+    fn add_playlist_song_with_progress(
+        &mut self,
+        playlist_id: PlaylistIdWrapper,
+        song_id: Vec<String>,
+        progress: InsertProgress<'_>,
+    ) -> impl Future<Output = ProviderResult<AddOutcome>> + Send;
 
     fn rem_playlist_song(
         &mut self,
         playlist_id: PlaylistIdWrapper,
         song_ids: Vec<String>,
-    ) -> impl Future<Output = ()>;
+    ) -> impl Future<Output = ProviderResult<()>> + Send;
+
+    /// Applies the `moves` one after another, each refers to the order left by the previous one.
+    fn move_playlist_songs(
+        &mut self,
+        playlist_id: PlaylistIdWrapper,
+        moves: Vec<SongMove>,
+    ) -> impl Future<Output = ProviderResult<()>> + Send;
 
-    fn search(&mut self, query: String, limit: u32) -> impl Future<Output = Vec<RSyncSong>>;
+    fn search(
+        &mut self,
+        query: String,
+        limit: u32,
+    ) -> impl Future<Output = ProviderResult<Vec<RSyncSong>>> + Send;
+
+    /// Searches every song and scores the results, one match per song in the same order.
+    fn search_list(
+        &mut self,
+        items: Vec<RSyncSong>,
+    ) -> impl Future<Output = ProviderResult<Vec<SongMatch>>> + Send;
 
-    fn search_list(&mut self, items: Vec<RSyncSong>) -> impl Future<Output = Vec<RSyncSong>>;
+    /// Daily request budget of the service, `None` if it has none worth tracking.
+    fn quota(&self) -> Option<Quota> {
+        None
+    }
 }
@@ -0,0 +1,172 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use chrono::{Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use super::provider_error::{ProviderError, ProviderResult};
+
+/// Quota of a new YouTube Data API project, unless `YOUTUBE_DAILY_QUOTA` is set.
+const DEFAULT_DAILY_LIMIT: u32 = 10_000;
+
+/// Units of a list request, e.g. a page of playlist items or the details of 50 videos.
+pub const LIST_COST: u32 = 1;
+/// Units of an insert, update, delete or rating.
+pub const WRITE_COST: u32 = 50;
+/// Units of a search request.
+pub const SEARCH_COST: u32 = 100;
+
+/// Items of a list request, the most the API returns per page.
+pub const PAGE_SIZE: u32 = 50;
+
+/// Estimated units of searching `songs` songs and inserting the found ones, a search also
+/// loads the details of the found videos.
+pub fn transfer_cost(songs: usize) -> u32 {
+    songs as u32 * (SEARCH_COST + LIST_COST) + insert_cost(songs)
+}
+
+/// Estimated units of inserting `songs` songs that were already searched.
+pub fn insert_cost(songs: usize) -> u32 {
+    songs as u32 * WRITE_COST
+}
+
+/// Day the quota belongs to, it is reset at midnight pacific time.
+pub fn quota_day() -> NaiveDate {
+    // standard time, during daylight saving time the reset is an hour later than assumed
+    (Utc::now() - Duration::hours(8)).date_naive()
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct QuotaUsage {
+    /// `YYYY-MM-DD` of the day the units were used on.
+    day: String,
+    used: u32,
+}
+
+/// Whether an operation fits into the remaining quota.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum QuotaCheck {
+    Fits,
+    /// Fits after the next reset of the quota.
+    Later,
+    /// Costs more than the whole daily quota.
+    Never,
+}
+
+/// Daily request budget of a service, shared by the clones of the provider and written to
+/// disk after each request so restarts keep counting.
+///
+/// Only the requests of this app are counted, other clients of the same project are not.
+#[derive(Clone, Debug)]
+pub struct Quota {
+    limit: u32,
+    usage: Arc<Mutex<QuotaUsage>>,
+    /// `None` keeps the usage in memory only.
+    path: Option<PathBuf>,
+}
+
+impl Quota {
+    /// Location of the usage, `RSYNC_YOUTUBE_QUOTA` or `youtube_quota.json` in the current directory.
+    pub fn default_path() -> PathBuf {
+        env::var_os("RSYNC_YOUTUBE_QUOTA")
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("youtube_quota.json"))
+    }
+
+    /// Units per day, `YOUTUBE_DAILY_QUOTA` or the quota of a new project.
+    pub fn daily_limit() -> u32 {
+        env::var("YOUTUBE_DAILY_QUOTA")
+            .ok()
+            .and_then(|limit| limit.parse().ok())
+            .unwrap_or(DEFAULT_DAILY_LIMIT)
+    }
+
+    pub fn new(limit: u32) -> Self {
+        Self {
+            limit,
+            usage: Arc::default(),
+            path: None,
+        }
+    }
+
+    /// Loads the usage from `path`, a missing or invalid file starts with the full quota.
+    pub fn load(path: &Path, limit: u32) -> Self {
+        let usage = match fs::read_to_string(path) {
+            Ok(data) => serde_json::from_str(&data).unwrap_or_else(|err| {
+                warn!("Ignoring invalid quota usage {}: {}", path.display(), err);
+                QuotaUsage::default()
+            }),
+            Err(_) => QuotaUsage::default(),
+        };
+        Self {
+            limit,
+            usage: Arc::new(Mutex::new(usage)),
+            path: Some(path.to_path_buf()),
+        }
+    }
+
+    fn save(&self, usage: &QuotaUsage) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let result = serde_json::to_string_pretty(usage)
+            .map_err(std::io::Error::from)
+            .and_then(|data| fs::write(path, data));
+        if let Err(err) = result {
+            warn!("Failed to save quota usage {}: {}", path.display(), err);
+        }
+    }
+
+    /// Usage of today, the units of an earlier day are dropped.
+    fn today(usage: &mut QuotaUsage) -> &mut QuotaUsage {
+        let day = quota_day().to_string();
+        if usage.day != day {
+            *usage = QuotaUsage { day, used: 0 };
+        }
+        usage
+    }
+
+    pub fn limit(&self) -> u32 {
+        self.limit
+    }
+
+    pub fn remaining(&self) -> u32 {
+        let mut usage = self.usage.lock().unwrap();
+        self.limit.saturating_sub(Self::today(&mut usage).used)
+    }
+
+    pub fn check(&self, units: u32) -> QuotaCheck {
+        match units {
+            units if units > self.limit => QuotaCheck::Never,
+            units if units > self.remaining() => QuotaCheck::Later,
+            _ => QuotaCheck::Fits,
+        }
+    }
+
+    /// Counts a request, it is refused without being sent if the quota does not cover it.
+    pub fn charge(&self, units: u32) -> ProviderResult<()> {
+        let mut usage = self.usage.lock().unwrap();
+        let today = Self::today(&mut usage);
+        if today.used + units > self.limit {
+            return Err(ProviderError::RateLimited(format!(
+                "The request needs {} quota units, {} of {} are left today",
+                units,
+                self.limit.saturating_sub(today.used),
+                self.limit
+            )));
+        }
+        today.used += units;
+        self.save(&usage);
+        Ok(())
+    }
+
+    /// Remaining units for the status bar.
+    pub fn summary(&self) -> String {
+        format!("{}/{}", self.remaining(), self.limit)
+    }
+}
@@ -0,0 +1,266 @@
+use std::{env, fmt::Display};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    matching::SongMatch,
+    reorder::SongMove,
+    types::music_types::{
+        PlaylistDetails, PlaylistIdWrapper, RSyncPlaylistItem, RSyncPlaylistItemProviderData,
+        RSyncSong,
+    },
+};
+
+use super::{
+    local_provider::LocalProvider,
+    mock_provider::MockProvider,
+    paging::PageStream,
+    provider_error::{ProviderError, ProviderResult},
+    provider_traits::{APIProvider, AddOutcome, InsertProgress},
+    quota::Quota,
+    spotify_provider::SpotifyProvider,
+    youtube_provider::YoutubeProvider,
+};
+
+/// Identifies a registered provider, e.g. `spotify`. Events, popups and the sync state refer
+/// to columns by this id.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ProviderId(pub String);
+
+impl From<&str> for ProviderId {
+    fn from(id: &str) -> Self {
+        ProviderId(id.to_string())
+    }
+}
+
+impl Display for ProviderId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Every supported service, lets columns of different services live in one list.
+///
+/// A new service gets a variant here and a line in [`ProviderRegistry::live`].
+#[derive(Clone, Debug)]
+pub enum AnyProvider {
+    Spotify(SpotifyProvider),
+    Youtube(YoutubeProvider),
+    Local(LocalProvider),
+    Mock(MockProvider),
+}
+
+impl AnyProvider {
+    /// Connects the provider known under `id`, every provider is served from the mock
+    /// fixture when `RSYNC_MOCK_FIXTURE` is set.
+    pub async fn connect(id: &ProviderId) -> ProviderResult<Self> {
+        if let Some(fixture) = env::var("RSYNC_MOCK_FIXTURE")
+            .ok()
+            .filter(|f| !f.is_empty())
+        {
+            return Ok(AnyProvider::Mock(MockProvider::from_fixture(fixture)?));
+        }
+        match id.0.as_str() {
+            "spotify" => Ok(AnyProvider::Spotify(SpotifyProvider::new().await?)),
+            "youtube" => Ok(AnyProvider::Youtube(YoutubeProvider::new().await?)),
+            "local" => Ok(AnyProvider::Local(LocalProvider::new().await?)),
+            _ => Err(ProviderError::NotFound(format!(
+                "Unknown provider {}, use spotify, youtube or local",
+                id
+            ))),
+        }
+    }
+}
+
+macro_rules! dispatch {
+    ($self:ident, $provider:ident => $call:expr) => {
+        match $self {
+            AnyProvider::Spotify($provider) => $call,
+            AnyProvider::Youtube($provider) => $call,
+            AnyProvider::Local($provider) => $call,
+            AnyProvider::Mock($provider) => $call,
+        }
+    };
+}
+
+impl APIProvider for AnyProvider {
+    fn playlist_pages(&mut self) -> PageStream<'_, RSyncPlaylistItem> {
+        dispatch!(self, provider => provider.playlist_pages())
+    }
+
+    fn playlist_song_pages(&mut self, playlist_id: PlaylistIdWrapper) -> PageStream<'_, RSyncSong> {
+        dispatch!(self, provider => provider.playlist_song_pages(playlist_id))
+    }
+
+    fn playlist_data(&self) -> RSyncPlaylistItemProviderData {
+        dispatch!(self, provider => provider.playlist_data())
+    }
+
+    async fn create_playlist(
+        &mut self,
+        playlist_name: String,
+    ) -> ProviderResult<RSyncPlaylistItem> {
+        dispatch!(self, provider => provider.create_playlist(playlist_name).await)
+    }
+
+    async fn update_playlist(
+        &mut self,
+        playlist: RSyncPlaylistItem,
+        details: PlaylistDetails,
+    ) -> ProviderResult<RSyncPlaylistItem> {
+        dispatch!(self, provider => provider.update_playlist(playlist, details).await)
+    }
+
+    async fn delete_playlist(&mut self, playlist_id: PlaylistIdWrapper) -> ProviderResult<()> {
+        dispatch!(self, provider => provider.delete_playlist(playlist_id).await)
+    }
+
+    async fn restore_playlist(
+        &mut self,
+        playlist: RSyncPlaylistItem,
+    ) -> ProviderResult<Option<RSyncPlaylistItem>> {
+        dispatch!(self, provider => provider.restore_playlist(playlist).await)
+    }
+
+    async fn add_playlist_song_with_progress(
+        &mut self,
+        playlist_id: PlaylistIdWrapper,
+        song_id: Vec<String>,
+        progress: InsertProgress<'_>,
+    ) -> ProviderResult<AddOutcome> {
+        dispatch!(self, provider => {
+            provider
+                .add_playlist_song_with_progress(playlist_id, song_id, progress)
+                .await
+        })
+    }
+
+    async fn rem_playlist_song(
+        &mut self,
+        playlist_id: PlaylistIdWrapper,
+        song_ids: Vec<String>,
+    ) -> ProviderResult<()> {
+        dispatch!(self, provider => provider.rem_playlist_song(playlist_id, song_ids).await)
+    }
+
+    async fn move_playlist_songs(
+        &mut self,
+        playlist_id: PlaylistIdWrapper,
+        moves: Vec<SongMove>,
+    ) -> ProviderResult<()> {
+        dispatch!(self, provider => provider.move_playlist_songs(playlist_id, moves).await)
+    }
+
+    async fn search(&mut self, query: String, limit: u32) -> ProviderResult<Vec<RSyncSong>> {
+        dispatch!(self, provider => provider.search(query, limit).await)
+    }
+
+    async fn search_list(&mut self, items: Vec<RSyncSong>) -> ProviderResult<Vec<SongMatch>> {
+        dispatch!(self, provider => provider.search_list(items).await)
+    }
+
+    fn quota(&self) -> Option<Quota> {
+        dispatch!(self, provider => provider.quota())
+    }
+}
+
+#[derive(Debug)]
+pub struct RegisteredProvider<P: APIProvider> {
+    pub id: ProviderId,
+    /// Shown in the column titles and messages.
+    pub name: String,
+    pub provider: P,
+}
+
+/// Providers shown by the app, one column each in the registration order.
+#[derive(Debug)]
+pub struct ProviderRegistry<P: APIProvider = AnyProvider> {
+    providers: Vec<RegisteredProvider<P>>,
+}
+
+impl<P: APIProvider> Default for ProviderRegistry<P> {
+    fn default() -> Self {
+        Self {
+            providers: Vec::new(),
+        }
+    }
+}
+
+impl<P: APIProvider> ProviderRegistry<P> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a provider, a provider registered under an existing id replaces it.
+    pub fn register(mut self, id: impl Into<ProviderId>, name: &str, provider: P) -> Self {
+        let id = id.into();
+        let registered = RegisteredProvider {
+            id: id.clone(),
+            name: name.to_string(),
+            provider,
+        };
+        match self.providers.iter().position(|entry| entry.id == id) {
+            Some(index) => self.providers[index] = registered,
+            None => self.providers.push(registered),
+        }
+        self
+    }
+
+    pub fn get(&self, id: &ProviderId) -> Option<&RegisteredProvider<P>> {
+        self.providers.iter().find(|entry| &entry.id == id)
+    }
+
+    pub fn ids(&self) -> Vec<ProviderId> {
+        self.providers
+            .iter()
+            .map(|entry| entry.id.clone())
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.providers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.providers.is_empty()
+    }
+}
+
+impl<P: APIProvider> IntoIterator for ProviderRegistry<P> {
+    type Item = RegisteredProvider<P>;
+    type IntoIter = std::vec::IntoIter<RegisteredProvider<P>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.providers.into_iter()
+    }
+}
+
+impl ProviderRegistry {
+    /// Registry with the live services, every one of them is authorized on the way.
+    ///
+    /// The local library is added when `RSYNC_LOCAL_LIBRARY` is set.
+    pub async fn live() -> ProviderResult<Self> {
+        let registry = ProviderRegistry::new()
+            .register(
+                "spotify",
+                "Spotify",
+                AnyProvider::Spotify(SpotifyProvider::new().await?),
+            )
+            .register(
+                "youtube",
+                "Youtube",
+                AnyProvider::Youtube(YoutubeProvider::new().await?),
+            );
+        match env::var("RSYNC_LOCAL_LIBRARY")
+            .ok()
+            .filter(|root| !root.is_empty())
+        {
+            Some(_) => Ok(registry.register(
+                "local",
+                "Local",
+                AnyProvider::Local(LocalProvider::new().await?),
+            )),
+            None => Ok(registry),
+        }
+    }
+}
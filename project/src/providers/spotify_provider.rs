@@ -1,64 +1,242 @@
+use futures::{stream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
 use spotify_rs::{
-    model::{playlist::SimplifiedPlaylist, track::Track, PlayableItem},
+    model::{
+        playlist::{Playlist, SimplifiedPlaylist},
+        track::Track,
+        PlayableItem,
+    },
     AuthCodeClient, RedirectUrl, Token,
 };
 use std::{
     collections::HashMap,
-    env,
+    env, fs,
+    path::{Path, PathBuf},
     sync::{Arc, Mutex},
 };
-use tokio::sync::{mpsc, oneshot};
+use tokio::sync::oneshot;
+use tracing::{info, warn};
 use warp::Filter;
 use webbrowser;
 
 use crate::{
-    event::{Event, GlobalEvent, GlobalEventData, GlobalEventDataFullfilness},
+    matching::{self, SongMatch},
+    providers::{
+        paging::{self, PageStream},
+        provider_error::{ProviderError, ProviderResult},
+    },
+    reorder::{PlaylistPositions, SongMove},
     types::music_types::{
-        PlaylistIdWrapper, RSyncPlaylistItem, RSyncPlaylistItemProviderData,
-        RSyncPlaylistItemProviderDataSpotify, RSyncSong, RSyncSongProviderData,
+        PlaylistDetails, PlaylistIdWrapper, RSyncPlaylistItem, RSyncPlaylistItemProviderData,
+        RSyncPlaylistItemProviderDataSpotify, RSyncSong, RSyncSongMetadata, RSyncSongProviderData,
     },
 };
 
-use super::provider_traits::{APIProvider, APIProviderBuilder};
+use super::provider_traits::{APIProvider, APIProviderBuilder, AddOutcome, InsertProgress};
+
+const SPOTIFY_SCOPES: [&str; 9] = [
+    "app-remote-control",
+    "playlist-read-private",
+    "playlist-read-collaborative",
+    "playlist-modify-private",
+    "playlist-modify-public",
+    "user-library-modify",
+    "user-library-read",
+    "user-read-email",
+    "user-read-private",
+];
+
+const DEFAULT_CALLBACK_PORT: u16 = 8989;
+
+/// Playlist items one request adds or removes at most.
+const PLAYLIST_ITEMS_LIMIT: usize = 100;
+/// Saved tracks one request saves or removes at most.
+const SAVED_TRACKS_LIMIT: usize = 50;
+
+/// Songs per request for the playlist, the API refuses larger requests.
+fn request_limit(playlist_id: &PlaylistIdWrapper) -> usize {
+    match playlist_id {
+        PlaylistIdWrapper::Liked => SAVED_TRACKS_LIMIT,
+        PlaylistIdWrapper::Id(_) => PLAYLIST_ITEMS_LIMIT,
+    }
+}
 
 struct LoginQueryParams {
     code: String,
     state: String,
 }
 
-pub struct SpotifyProviderBuilder {}
+/// Refresh token persisted between launches, so the browser login is needed only once.
+#[derive(Serialize, Deserialize)]
+struct SpotifyTokenCache {
+    refresh_token: String,
+}
+
+impl SpotifyTokenCache {
+    fn load(path: &Path) -> Option<Self> {
+        let data = fs::read_to_string(path).ok()?;
+        serde_json::from_str(&data).ok()
+    }
+
+    fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string(self)?)?;
+        // the refresh token grants full access to the account, keep it private
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+        }
+        Ok(())
+    }
+}
+
+/// Location of the token cache, `SPOTIFY_TOKEN_CACHE` or the per-user cache directory.
+fn default_cache_path() -> PathBuf {
+    if let Some(path) = env::var_os("SPOTIFY_TOKEN_CACHE").filter(|path| !path.is_empty()) {
+        return PathBuf::from(path);
+    }
+    let cache_dir = env::var_os("XDG_CACHE_HOME")
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .or_else(|| env::var_os("LOCALAPPDATA").map(PathBuf::from));
+    match cache_dir {
+        Some(cache_dir) => cache_dir.join("rsync").join("spotify_tokencache.json"),
+        None => PathBuf::from("spotify_tokencache.json"),
+    }
+}
+
+/// Port of the login callback server, `SPOTIFY_CALLBACK_PORT` or the port of `SPOTIFY_CALLBACK`.
+fn default_callback_port() -> u16 {
+    if let Some(port) = env::var("SPOTIFY_CALLBACK_PORT")
+        .ok()
+        .and_then(|port| port.parse().ok())
+    {
+        return port;
+    }
+    env::var("SPOTIFY_CALLBACK")
+        .ok()
+        .and_then(|callback| {
+            let authority = callback.split("://").nth(1)?.split('/').next()?;
+            authority.rsplit_once(':')?.1.parse().ok()
+        })
+        .unwrap_or(DEFAULT_CALLBACK_PORT)
+}
+
+pub struct SpotifyProviderBuilder {
+    cache_path: PathBuf,
+    callback_port: u16,
+}
 impl SpotifyProviderBuilder {
     #[allow(unused)]
     fn new() -> SpotifyProviderBuilder {
-        SpotifyProviderBuilder {}
+        SpotifyProviderBuilder {
+            cache_path: default_cache_path(),
+            callback_port: default_callback_port(),
+        }
+    }
+
+    pub fn cache_path(mut self, cache_path: impl Into<PathBuf>) -> Self {
+        self.cache_path = cache_path.into();
+        self
+    }
+
+    pub fn callback_port(mut self, callback_port: u16) -> Self {
+        self.callback_port = callback_port;
+        self
     }
 
-    async fn new_authorized() -> SpotifyProvider {
+    async fn new_authorized() -> ProviderResult<SpotifyProvider> {
         SpotifyProviderBuilder::new().authorize().await
     }
+
+    /// Tries to log in silently with the cached refresh token.
+    async fn authorize_cached(
+        &self,
+        client_id: &str,
+        client_secret: &str,
+    ) -> Option<AuthCodeClient<Token>> {
+        let cache = SpotifyTokenCache::load(&self.cache_path)?;
+        match AuthCodeClient::from_refresh_token(
+            client_id,
+            client_secret,
+            Some(SPOTIFY_SCOPES.to_vec()),
+            true,
+            cache.refresh_token,
+        )
+        .await
+        {
+            Ok(client) => Some(client),
+            Err(err) => {
+                warn!(
+                    "Refreshing cached spotify token failed, logging in again: {}",
+                    err
+                );
+                None
+            }
+        }
+    }
+
+    fn store_token(&self, client: &AuthCodeClient<Token>) {
+        let Some(refresh_token) = client.refresh_token() else {
+            return;
+        };
+        let cache = SpotifyTokenCache {
+            refresh_token: refresh_token.to_string(),
+        };
+        match cache.save(&self.cache_path) {
+            Ok(()) => info!("Spotify token cached in {}", self.cache_path.display()),
+            Err(err) => warn!("Failed to cache spotify token: {}", err),
+        }
+    }
+}
+
+impl From<spotify_rs::Error> for ProviderError {
+    fn from(err: spotify_rs::Error) -> Self {
+        match err {
+            spotify_rs::Error::Spotify {
+                status,
+                description,
+            } => ProviderError::from_status(status, description),
+            spotify_rs::Error::NotAuthenticated
+            | spotify_rs::Error::ExpiredToken
+            | spotify_rs::Error::RefreshUnavailable => ProviderError::Auth(err.to_string()),
+            spotify_rs::Error::Deserialization(_) => {
+                ProviderError::MalformedResponse(err.to_string())
+            }
+            _ => ProviderError::Other(err.to_string()),
+        }
+    }
+}
+
+fn env_var(name: &str) -> ProviderResult<String> {
+    env::var(name).map_err(|_| ProviderError::Auth(format!("{} is not set", name)))
 }
 
 impl APIProviderBuilder for SpotifyProviderBuilder {
     #[allow(refining_impl_trait)]
-    async fn authorize(&mut self) -> SpotifyProvider {
+    async fn authorize(&mut self) -> ProviderResult<SpotifyProvider> {
         // This should match the redirect URI you set in your app's settings
-        let var_callback = env::var("SPOTIFY_CALLBACK").unwrap();
-        let var_client_id = env::var("SPOTIFY_CLIENT_ID").unwrap();
-        let var_client_secret = env::var("SPOTIFY_CLIENT_SECRET").unwrap();
+        let var_callback = env_var("SPOTIFY_CALLBACK")?;
+        let var_client_id = env_var("SPOTIFY_CLIENT_ID")?;
+        let var_client_secret = env_var("SPOTIFY_CLIENT_SECRET")?;
+
+        if let Some(client) = self
+            .authorize_cached(&var_client_id, &var_client_secret)
+            .await
+        {
+            let owner_name = spotify_rs::get_current_user_profile(&client).await?.id;
+            self.store_token(&client);
+            return Ok(SpotifyProvider { client, owner_name });
+        }
 
-        let redirect_url = RedirectUrl::new(var_callback.to_owned()).unwrap();
+        let redirect_url = RedirectUrl::new(var_callback.to_owned())
+            .map_err(|_| ProviderError::Auth("SPOTIFY_CALLBACK is not a valid url".into()))?;
         let auto_refresh = true;
-        let scopes = vec![
-            "app-remote-control",
-            "playlist-read-private",
-            "playlist-read-collaborative",
-            "playlist-modify-private",
-            "playlist-modify-public",
-            "user-library-modify",
-            "user-library-read",
-            "user-read-email",
-            "user-read-private",
-        ];
+        let scopes = SPOTIFY_SCOPES.to_vec();
 
         // Redirect the user to this URL to get the auth code and CSRF token
         let (client, url) = AuthCodeClient::new(
@@ -70,12 +248,12 @@ impl APIProviderBuilder for SpotifyProviderBuilder {
         );
 
         // Step 2: Get the auth token using browser and callback to local server
-        webbrowser::open(url.as_str()).unwrap_or_else(|_| {
-            panic!(
+        webbrowser::open(url.as_str()).map_err(|_| {
+            ProviderError::Auth(format!(
                 "Failed to open browser. Please visit the url {}",
                 url.as_str()
-            )
-        });
+            ))
+        })?;
 
         // Set up a channel to receive the authorization code from the callback
         let (tx, rx) = oneshot::channel::<LoginQueryParams>();
@@ -98,7 +276,7 @@ impl APIProviderBuilder for SpotifyProviderBuilder {
                         if let Some(tx) = tx.lock().unwrap().take() {
                             let _ = tx.send(LoginQueryParams {
                                 code: code.clone(),
-                                state: query_params.get("state").unwrap().clone(),
+                                state: query_params.get("state").cloned().unwrap_or_default(),
                             });
                         }
                     }
@@ -129,31 +307,28 @@ impl APIProviderBuilder for SpotifyProviderBuilder {
                 },
             );
 
-        // Start the warp server on port 8888
-        let server = warp::serve(callback_route).bind(([127, 0, 0, 1], 8989));
+        // Start the warp server on the configured callback port
+        let server = warp::serve(callback_route).bind(([127, 0, 0, 1], self.callback_port));
         let server_handle = tokio::spawn(server);
 
         // Wait for either the authorization code or server completion
         let auth_result = tokio::select! {
             _ = server_handle => {
-                panic!("Server closed unexpectedly");
+                return Err(ProviderError::Auth("Callback server closed unexpectedly".into()));
             },
             code = rx => {
-                code.expect("Failed to receive authorization code")
+                code.map_err(|_| ProviderError::Auth("Failed to receive authorization code".into()))?
             },
         };
 
         // Step 3: Finally, exchange the auth code for an access token
         let client = client
             .authenticate(auth_result.code, auth_result.state)
-            .await
-            .unwrap();
-        let owner_name = spotify_rs::get_current_user_profile(&client)
-            .await
-            .unwrap()
-            .id;
+            .await?;
+        let owner_name = spotify_rs::get_current_user_profile(&client).await?.id;
+        self.store_token(&client);
 
-        SpotifyProvider { client, owner_name }
+        Ok(SpotifyProvider { client, owner_name })
     }
 }
 
@@ -178,18 +353,43 @@ impl RSyncPlaylistItem {
     }
 }
 
+impl From<Playlist> for RSyncPlaylistItem {
+    fn from(item: Playlist) -> Self {
+        RSyncPlaylistItem {
+            collaborative: item.collaborative,
+            description: item.description,
+            url: item.external_urls.spotify,
+            id: PlaylistIdWrapper::Id(item.id),
+            name: item.name,
+            owned: true,
+            public: item.public.unwrap_or(false),
+            tracks: 0,
+            r#type: RSyncPlaylistItemProviderData::Spotify(RSyncPlaylistItemProviderDataSpotify {
+                snapshot_id: item.snapshot_id,
+            }),
+        }
+    }
+}
+
 impl From<Track> for RSyncSong {
     fn from(track: Track) -> Self {
+        let artists = track
+            .artists
+            .iter()
+            .map(|f| -> String { f.name.clone() })
+            .collect::<Vec<String>>();
         RSyncSong {
-            artists: track
-                .artists
-                .iter()
-                .map(|f| -> String { f.name.clone() })
-                .collect::<Vec<String>>()
-                .join(", "),
+            artists: artists.join(", "),
             url: track.external_urls.spotify,
             id: track.id,
             name: track.name,
+            metadata: RSyncSongMetadata {
+                isrc: track.external_ids.isrc,
+                duration_ms: Some(track.duration_ms),
+                album: Some(track.album.name),
+                artists,
+                added_at: None,
+            },
             r#type: RSyncSongProviderData::Spotify,
         }
     }
@@ -201,17 +401,49 @@ pub struct SpotifyProvider {
     owner_name: String,
 }
 
-impl APIProvider for SpotifyProvider {
-    async fn new() -> Self {
+impl SpotifyProvider {
+    pub async fn new() -> ProviderResult<Self> {
         SpotifyProviderBuilder::new_authorized().await
     }
 
-    async fn get_playlists(&mut self) -> Vec<RSyncPlaylistItem> {
-        let mut total: Option<u32> = None;
-        let mut offset: u32 = 0;
-        let per_request = 10;
-        let mut playlists: Vec<RSyncPlaylistItem> = Vec::new();
-        playlists.push(RSyncPlaylistItem {
+    /// Every item of a playlist in its order, `None` for the items that are not songs.
+    fn playlist_item_pages(&self, playlist_id: String) -> PageStream<'_, Option<RSyncSong>> {
+        let client = &self.client;
+        paging::offset_pages(20, move |offset, limit| {
+            let playlist_id = playlist_id.clone();
+            async move {
+                let response = spotify_rs::playlist_items(playlist_id)
+                    .limit(limit)
+                    .offset(offset)
+                    .get(client)
+                    .await?;
+                let songs = response
+                    .items
+                    .into_iter()
+                    .map(|playlist_track| {
+                        playlist_track.and_then(|track_data| match track_data.track {
+                            Some(PlayableItem::Track(track)) => {
+                                let mut song: RSyncSong = track.into();
+                                song.metadata.added_at = Some(track_data.added_at);
+                                Some(song)
+                            }
+                            _ => {
+                                // podcasts and are not a part i want to deal with.... sorry
+                                // Also. Some responses are invalid in relation to spotify api definition which is funny :D
+                                None
+                            }
+                        })
+                    })
+                    .collect();
+                Ok((songs, response.total))
+            }
+        })
+    }
+}
+
+impl APIProvider for SpotifyProvider {
+    fn playlist_pages(&mut self) -> PageStream<'_, RSyncPlaylistItem> {
+        let favorites = RSyncPlaylistItem {
             collaborative: false,
             description: Some("Favourite playlist".into()),
             url: "https://open.spotify.com/collection/tracks".into(),
@@ -223,196 +455,256 @@ impl APIProvider for SpotifyProvider {
             r#type: RSyncPlaylistItemProviderData::Spotify(RSyncPlaylistItemProviderDataSpotify {
                 snapshot_id: "".into(),
             }),
-        });
-
-        loop {
+        };
+        let client = &self.client;
+        let owner_name = &self.owner_name;
+        // the count of the saved tracks comes with a page of them
+        let favorites = async move {
+            let saved = spotify_rs::saved_tracks().limit(1).get(client).await?;
+            Ok(vec![RSyncPlaylistItem {
+                tracks: saved.total,
+                ..favorites
+            }])
+        };
+        let playlists = paging::offset_pages(10, move |offset, limit| async move {
             let response = spotify_rs::current_user_playlists()
-                .limit(per_request)
+                .limit(limit)
                 .offset(offset)
-                .get(&self.client)
-                .await
-                .unwrap();
-            if total.is_none() {
-                total = Some(response.total);
-            }
-            offset += per_request;
-
-            for playlist in response.items.into_iter().flatten() {
-                playlists.push(RSyncPlaylistItem::from(playlist, self.owner_name.clone()));
-            }
-            if offset > total.unwrap() {
-                break;
-            }
-        }
-        playlists
+                .get(client)
+                .await?;
+            let playlists = response
+                .items
+                .into_iter()
+                .flatten()
+                .map(|playlist| RSyncPlaylistItem::from(playlist, owner_name.clone()))
+                .collect();
+            Ok((playlists, response.total))
+        });
+        stream::once(favorites).chain(playlists).boxed()
     }
 
-    async fn get_playlist_songs(
-        &mut self,
-        playlist_id: PlaylistIdWrapper,
-        event_sender: Option<(mpsc::UnboundedSender<Event>, u128)>,
-    ) -> Vec<RSyncSong> {
+    fn playlist_song_pages(&mut self, playlist_id: PlaylistIdWrapper) -> PageStream<'_, RSyncSong> {
         match playlist_id {
             PlaylistIdWrapper::Liked => {
-                let mut total = None;
-                let mut offset: u32 = 0;
-                let per_request = 40;
-                let mut songs: Vec<RSyncSong> = Vec::new();
-
-                loop {
+                let client = &self.client;
+                paging::offset_pages(40, move |offset, limit| async move {
                     let response = spotify_rs::saved_tracks()
-                        .limit(per_request)
+                        .limit(limit)
                         .offset(offset)
-                        .get(&self.client)
-                        .await
-                        .unwrap();
-                    let mut songs_inner: Vec<RSyncSong> = Vec::new();
-                    if total.is_none() {
-                        total = Some(response.total);
-                    }
-                    offset += per_request;
+                        .get(client)
+                        .await?;
+                    let songs = response
+                        .items
+                        .into_iter()
+                        .flatten()
+                        .map(|saved_track| {
+                            let mut song: RSyncSong = saved_track.track.into();
+                            song.metadata.added_at = Some(saved_track.added_at);
+                            song
+                        })
+                        .collect();
+                    Ok((songs, response.total))
+                })
+            }
+            PlaylistIdWrapper::Id(playlist_id) => self
+                .playlist_item_pages(playlist_id)
+                .map_ok(|songs| songs.into_iter().flatten().collect())
+                .boxed(),
+        }
+    }
 
-                    for playlist_track in response.items {
-                        songs_inner.push(playlist_track.unwrap().track.into());
-                    }
+    fn playlist_data(&self) -> RSyncPlaylistItemProviderData {
+        // the snapshot of a playlist is only known once it was listed
+        RSyncPlaylistItemProviderData::Spotify(RSyncPlaylistItemProviderDataSpotify {
+            snapshot_id: String::new(),
+        })
+    }
 
-                    if offset > total.unwrap() {
-                        break;
-                    }
+    async fn create_playlist(
+        &mut self,
+        playlist_name: String,
+    ) -> ProviderResult<RSyncPlaylistItem> {
+        let playlist = spotify_rs::create_playlist(self.owner_name.clone(), playlist_name)
+            .send(&self.client)
+            .await?;
+        Ok(playlist.into())
+    }
 
-                    if let Some(event_sender) = event_sender.clone() {
-                        event_sender
-                            .0
-                            .send(Event::DataReceived(
-                                event_sender.1,
-                                GlobalEvent::Spotify(GlobalEventData::Songs(
-                                    GlobalEventDataFullfilness::Partial(songs_inner.clone()),
-                                )),
-                            ))
-                            .unwrap();
-                    }
-                    songs.append(&mut songs_inner);
-                }
-                songs
-            }
+    async fn update_playlist(
+        &mut self,
+        playlist: RSyncPlaylistItem,
+        details: PlaylistDetails,
+    ) -> ProviderResult<RSyncPlaylistItem> {
+        let PlaylistIdWrapper::Id(playlist_id) = &playlist.id else {
+            return Err(ProviderError::Permission(
+                "Favorites can not be edited".into(),
+            ));
+        };
+        spotify_rs::change_playlist_details(playlist_id.clone())
+            .name(details.name.clone())
+            .description(details.description.clone().unwrap_or_default())
+            .public(details.public)
+            .send(&self.client)
+            .await?;
+        Ok(playlist.with_details(details))
+    }
+
+    async fn delete_playlist(&mut self, playlist_id: PlaylistIdWrapper) -> ProviderResult<()> {
+        match playlist_id {
+            PlaylistIdWrapper::Liked => Err(ProviderError::Permission(
+                "Favorites can not be deleted".into(),
+            )),
+            // spotify has no deletion, an unfollowed playlist disappears from the library
             PlaylistIdWrapper::Id(playlist_id) => {
-                let mut total: Option<u32> = None;
-                let mut offset: u32 = 0;
-                let per_request = 20;
-                let mut songs: Vec<RSyncSong> = Vec::new();
-
-                loop {
-                    let response = spotify_rs::playlist_items(&playlist_id)
-                        .limit(per_request)
-                        .offset(offset)
-                        .get(&self.client)
-                        .await
-                        .unwrap();
-                    if total.is_none() {
-                        total = Some(response.total);
-                    }
-                    offset += per_request;
-
-                    for playlist_track in response.items {
-                        if let Some(track_data) = playlist_track {
-                            match track_data.track {
-                                Some(PlayableItem::Track(track)) => {
-                                    songs.push(track.into());
-                                }
-                                _ => {
-                                    // podcasts and are not a part i want to deal with.... sorry
-                                    // Also. Some responses are invalid in relation to spotify api definition which is funny :D
-                                }
-                            }
-                        }
-                    }
-                    if offset > total.unwrap() {
-                        break;
-                    }
-                }
-                songs
+                spotify_rs::unfollow_playlist(playlist_id, &self.client).await?;
+                Ok(())
             }
         }
     }
 
-    async fn create_playlist(&mut self, playlist_name: String) {
-        spotify_rs::create_playlist(self.owner_name.clone(), playlist_name)
+    async fn restore_playlist(
+        &mut self,
+        playlist: RSyncPlaylistItem,
+    ) -> ProviderResult<Option<RSyncPlaylistItem>> {
+        let PlaylistIdWrapper::Id(playlist_id) = &playlist.id else {
+            return Ok(None);
+        };
+        // the unfollowed playlist still exists with its id and songs
+        spotify_rs::follow_playlist(playlist_id.clone())
+            .public(playlist.public)
             .send(&self.client)
-            .await
-            .unwrap();
+            .await?;
+        Ok(Some(playlist))
     }
 
-    async fn add_playlist_song(&mut self, playlist_id: PlaylistIdWrapper, song_ids: Vec<String>) {
-        match playlist_id {
-            PlaylistIdWrapper::Liked => {
-                let song_uris: Vec<String> = song_ids
-                    .iter()
-                    .map(|song_id| self.convert_id_to_uri(song_id))
-                    .collect();
-                spotify_rs::save_tracks(song_uris.as_slice(), &self.client)
-                    .await
-                    .unwrap();
+    async fn add_playlist_song_with_progress(
+        &mut self,
+        playlist_id: PlaylistIdWrapper,
+        song_ids: Vec<String>,
+        progress: InsertProgress<'_>,
+    ) -> ProviderResult<AddOutcome> {
+        let mut added = Vec::new();
+        for chunk in song_ids.chunks(request_limit(&playlist_id)) {
+            let song_uris: Vec<String> = chunk
+                .iter()
+                .map(|song_id| self.convert_id_to_uri(song_id))
+                .collect();
+            let sent = match &playlist_id {
+                PlaylistIdWrapper::Liked => {
+                    spotify_rs::save_tracks(song_uris.as_slice(), &self.client)
+                        .await
+                        .map(|_| ())
+                }
+                PlaylistIdWrapper::Id(playlist_id) => {
+                    spotify_rs::add_items_to_playlist(playlist_id.clone(), song_uris.as_slice())
+                        .send(&self.client)
+                        .await
+                        .map(|_| ())
+                }
+            };
+            // the earlier chunks stay added
+            if let Err(err) = sent {
+                return Ok(AddOutcome::partial(added, err.into()));
             }
-            PlaylistIdWrapper::Id(playlist_id) => {
-                let song_uris: Vec<String> = song_ids
-                    .iter()
-                    .map(|song_id| self.convert_id_to_uri(song_id))
-                    .collect();
-                spotify_rs::add_items_to_playlist(playlist_id, song_uris.as_slice())
-                    .send(&self.client)
-                    .await
-                    .unwrap();
+            added.extend_from_slice(chunk);
+            progress(added.len());
+        }
+        Ok(AddOutcome::all(added))
+    }
+
+    async fn rem_playlist_song(
+        &mut self,
+        playlist_id: PlaylistIdWrapper,
+        song_ids: Vec<String>,
+    ) -> ProviderResult<()> {
+        for chunk in song_ids.chunks(request_limit(&playlist_id)) {
+            let song_uris: Vec<String> = chunk
+                .iter()
+                .map(|song_id| self.convert_id_to_uri(song_id))
+                .collect();
+            match &playlist_id {
+                PlaylistIdWrapper::Liked => {
+                    spotify_rs::remove_saved_tracks(song_uris.as_slice(), &self.client).await?;
+                }
+                PlaylistIdWrapper::Id(playlist_id) => {
+                    spotify_rs::remove_playlist_items(playlist_id.clone(), song_uris.as_slice())
+                        .send(&self.client)
+                        .await?;
+                }
             }
         }
+        Ok(())
     }
 
-    async fn rem_playlist_song(&mut self, playlist_id: PlaylistIdWrapper, song_ids: Vec<String>) {
-        let song_uris: Vec<String> = song_ids
-            .iter()
-            .map(|song_id| self.convert_id_to_uri(song_id))
-            .collect();
-        match playlist_id {
-            PlaylistIdWrapper::Liked => {
-                spotify_rs::remove_saved_tracks(song_uris.as_slice(), &self.client)
-                    .await
-                    .unwrap();
+    async fn move_playlist_songs(
+        &mut self,
+        playlist_id: PlaylistIdWrapper,
+        moves: Vec<SongMove>,
+    ) -> ProviderResult<()> {
+        let PlaylistIdWrapper::Id(playlist_id) = playlist_id else {
+            return Err(ProviderError::Permission(
+                "Favorites are ordered by the date they were liked".into(),
+            ));
+        };
+        // the moves skip the items that are not shown, the service counts them
+        let tracks = self
+            .playlist_item_pages(playlist_id.clone())
+            .try_concat()
+            .await?;
+        let mut positions =
+            PlaylistPositions::new(tracks.iter().map(|song| ((), song.is_some())).collect());
+        let mut snapshot_id: Option<String> = None;
+        for song_move in moves {
+            let song_move = positions.translate(&song_move)?;
+            if song_move.is_noop() {
+                continue;
             }
-            PlaylistIdWrapper::Id(playlist_id) => {
-                spotify_rs::remove_playlist_items(playlist_id, song_uris.as_slice())
-                    .send(&self.client)
-                    .await
-                    .unwrap();
+            let mut request = spotify_rs::update_playlist_items(
+                playlist_id.clone(),
+                song_move.range_start as u32,
+                song_move.insert_before as u32,
+            )
+            .range_length(song_move.range_length as u32);
+            // a concurrent change of the playlist fails the move instead of scrambling it
+            if let Some(snapshot_id) = snapshot_id {
+                request = request.snapshot_id(snapshot_id);
             }
+            snapshot_id = Some(request.send(&self.client).await?);
         }
+        Ok(())
     }
 
-    async fn search(&mut self, query: String, limit: u32) -> Vec<RSyncSong> {
+    async fn search(&mut self, query: String, limit: u32) -> ProviderResult<Vec<RSyncSong>> {
         let items = [spotify_rs::model::search::Item::Track];
         let data = spotify_rs::search(query, &items)
             .limit(limit)
             .get(&self.client)
-            .await
-            .unwrap()
+            .await?
             .tracks
-            .unwrap()
-            .clone()
+            .ok_or_else(|| ProviderError::malformed("tracks"))?
             .items;
-        let mut songs = Vec::new();
-        for track in data {
-            songs.push(track.unwrap().into());
-        }
-        songs
+        Ok(data.into_iter().flatten().map(RSyncSong::from).collect())
     }
 
-    async fn search_list(&mut self, items: Vec<RSyncSong>) -> Vec<RSyncSong> {
-        let mut songs = Vec::new();
+    async fn search_list(&mut self, items: Vec<RSyncSong>) -> ProviderResult<Vec<SongMatch>> {
+        let mut matches = Vec::new();
         for item in items {
-            let found = self
-                .search(format!("{} ({})", item.name, item.artists), 1)
-                .await;
-            songs.push(found[0].clone());
+            // the recording code identifies the exact release, text search is the fallback
+            let mut found = match &item.metadata.isrc {
+                Some(isrc) => self.search(format!("isrc:{}", isrc), 1).await?,
+                None => Vec::new(),
+            };
+            if found.is_empty() {
+                let query = format!(
+                    "track:{} artist:{}",
+                    matching::normalise_title(&item.name),
+                    item.primary_artist()
+                );
+                found = self.search(query, matching::CANDIDATE_LIMIT).await?;
+            }
+            matches.push(matching::best_match(item, found));
         }
-        songs
+        Ok(matches)
     }
 }
 
@@ -421,13 +713,12 @@ impl SpotifyProvider {
         ["spotify", "track", song_id].join(":")
     }
 
-    pub async fn rem_liked_song(&mut self, song_ids: Vec<String>) {
+    pub async fn rem_liked_song(&mut self, song_ids: Vec<String>) -> ProviderResult<()> {
         let song_uris: Vec<String> = song_ids
             .iter()
             .map(|song_id| self.convert_id_to_uri(song_id))
             .collect();
-        spotify_rs::remove_saved_tracks(song_uris.as_slice(), &self.client)
-            .await
-            .unwrap();
+        spotify_rs::remove_saved_tracks(song_uris.as_slice(), &self.client).await?;
+        Ok(())
     }
 }
@@ -1,5 +1,13 @@
-use std::{env, fmt::Debug};
+use std::{
+    collections::HashSet,
+    env,
+    fmt::Debug,
+    future::Future,
+    pin::pin,
+    sync::atomic::{AtomicBool, Ordering},
+};
 
+use futures::{stream, Stream, StreamExt, TryStreamExt};
 use google_youtube3::{
     api::{
         Playlist, PlaylistItem, PlaylistItemSnippet, PlaylistSnippet, PlaylistStatus, ResourceId,
@@ -9,17 +17,23 @@ use google_youtube3::{
     yup_oauth2::{self, authenticator_delegate::InstalledFlowDelegate},
     YouTube,
 };
-use tokio::sync::mpsc;
 
 use crate::{
-    event::{Event, GlobalEvent, GlobalEventData, GlobalEventDataFullfilness},
+    matching::{self, SongMatch},
+    providers::{
+        backoff,
+        paging::{self, PageStream},
+        provider_error::{ProviderError, ProviderResult},
+        quota::{Quota, LIST_COST, PAGE_SIZE, SEARCH_COST, WRITE_COST},
+    },
+    reorder::{PlaylistPositions, SongMove},
     types::music_types::{
-        PlaylistIdWrapper, RSyncPlaylistItem, RSyncPlaylistItemProviderData, RSyncSong,
-        RSyncSongProviderData, RSyncSongProviderDataYoutube,
+        PlaylistDetails, PlaylistIdWrapper, RSyncPlaylistItem, RSyncPlaylistItemProviderData,
+        RSyncSong, RSyncSongMetadata, RSyncSongProviderData, RSyncSongProviderDataYoutube,
     },
 };
 
-use super::provider_traits::{APIProvider, APIProviderBuilder};
+use super::provider_traits::{APIProvider, APIProviderBuilder, AddOutcome, InsertProgress};
 
 pub struct YoutubeProviderBuilder {}
 impl YoutubeProviderBuilder {
@@ -28,11 +42,103 @@ impl YoutubeProviderBuilder {
         YoutubeProviderBuilder {}
     }
 
-    async fn new_authorized() -> YoutubeProvider {
+    async fn new_authorized() -> ProviderResult<YoutubeProvider> {
         YoutubeProviderBuilder::new().authorize().await
     }
 }
 
+impl From<google_youtube3::Error> for ProviderError {
+    fn from(err: google_youtube3::Error) -> Self {
+        match err {
+            google_youtube3::Error::BadRequest(ref body) => {
+                let code = body["error"]["code"].as_u64().unwrap_or_default() as u16;
+                let reason = body["error"]["errors"][0]["reason"]
+                    .as_str()
+                    .unwrap_or_default();
+                let message = body["error"]["message"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string();
+                match reason {
+                    "quotaExceeded" | "rateLimitExceeded" | "userRateLimitExceeded" => {
+                        ProviderError::RateLimited(message)
+                    }
+                    _ => ProviderError::from_status(code, message),
+                }
+            }
+            google_youtube3::Error::Failure(ref response) => {
+                ProviderError::from_status(response.status().as_u16(), err.to_string())
+            }
+            google_youtube3::Error::MissingToken(_) | google_youtube3::Error::MissingAPIKey => {
+                ProviderError::Auth(err.to_string())
+            }
+            google_youtube3::Error::JsonDecodeError(_, _) => {
+                ProviderError::MalformedResponse(err.to_string())
+            }
+            _ => ProviderError::Other(err.to_string()),
+        }
+    }
+}
+
+/// Playlist inserts or ratings of liked videos that are sent at the same time.
+const INSERT_CONCURRENCY: usize = 4;
+
+/// Rate limits and server errors pass with time, an exhausted daily quota does not.
+fn is_transient(err: &google_youtube3::Error) -> bool {
+    match err {
+        google_youtube3::Error::BadRequest(body) => {
+            let code = body["error"]["code"].as_u64().unwrap_or_default();
+            let reason = body["error"]["errors"][0]["reason"]
+                .as_str()
+                .unwrap_or_default();
+            code == 429
+                || code >= 500
+                || matches!(reason, "rateLimitExceeded" | "userRateLimitExceeded")
+        }
+        google_youtube3::Error::Failure(response) => {
+            let status = response.status().as_u16();
+            status == 429 || status >= 500
+        }
+        _ => false,
+    }
+}
+
+/// Sends the request built by `call`, its `units` are charged once even if it is retried.
+async fn send<T, F, Fut>(quota: &Quota, units: u32, call: F) -> ProviderResult<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = google_youtube3::Result<T>>,
+{
+    quota.charge(units)?;
+    Ok(backoff::retry(is_transient, call).await?)
+}
+
+/// Collects the results of concurrent inserts in the requested order, `None` was not sent.
+async fn add_outcome(
+    results: impl Stream<Item = (String, Option<ProviderResult<()>>)>,
+    progress: InsertProgress<'_>,
+) -> AddOutcome {
+    let mut results = pin!(results);
+    let mut outcome = AddOutcome::default();
+    while let Some((id, result)) = results.next().await {
+        match result {
+            Some(Ok(())) => {
+                outcome.added.push(id);
+                progress(outcome.added.len());
+            }
+            Some(Err(err)) => {
+                outcome.error.get_or_insert(err);
+            }
+            None => (),
+        }
+    }
+    outcome
+}
+
+fn env_var(name: &str) -> ProviderResult<String> {
+    env::var(name).map_err(|_| ProviderError::Auth(format!("{} is not set", name)))
+}
+
 struct YupOauthDelegate {}
 impl InstalledFlowDelegate for YupOauthDelegate {
     fn redirect_uri(&self) -> Option<&str> {
@@ -54,7 +160,7 @@ impl YupOauthDelegate {
         use tokio::io::AsyncBufReadExt;
         if need_code {
             webbrowser::open(url)
-                .unwrap_or_else(|_| panic!("Failed to open browser. Please visit the url {}", url));
+                .map_err(|_| format!("Failed to open browser. Please visit the url {}", url))?;
             let mut user_input = String::new();
             tokio::io::BufReader::new(tokio::io::stdin())
                 .read_line(&mut user_input)
@@ -65,7 +171,7 @@ impl YupOauthDelegate {
             Ok(user_input)
         } else {
             webbrowser::open(url)
-                .unwrap_or_else(|_| panic!("Failed to open browser. Please visit the url {}", url));
+                .map_err(|_| format!("Failed to open browser. Please visit the url {}", url))?;
             Ok(String::new())
         }
     }
@@ -73,14 +179,14 @@ impl YupOauthDelegate {
 
 impl APIProviderBuilder for YoutubeProviderBuilder {
     #[allow(refining_impl_trait)]
-    async fn authorize(&mut self) -> YoutubeProvider {
+    async fn authorize(&mut self) -> ProviderResult<YoutubeProvider> {
         let secret: yup_oauth2::ApplicationSecret = yup_oauth2::ApplicationSecret {
-            client_secret: env::var("YOUTUBE_CLIENT_SECRET").unwrap(),
-            client_id: env::var("YOUTUBE_CLIENT_ID").unwrap(),
-            project_id: Some(env::var("YOUTUBE_PROJECT_ID").unwrap()),
-            auth_uri: env::var("YOUTUBE_AUTH_URI").unwrap(),
-            token_uri: env::var("YOUTUBE_TOKEN_URI").unwrap(),
-            auth_provider_x509_cert_url: Some(env::var("YOUTUBE_CERTS").unwrap()),
+            client_secret: env_var("YOUTUBE_CLIENT_SECRET")?,
+            client_id: env_var("YOUTUBE_CLIENT_ID")?,
+            project_id: Some(env_var("YOUTUBE_PROJECT_ID")?),
+            auth_uri: env_var("YOUTUBE_AUTH_URI")?,
+            token_uri: env_var("YOUTUBE_TOKEN_URI")?,
+            auth_provider_x509_cert_url: Some(env_var("YOUTUBE_CERTS")?),
             redirect_uris: vec!["http://localhost".into()],
             ..Default::default()
         };
@@ -94,50 +200,50 @@ impl APIProviderBuilder for YoutubeProviderBuilder {
         .persist_tokens_to_disk("tokencache.json")
         .build()
         .await
-        .unwrap();
+        .map_err(|err| ProviderError::Auth(err.to_string()))?;
 
         //this will for some BS reason just printy
         let _ = auth
             .token(&["https://www.googleapis.com/auth/youtube"])
             .await
-            .unwrap();
+            .map_err(|err| ProviderError::Auth(err.to_string()))?;
 
         let yt_client =
             hyper_util::client::legacy::Client::builder(hyper_util::rt::TokioExecutor::new())
                 .build(
                     hyper_rustls::HttpsConnectorBuilder::new()
                         .with_native_roots()
-                        .unwrap()
+                        .map_err(|err| ProviderError::Other(err.to_string()))?
                         .https_or_http()
                         .enable_http1()
                         .build(),
                 );
 
         let client = YouTube::new(yt_client, auth);
-        let liked_playlist_id = client
-            .channels()
-            .list(&vec!["contentDetails".into()])
-            .mine(true)
-            .doit()
-            .await
-            .unwrap()
-            .1
-            .items
-            .unwrap()
-            .first()
-            .unwrap()
-            .content_details
-            .clone()
-            .unwrap()
-            .related_playlists
-            .unwrap()
-            .likes
-            .unwrap();
-
-        YoutubeProvider {
+        let quota = Quota::load(&Quota::default_path(), Quota::daily_limit());
+        let liked_playlist_id = send(&quota, LIST_COST, || {
+            client
+                .channels()
+                .list(&vec!["contentDetails".into()])
+                .mine(true)
+                .doit()
+        })
+        .await?
+        .1
+        .items
+        .unwrap_or_default()
+        .into_iter()
+        .next()
+        .and_then(|channel| channel.content_details)
+        .and_then(|details| details.related_playlists)
+        .and_then(|playlists| playlists.likes)
+        .ok_or_else(|| ProviderError::malformed("liked playlist of the channel"))?;
+
+        Ok(YoutubeProvider {
             client,
             liked_playlist_id,
-        }
+            quota,
+        })
     }
 }
 
@@ -146,6 +252,7 @@ pub struct YoutubeProvider {
     client:
         YouTube<hyper_rustls::HttpsConnector<hyper_util::client::legacy::connect::HttpConnector>>,
     liked_playlist_id: String,
+    quota: Quota,
 }
 impl Debug for YoutubeProvider {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -153,95 +260,148 @@ impl Debug for YoutubeProvider {
     }
 }
 
-impl From<Playlist> for RSyncPlaylistItem {
-    fn from(item: Playlist) -> Self {
-        RSyncPlaylistItem {
+impl TryFrom<Playlist> for RSyncPlaylistItem {
+    type Error = ProviderError;
+
+    fn try_from(item: Playlist) -> ProviderResult<Self> {
+        let id = item
+            .id
+            .ok_or_else(|| ProviderError::malformed("playlist id"))?;
+        let snippet = item
+            .snippet
+            .ok_or_else(|| ProviderError::malformed("playlist snippet"))?;
+        Ok(RSyncPlaylistItem {
             collaborative: false,
-            description: item.snippet.clone().unwrap().description,
-            url: format!(
-                "https://music.youtube.com/playlist?list={}",
-                item.id.as_ref().unwrap()
-            ),
-            id: PlaylistIdWrapper::Id(item.id.unwrap()),
-            name: item
-                .snippet
-                .as_ref()
-                .unwrap()
+            description: snippet.description,
+            url: format!("https://music.youtube.com/playlist?list={}", id),
+            id: PlaylistIdWrapper::Id(id),
+            name: snippet
                 .title
-                .as_ref()
-                .unwrap()
-                .clone(),
-            owned: item.snippet.unwrap().channel_id.unwrap() == "UC_ACaQ9yyt3iLSDWbg9SO9g",
-            public: item.status.unwrap().privacy_status.unwrap() == "private",
-            tracks: item.content_details.unwrap().item_count.unwrap(),
+                .ok_or_else(|| ProviderError::malformed("playlist title"))?,
+            owned: snippet.channel_id.as_deref() == Some("UC_ACaQ9yyt3iLSDWbg9SO9g"),
+            public: item
+                .status
+                .and_then(|status| status.privacy_status)
+                .as_deref()
+                == Some("public"),
+            tracks: item
+                .content_details
+                .and_then(|details| details.item_count)
+                .unwrap_or_default(),
             r#type: RSyncPlaylistItemProviderData::Youtube,
-        }
+        })
     }
 }
 
-impl From<PlaylistItem> for RSyncSong {
-    fn from(track: PlaylistItem) -> Self {
-        let snippet = track.snippet.unwrap();
-        let artist_name = snippet
-            .video_owner_channel_title
-            .clone()
-            .unwrap_or_default();
-        RSyncSong {
+impl TryFrom<PlaylistItem> for RSyncSong {
+    type Error = ProviderError;
+
+    fn try_from(track: PlaylistItem) -> ProviderResult<Self> {
+        let snippet = track
+            .snippet
+            .ok_or_else(|| ProviderError::malformed("playlist item snippet"))?;
+        let artist_name = snippet.video_owner_channel_title.unwrap_or_default();
+        let video_id = snippet
+            .resource_id
+            .and_then(|resource| resource.video_id)
+            .ok_or_else(|| ProviderError::malformed("video id of playlist item"))?;
+        Ok(RSyncSong {
             artists: artist_name
                 .strip_suffix(" - Topic")
                 .unwrap_or(&artist_name)
                 .to_string(),
-            url: format!(
-                "https://music.youtube.com/watch?v={}",
-                snippet
-                    .resource_id
-                    .as_ref()
-                    .unwrap()
-                    .video_id
-                    .as_ref()
-                    .unwrap()
-            ),
-            id: snippet.resource_id.unwrap().video_id.unwrap(),
-            name: snippet.title.unwrap(),
+            url: format!("https://music.youtube.com/watch?v={}", video_id),
+            id: video_id,
+            name: snippet.title.unwrap_or_default(),
+            metadata: RSyncSongMetadata {
+                // published_at of a playlist item is when it was added to the playlist
+                added_at: snippet.published_at.map(|date| date.to_rfc3339()),
+                ..Default::default()
+            },
             r#type: RSyncSongProviderData::Youtube(RSyncSongProviderDataYoutube {
                 playlist_id: track.id,
             }),
-        }
+        })
     }
 }
 
-impl From<SearchResult> for RSyncSong {
-    fn from(track: SearchResult) -> Self {
-        let snippet = track.snippet.unwrap();
-        let artist_name = snippet.channel_title.clone().unwrap_or_default();
-        RSyncSong {
+impl TryFrom<SearchResult> for RSyncSong {
+    type Error = ProviderError;
+
+    fn try_from(track: SearchResult) -> ProviderResult<Self> {
+        let snippet = track
+            .snippet
+            .ok_or_else(|| ProviderError::malformed("search result snippet"))?;
+        let artist_name = snippet.channel_title.unwrap_or_default();
+        let video_id = track
+            .id
+            .and_then(|resource| resource.video_id)
+            .ok_or_else(|| ProviderError::malformed("video id of search result"))?;
+        Ok(RSyncSong {
             artists: artist_name
                 .strip_suffix(" - Topic")
                 .unwrap_or(&artist_name)
                 .to_string(),
-            url: format!(
-                "https://music.youtube.com/watch?v={}",
-                track.id.as_ref().unwrap().video_id.clone().unwrap()
-            ),
-            id: track.id.unwrap().video_id.unwrap(),
-            name: snippet.title.unwrap(),
+            url: format!("https://music.youtube.com/watch?v={}", video_id),
+            id: video_id,
+            name: snippet.title.unwrap_or_default(),
+            metadata: RSyncSongMetadata::default(),
             r#type: RSyncSongProviderData::Youtube(RSyncSongProviderDataYoutube {
                 playlist_id: None,
             }),
+        })
+    }
+}
+
+/// Length of the video, youtube returns it as an ISO 8601 duration like `PT1H3M25S`.
+fn video_duration_ms(video: &Video) -> Option<u32> {
+    let duration = video.content_details.as_ref()?.duration.as_ref()?;
+    let mut seconds = 0;
+    let mut number = String::new();
+    for c in duration.strip_prefix("PT")?.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'H' | 'M' | 'S' => {
+                let value: u32 = number.parse().ok()?;
+                number.clear();
+                seconds += match c {
+                    'H' => value * 3600,
+                    'M' => value * 60,
+                    _ => value,
+                };
+            }
+            _ => return None,
         }
     }
+    Some(seconds * 1000)
 }
 
-impl APIProvider for YoutubeProvider {
-    async fn new() -> Self {
+/// Checks the category of the detailed video data, youtube-music api does not have public
+/// access so this is the only way to filter just the music.
+fn is_music_video(video: &Video) -> bool {
+    video
+        .snippet
+        .as_ref()
+        .and_then(|snippet| snippet.category_id.as_deref())
+        == Some("10")
+}
+
+fn video_id_of(item: &PlaylistItem) -> Option<String> {
+    item.snippet
+        .as_ref()
+        .and_then(|snippet| snippet.resource_id.as_ref())
+        .and_then(|resource| resource.video_id.clone())
+}
+
+impl YoutubeProvider {
+    pub async fn new() -> ProviderResult<Self> {
         YoutubeProviderBuilder::new_authorized().await
     }
+}
 
-    async fn get_playlists(&mut self) -> Vec<RSyncPlaylistItem> {
-        let mut next_page_token: Option<String> = Some("".into());
-        let mut playlists: Vec<RSyncPlaylistItem> = Vec::new();
-
-        playlists.push(RSyncPlaylistItem {
+impl APIProvider for YoutubeProvider {
+    fn playlist_pages(&mut self) -> PageStream<'_, RSyncPlaylistItem> {
+        let favorites = RSyncPlaylistItem {
             collaborative: false,
             description: Some("Favourite playlist".into()),
             url: format!(
@@ -255,271 +415,468 @@ impl APIProvider for YoutubeProvider {
             public: false,
             tracks: 0,
             r#type: RSyncPlaylistItemProviderData::Youtube,
+        };
+        let provider = &*self;
+        let client = &self.client;
+        let quota = &self.quota;
+        // the liked videos are a playlist of the channel that is not listed with the others
+        let favorites = async move {
+            let tracks = provider.item_count(&provider.liked_playlist_id).await?;
+            Ok(vec![RSyncPlaylistItem {
+                tracks,
+                ..favorites
+            }])
+        };
+        let playlists = paging::token_pages(move |page_token| async move {
+            let result_body = send(quota, LIST_COST, || {
+                client
+                    .playlists()
+                    .list(&vec![
+                        "snippet".into(),
+                        "contentDetails".into(),
+                        "status".into(),
+                    ])
+                    .page_token(page_token.as_deref().unwrap_or_default())
+                    .max_results(PAGE_SIZE)
+                    .mine(true)
+                    .doit()
+            })
+            .await?
+            .1;
+            let playlists = result_body
+                .items
+                .unwrap_or_default()
+                .into_iter()
+                .map(RSyncPlaylistItem::try_from)
+                .collect::<ProviderResult<Vec<RSyncPlaylistItem>>>()?;
+            Ok((playlists, result_body.next_page_token))
         });
+        stream::once(favorites).chain(playlists).boxed()
+    }
 
-        loop {
-            if next_page_token.is_none() {
-                break;
-            }
+    fn playlist_song_pages(&mut self, playlist_id: PlaylistIdWrapper) -> PageStream<'_, RSyncSong> {
+        match playlist_id {
+            PlaylistIdWrapper::Liked => self.playlist_item_pages(self.liked_playlist_id.clone()),
+            PlaylistIdWrapper::Id(playlist_id) => self.playlist_item_pages(playlist_id),
+        }
+    }
 
-            let result = self
-                .client
+    fn playlist_data(&self) -> RSyncPlaylistItemProviderData {
+        RSyncPlaylistItemProviderData::Youtube
+    }
+
+    async fn create_playlist(
+        &mut self,
+        playlist_name: String,
+    ) -> ProviderResult<RSyncPlaylistItem> {
+        let playlist = send(&self.quota, WRITE_COST, || {
+            self.client
                 .playlists()
-                .list(&vec![
-                    "snippet".into(),
-                    "contentDetails".into(),
-                    "status".into(),
-                ])
-                .page_token(next_page_token.unwrap().as_str())
-                .mine(true)
+                .insert(Playlist {
+                    snippet: Some(PlaylistSnippet {
+                        tags: Some(["RustSync".to_string()].into()),
+                        title: Some(playlist_name.clone()),
+                        ..Default::default()
+                    }),
+                    status: Some(PlaylistStatus {
+                        privacy_status: Some("private".into()),
+                    }),
+                    ..Default::default()
+                })
                 .doit()
-                .await;
-            let result_body = result.unwrap().1;
-            next_page_token = result_body.next_page_token.clone();
-
-            for playlist in result_body.items.unwrap() {
-                playlists.push(playlist.into());
-            }
-        }
-        playlists
+        })
+        .await?
+        .1;
+        playlist.try_into()
     }
 
-    async fn get_playlist_songs(
+    async fn update_playlist(
         &mut self,
-        playlist_id: PlaylistIdWrapper,
-        event_sender: Option<(mpsc::UnboundedSender<Event>, u128)>,
-    ) -> Vec<RSyncSong> {
+        playlist: RSyncPlaylistItem,
+        details: PlaylistDetails,
+    ) -> ProviderResult<RSyncPlaylistItem> {
+        let PlaylistIdWrapper::Id(playlist_id) = &playlist.id else {
+            return Err(ProviderError::Permission(
+                "Favorites can not be edited".into(),
+            ));
+        };
+        let privacy_status = match details.public {
+            true => "public",
+            false => "private",
+        };
+        let updated: RSyncPlaylistItem = send(&self.quota, WRITE_COST, || {
+            self.client
+                .playlists()
+                .update(Playlist {
+                    id: Some(playlist_id.clone()),
+                    snippet: Some(PlaylistSnippet {
+                        tags: Some(["RustSync".to_string()].into()),
+                        title: Some(details.name.clone()),
+                        description: details.description.clone(),
+                        ..Default::default()
+                    }),
+                    status: Some(PlaylistStatus {
+                        privacy_status: Some(privacy_status.into()),
+                    }),
+                    ..Default::default()
+                })
+                .doit()
+        })
+        .await?
+        .1
+        .try_into()?;
+        // the update response has no content details
+        Ok(RSyncPlaylistItem {
+            tracks: playlist.tracks,
+            owned: playlist.owned,
+            ..updated
+        })
+    }
+
+    async fn delete_playlist(&mut self, playlist_id: PlaylistIdWrapper) -> ProviderResult<()> {
         match playlist_id {
-            PlaylistIdWrapper::Liked => {
-                self.get_playlist_songs_inner(&self.liked_playlist_id.clone(), event_sender)
-                    .await
-            }
+            PlaylistIdWrapper::Liked => Err(ProviderError::Permission(
+                "Favorites can not be deleted".into(),
+            )),
             PlaylistIdWrapper::Id(playlist_id) => {
-                self.get_playlist_songs_inner(&playlist_id, event_sender)
-                    .await
+                send(&self.quota, WRITE_COST, || {
+                    self.client.playlists().delete(&playlist_id).doit()
+                })
+                .await?;
+                Ok(())
             }
         }
     }
 
-    async fn create_playlist(&mut self, playlist_name: String) {
-        self.client
-            .playlists()
-            .insert(Playlist {
-                snippet: Some(PlaylistSnippet {
-                    tags: Some(["RustSync".to_string()].into()),
-                    title: Some(playlist_name),
-                    ..Default::default()
-                }),
-                status: Some(PlaylistStatus {
-                    privacy_status: Some("private".into()),
-                }),
-                ..Default::default()
-            })
-            .doit()
-            .await
-            .unwrap();
-    }
-
-    async fn add_playlist_song(&mut self, playlist_id: PlaylistIdWrapper, song_id: Vec<String>) {
-        match playlist_id {
+    async fn add_playlist_song_with_progress(
+        &mut self,
+        playlist_id: PlaylistIdWrapper,
+        song_id: Vec<String>,
+        progress: InsertProgress<'_>,
+    ) -> ProviderResult<AddOutcome> {
+        let provider = &*self;
+        match &playlist_id {
+            // a few items are sent at a time, each with its position so the order is kept
             PlaylistIdWrapper::Id(p_id) => {
-                for id in song_id {
-                    self.client
-                        .playlist_items()
-                        .insert(PlaylistItem {
-                            snippet: Some(PlaylistItemSnippet {
-                                playlist_id: Some(p_id.clone()),
-                                resource_id: Some(ResourceId {
-                                    kind: Some("youtube#video".into()),
-                                    video_id: Some(id),
+                let start = self.item_count(p_id).await?;
+                // the items after a failed insert are not sent, their positions would be off
+                let failed = &AtomicBool::new(false);
+                let inserted = stream::iter(song_id.into_iter().zip(start..))
+                    .map(|(id, position)| async move {
+                        if failed.load(Ordering::Relaxed) {
+                            return (id, None);
+                        }
+                        let inserted = send(&provider.quota, WRITE_COST, || {
+                            provider
+                                .client
+                                .playlist_items()
+                                .insert(PlaylistItem {
+                                    snippet: Some(PlaylistItemSnippet {
+                                        playlist_id: Some(p_id.clone()),
+                                        position: Some(position),
+                                        resource_id: Some(ResourceId {
+                                            kind: Some("youtube#video".into()),
+                                            video_id: Some(id.clone()),
+                                            ..Default::default()
+                                        }),
+                                        ..Default::default()
+                                    }),
                                     ..Default::default()
-                                }),
-                                ..Default::default()
-                            }),
-                            ..Default::default()
+                                })
+                                .doit()
                         })
-                        .doit()
-                        .await
-                        .unwrap();
-                }
+                        .await;
+                        if inserted.is_err() {
+                            failed.store(true, Ordering::Relaxed);
+                        }
+                        (id, Some(inserted.map(|_| ())))
+                    })
+                    .buffered(INSERT_CONCURRENCY);
+                Ok(add_outcome(inserted, progress).await)
             }
+            // liked videos have no order, a few ratings are sent at a time
             PlaylistIdWrapper::Liked => {
-                for id in song_id {
-                    self.client
-                        .videos()
-                        .rate(id.as_str(), "like")
-                        .doit()
-                        .await
-                        .unwrap();
-                }
+                let rated = stream::iter(song_id)
+                    .map(|id| async move {
+                        let rated = send(&provider.quota, WRITE_COST, || {
+                            provider.client.videos().rate(id.as_str(), "like").doit()
+                        })
+                        .await;
+                        (id, Some(rated.map(|_| ())))
+                    })
+                    .buffered(INSERT_CONCURRENCY);
+                Ok(add_outcome(rated, progress).await)
             }
         }
     }
 
-    async fn search(&mut self, query: String, limit: u32) -> Vec<RSyncSong> {
-        let search_data = self
-            .client
-            .search()
-            .list(&vec!["snippet".into()])
-            .q(query.as_str())
-            .video_category_id("10")
-            .add_type("video")
-            .doit()
-            .await
-            .unwrap()
-            .1
-            .items
-            .unwrap();
+    async fn search(&mut self, query: String, limit: u32) -> ProviderResult<Vec<RSyncSong>> {
+        let search_data = send(&self.quota, SEARCH_COST, || {
+            self.client
+                .search()
+                .list(&vec!["snippet".into()])
+                .q(query.as_str())
+                .video_category_id("10")
+                .add_type("video")
+                .doit()
+        })
+        .await?
+        .1
+        .items
+        .unwrap_or_default();
 
         let mut songs = Vec::new();
 
         //all this second request mess for almost nothing
         let song_ids: Vec<String> = search_data
             .iter()
-            .map(|data: &SearchResult| -> String { data.id.clone().unwrap().video_id.unwrap() })
+            .filter_map(|data: &SearchResult| data.id.as_ref()?.video_id.clone())
             .collect();
 
         if song_ids.is_empty() {
-            return songs;
+            return Ok(songs);
         }
 
-        let detailed_song_data = self.get_detailed_video_data(song_ids).await;
+        let detailed_song_data = self.get_detailed_video_data(song_ids).await?;
 
-        for (song, detailed_song) in search_data.iter().zip(detailed_song_data) {
-            if detailed_song.snippet.unwrap().category_id.unwrap() == "10" {
-                //all this mess up here just to get the category number to filter just the music cause youtube-music api does not have public access
-                songs.push(song.clone().into());
+        for (song, detailed_song) in search_data.into_iter().zip(detailed_song_data) {
+            if is_music_video(&detailed_song) {
+                let mut song: RSyncSong = song.try_into()?;
+                song.metadata.duration_ms = video_duration_ms(&detailed_song);
+                songs.push(song);
             }
         }
 
-        if songs.len() as u32 > limit {
-            songs = songs[0..((limit + 1) as usize)].to_vec();
-        }
+        songs.truncate(limit as usize);
 
-        songs
+        Ok(songs)
     }
 
-    async fn search_list(&mut self, items: Vec<RSyncSong>) -> Vec<RSyncSong> {
-        let mut songs = Vec::new();
+    /// Refused up front if the searches do not fit into the quota, a search costs 100 units.
+    async fn search_list(&mut self, items: Vec<RSyncSong>) -> ProviderResult<Vec<SongMatch>> {
+        let cost = items.len() as u32 * (SEARCH_COST + LIST_COST);
+        if cost > self.quota.remaining() {
+            return Err(ProviderError::RateLimited(format!(
+                "Searching {} songs needs {} quota units, {} are left today",
+                items.len(),
+                cost,
+                self.quota.remaining()
+            )));
+        }
+        let mut matches = Vec::new();
         for item in items {
             let found = self
-                .search(format!("{} ({})", item.name, item.artists), 1)
-                .await;
-            songs.push(found[0].clone());
+                .search(matching::search_query(&item), matching::CANDIDATE_LIMIT)
+                .await?;
+            matches.push(matching::best_match(item, found));
         }
-        songs
+        Ok(matches)
     }
 
-    async fn rem_playlist_song(&mut self, _playlist_id: PlaylistIdWrapper, song_ids: Vec<String>) {
+    async fn rem_playlist_song(
+        &mut self,
+        _playlist_id: PlaylistIdWrapper,
+        song_ids: Vec<String>,
+    ) -> ProviderResult<()> {
         for id in song_ids {
-            self.client
-                .playlist_items()
-                .delete(&id)
-                .doit()
-                .await
-                .unwrap();
+            send(&self.quota, WRITE_COST, || {
+                self.client.playlist_items().delete(&id).doit()
+            })
+            .await?;
         }
+        Ok(())
     }
-}
 
-impl YoutubeProvider {
-    async fn get_playlist_songs_inner(
+    async fn move_playlist_songs(
         &mut self,
-        playlist_id: &String,
-        event_sender: Option<(mpsc::UnboundedSender<Event>, u128)>,
-    ) -> Vec<RSyncSong> {
-        let mut next_page_token: Option<String> = Some("".into());
-        let mut songs: Vec<RSyncSong> = Vec::new();
+        playlist_id: PlaylistIdWrapper,
+        moves: Vec<SongMove>,
+    ) -> ProviderResult<()> {
+        let PlaylistIdWrapper::Id(playlist_id) = playlist_id else {
+            return Err(ProviderError::Permission(
+                "Favorites are ordered by the date they were liked".into(),
+            ));
+        };
+        // the moves skip the videos that are not music, the playlist positions count them
+        let shown: HashSet<String> = self
+            .playlist_item_pages(playlist_id.clone())
+            .try_concat()
+            .await?
+            .iter()
+            .map(RSyncSong::removal_id)
+            .collect();
+        let items = self.playlist_item_ids(&playlist_id).await?;
+        let mut positions = PlaylistPositions::new(
+            items
+                .into_iter()
+                .map(|item| {
+                    let is_shown = shown.contains(&item.0);
+                    (item, is_shown)
+                })
+                .collect(),
+        );
 
-        loop {
-            if next_page_token.is_none() {
-                break;
+        for song_move in moves {
+            let song_move = positions.translate(&song_move)?;
+            if song_move.is_noop() {
+                continue;
             }
+            // items are moved one by one, moving down each goes behind the ones moved before
+            let destination = song_move.destination();
+            for offset in 0..song_move.range_length {
+                let position = match song_move.insert_before > song_move.range_start {
+                    true => destination + song_move.range_length - 1,
+                    false => destination + offset,
+                };
+                let Some((item_id, video_id)) = positions.get(destination + offset).cloned() else {
+                    break;
+                };
+                send(&self.quota, WRITE_COST, || {
+                    self.client
+                        .playlist_items()
+                        .update(PlaylistItem {
+                            id: Some(item_id.clone()),
+                            snippet: Some(PlaylistItemSnippet {
+                                playlist_id: Some(playlist_id.clone()),
+                                position: Some(position as u32),
+                                resource_id: Some(ResourceId {
+                                    kind: Some("youtube#video".into()),
+                                    video_id: Some(video_id.clone()),
+                                    ..Default::default()
+                                }),
+                                ..Default::default()
+                            }),
+                            ..Default::default()
+                        })
+                        .doit()
+                })
+                .await?;
+            }
+        }
+        Ok(())
+    }
 
-            let mut songs_inner: Vec<RSyncSong> = Vec::new();
-            let result_body = self
-                .client
-                .playlist_items()
-                .list(&vec![
-                    "snippet".into(),
-                    "contentDetails".into(),
-                    "status".into(),
-                ])
-                .playlist_id(playlist_id.as_str())
-                .page_token(next_page_token.unwrap().as_str())
-                .doit()
-                .await
-                .unwrap()
-                .1;
-            next_page_token = result_body.next_page_token.clone();
+    fn quota(&self) -> Option<Quota> {
+        Some(self.quota.clone())
+    }
+}
 
-            //all this second request mess for almost nothing
-            let song_ids: Vec<String> = result_body
-                .items
-                .as_ref()
-                .unwrap()
-                .iter()
-                .map(|data| -> String {
-                    data.snippet
-                        .clone()
-                        .unwrap()
-                        .resource_id
-                        .unwrap()
-                        .video_id
-                        .unwrap()
-                        .clone()
+impl YoutubeProvider {
+    /// Music videos of a playlist, every page of items costs a second request for the details.
+    fn playlist_item_pages(&self, playlist_id: String) -> PageStream<'_, RSyncSong> {
+        paging::token_pages(move |page_token| {
+            let playlist_id = playlist_id.clone();
+            async move {
+                let result_body = send(&self.quota, LIST_COST, || {
+                    self.client
+                        .playlist_items()
+                        .list(&vec![
+                            "snippet".into(),
+                            "contentDetails".into(),
+                            "status".into(),
+                        ])
+                        .playlist_id(playlist_id.as_str())
+                        .page_token(page_token.as_deref().unwrap_or_default())
+                        .max_results(PAGE_SIZE)
+                        .doit()
                 })
-                .collect();
-
-            if song_ids.is_empty() {
-                return songs;
-            }
+                .await?
+                .1;
+                let items = result_body.items.unwrap_or_default();
 
-            let detailed_song_data = self.get_detailed_video_data(song_ids).await;
+                //all this second request mess for almost nothing
+                let song_ids: Vec<String> = items.iter().filter_map(video_id_of).collect();
+                if song_ids.is_empty() {
+                    return Ok((Vec::new(), result_body.next_page_token));
+                }
 
-            for (song, detailed_song) in result_body
-                .items
-                .as_ref()
-                .unwrap()
-                .iter()
-                .zip(detailed_song_data)
-            {
-                //all this mess up here just to get the category number to filter just the music cause youtube-music api does not have public access
-                if detailed_song.snippet.unwrap().category_id.unwrap() == "10" {
-                    songs_inner.push(song.clone().into());
+                let detailed_song_data = self.get_detailed_video_data(song_ids).await?;
+                let mut songs: Vec<RSyncSong> = Vec::new();
+                for (song, detailed_song) in items.into_iter().zip(detailed_song_data) {
+                    if is_music_video(&detailed_song) {
+                        let mut song: RSyncSong = song.try_into()?;
+                        song.metadata.duration_ms = video_duration_ms(&detailed_song);
+                        songs.push(song);
+                    }
                 }
+                Ok((songs, result_body.next_page_token))
             }
+        })
+    }
 
-            if let Some(event_sender) = event_sender.clone() {
-                event_sender
-                    .0
-                    .send(Event::DataReceived(
-                        event_sender.1,
-                        GlobalEvent::Youtube(GlobalEventData::Songs(
-                            GlobalEventDataFullfilness::Partial(songs_inner.clone()),
-                        )),
-                    ))
-                    .unwrap();
+    /// Number of items of a playlist, music or not.
+    async fn item_count(&self, playlist_id: &str) -> ProviderResult<u32> {
+        let response = send(&self.quota, LIST_COST, || {
+            self.client
+                .playlists()
+                .list(&vec!["contentDetails".into()])
+                .add_id(playlist_id)
+                .doit()
+        })
+        .await?
+        .1;
+        Ok(response
+            .items
+            .unwrap_or_default()
+            .into_iter()
+            .find_map(|playlist| playlist.content_details?.item_count)
+            .unwrap_or_default())
+    }
+
+    /// Playlist item ids with their video ids of every item, music or not, in playlist order.
+    async fn playlist_item_ids(
+        &mut self,
+        playlist_id: &str,
+    ) -> ProviderResult<Vec<(String, String)>> {
+        let mut next_page_token: Option<String> = Some("".into());
+        let mut items: Vec<(String, String)> = Vec::new();
+
+        while let Some(page_token) = next_page_token {
+            let result_body = send(&self.quota, LIST_COST, || {
+                self.client
+                    .playlist_items()
+                    .list(&vec!["snippet".into()])
+                    .playlist_id(playlist_id)
+                    .page_token(page_token.as_str())
+                    .max_results(PAGE_SIZE)
+                    .doit()
+            })
+            .await?
+            .1;
+            next_page_token = result_body.next_page_token;
+            for item in result_body.items.unwrap_or_default() {
+                let video_id = video_id_of(&item)
+                    .ok_or_else(|| ProviderError::malformed("video id of playlist item"))?;
+                let item_id = item
+                    .id
+                    .ok_or_else(|| ProviderError::malformed("playlist item id"))?;
+                items.push((item_id, video_id));
             }
-            songs.append(&mut songs_inner);
         }
-
-        songs
+        Ok(items)
     }
 
-    pub async fn get_detailed_video_data(&mut self, song_ids: Vec<String>) -> Vec<Video> {
+    pub async fn get_detailed_video_data(
+        &self,
+        song_ids: Vec<String>,
+    ) -> ProviderResult<Vec<Video>> {
         //all this second request mess for almost nothing
-        let mut detailed_song_data_request = self.client.videos().list(&vec!["snippet".into()]);
-
-        for song_id in song_ids {
-            detailed_song_data_request = detailed_song_data_request.add_id(&song_id.clone());
+        let mut videos = Vec::new();
+        for song_ids in song_ids.chunks(PAGE_SIZE as usize) {
+            let response = send(&self.quota, LIST_COST, || {
+                let mut detailed_song_data_request = self
+                    .client
+                    .videos()
+                    .list(&vec!["snippet".into(), "contentDetails".into()]);
+                for song_id in song_ids {
+                    detailed_song_data_request = detailed_song_data_request.add_id(song_id);
+                }
+                detailed_song_data_request.doit()
+            })
+            .await?
+            .1;
+            videos.extend(response.items.unwrap_or_default());
         }
-        detailed_song_data_request
-            .doit()
-            .await
-            .unwrap()
-            .1
-            .items
-            .unwrap()
+        Ok(videos)
     }
 }
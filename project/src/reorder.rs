@@ -0,0 +1,397 @@
+use std::{cmp::Ordering, ops::Range};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    matching,
+    providers::{
+        provider_error::{ProviderError, ProviderResult},
+        provider_traits::APIProvider,
+    },
+    types::music_types::{PlaylistIdWrapper, RSyncSong},
+};
+
+/// Moves a block of songs inside of a playlist, the same semantics as Spotify's reorder call.
+///
+/// `range_start` and `insert_before` are positions in the playlist before the move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SongMove {
+    pub range_start: usize,
+    pub range_length: usize,
+    pub insert_before: usize,
+}
+
+impl SongMove {
+    /// Songs of the block in a playlist of `len` songs, fails if the move does not fit into it.
+    pub fn range(&self, len: usize) -> ProviderResult<Range<usize>> {
+        let range = self.range_start..self.range_start + self.range_length;
+        let inside_block = range.start < self.insert_before && self.insert_before < range.end;
+        match range.end > len || self.insert_before > len || inside_block {
+            true => Err(ProviderError::NotFound(
+                "Playlist changed while reordering, refresh the songs".into(),
+            )),
+            false => Ok(range),
+        }
+    }
+
+    /// Position of the first song of the block once it is moved.
+    pub fn destination(&self) -> usize {
+        match self.insert_before > self.range_start {
+            true => self.insert_before - self.range_length,
+            false => self.insert_before,
+        }
+    }
+
+    pub fn is_noop(&self) -> bool {
+        self.range_length == 0 || self.destination() == self.range_start
+    }
+
+    pub fn apply<T>(&self, items: &mut Vec<T>) -> ProviderResult<()> {
+        let range = self.range(items.len())?;
+        let block: Vec<T> = items.drain(range).collect();
+        let destination = self.destination();
+        items.splice(destination..destination, block);
+        Ok(())
+    }
+
+    /// Move that puts the block back where it was.
+    pub fn inverse(&self) -> SongMove {
+        let destination = self.destination();
+        SongMove {
+            range_start: destination,
+            range_length: self.range_length,
+            insert_before: match self.range_start > destination {
+                true => self.range_start + self.range_length,
+                false => self.range_start,
+            },
+        }
+    }
+}
+
+/// Order the sort command puts a playlist in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortKey {
+    Title,
+    Artist,
+    DateAdded,
+}
+
+impl SortKey {
+    pub const ALL: [SortKey; 3] = [SortKey::Title, SortKey::Artist, SortKey::DateAdded];
+
+    fn compare(&self, a: &RSyncSong, b: &RSyncSong) -> Ordering {
+        let title = || matching::normalise(&a.name).cmp(&matching::normalise(&b.name));
+        match self {
+            SortKey::Title => title(),
+            SortKey::Artist => matching::normalise(&a.artists)
+                .cmp(&matching::normalise(&b.artists))
+                .then_with(title),
+            // songs without a date keep their place at the end
+            SortKey::DateAdded => match (&a.metadata.added_at, &b.metadata.added_at) {
+                (Some(a), Some(b)) => a.cmp(b),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            },
+        }
+    }
+}
+
+impl From<SortKey> for ratatui::text::Text<'static> {
+    fn from(key: SortKey) -> Self {
+        match key {
+            SortKey::Title => "Title".into(),
+            SortKey::Artist => "Artist".into(),
+            SortKey::DateAdded => "Date added".into(),
+        }
+    }
+}
+
+/// Current positions of the songs in their sorted order, equal songs keep their order.
+pub fn sorted_order(songs: &[RSyncSong], key: SortKey) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..songs.len()).collect();
+    order.sort_by(|a, b| key.compare(&songs[*a], &songs[*b]));
+    order
+}
+
+/// Positions of the longest increasing subsequence of `ranks`.
+fn longest_increasing(ranks: &[usize]) -> Vec<usize> {
+    // tails[l] is the position ending the best subsequence of length l + 1
+    let mut tails: Vec<usize> = Vec::new();
+    let mut previous: Vec<Option<usize>> = vec![None; ranks.len()];
+    for (position, rank) in ranks.iter().enumerate() {
+        let length = tails.partition_point(|tail| ranks[*tail] < *rank);
+        previous[position] = length.checked_sub(1).map(|length| tails[length]);
+        match length == tails.len() {
+            true => tails.push(position),
+            false => tails[length] = position,
+        }
+    }
+    let mut positions = Vec::new();
+    let mut current = tails.last().copied();
+    while let Some(position) = current {
+        positions.push(position);
+        current = previous[position];
+    }
+    positions.reverse();
+    positions
+}
+
+/// Single song moves turning the playlist into `order`, where `order[i]` is the current
+/// position of the song that should end up at `i`.
+///
+/// The longest run of songs already in the right order stays in place, every other song
+/// is moved once behind its new predecessor, so no shorter list of single moves exists.
+pub fn plan_moves(order: &[usize]) -> Vec<SongMove> {
+    let mut ranks = vec![0; order.len()];
+    for (rank, position) in order.iter().enumerate() {
+        ranks[*position] = rank;
+    }
+    let mut kept = vec![false; order.len()];
+    for position in longest_increasing(&ranks) {
+        kept[ranks[position]] = true;
+    }
+
+    // ranks of the songs in their current playlist order
+    let mut current = ranks;
+    let mut moves = Vec::new();
+    for rank in (0..order.len()).filter(|rank| !kept[*rank]) {
+        let range_start = current.iter().position(|r| *r == rank).unwrap();
+        let insert_before = match rank {
+            0 => 0,
+            _ => current.iter().position(|r| *r == rank - 1).unwrap() + 1,
+        };
+        let song_move = SongMove {
+            range_start,
+            range_length: 1,
+            insert_before,
+        };
+        if !song_move.is_noop() {
+            // the plan only refers to positions inside of `current`
+            let _ = song_move.apply(&mut current);
+            moves.push(song_move);
+        }
+    }
+    moves
+}
+
+/// Puts the songs of the playlist into `order` with as few moves as possible.
+pub async fn reorder<P: APIProvider>(
+    provider: &mut P,
+    playlist_id: PlaylistIdWrapper,
+    order: &[usize],
+) -> ProviderResult<usize> {
+    let moves = plan_moves(order);
+    let count = moves.len();
+    if !moves.is_empty() {
+        provider.move_playlist_songs(playlist_id, moves).await?;
+    }
+    Ok(count)
+}
+
+/// Songs a service lists in a playlist, `shown` are the ones the app lists as well.
+///
+/// Services count every item of a playlist, e.g. Spotify episodes or YouTube videos that
+/// are not music, while the moves refer to the shown songs only.
+#[derive(Clone, Debug)]
+pub struct PlaylistPositions<T> {
+    items: Vec<(T, bool)>,
+}
+
+impl<T> PlaylistPositions<T> {
+    pub fn new(items: Vec<(T, bool)>) -> Self {
+        Self { items }
+    }
+
+    /// Item at `position` of the whole playlist.
+    pub fn get(&self, position: usize) -> Option<&T> {
+        self.items.get(position).map(|(item, _)| item)
+    }
+
+    /// Translates a move of shown songs into a move of the whole playlist and applies it.
+    ///
+    /// Hidden items between the moved songs are moved along with them.
+    pub fn translate(&mut self, song_move: &SongMove) -> ProviderResult<SongMove> {
+        let shown: Vec<usize> = (0..self.items.len())
+            .filter(|position| self.items[*position].1)
+            .collect();
+        let range = song_move.range(shown.len())?;
+        if range.is_empty() {
+            return Ok(SongMove {
+                range_start: 0,
+                range_length: 0,
+                insert_before: 0,
+            });
+        }
+        let range_start = shown[range.start];
+        let translated = SongMove {
+            range_start,
+            range_length: shown[range.end - 1] + 1 - range_start,
+            insert_before: shown
+                .get(song_move.insert_before)
+                .copied()
+                .unwrap_or(self.items.len()),
+        };
+        translated.apply(&mut self.items)?;
+        Ok(translated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::music_types::RSyncSongProviderData;
+
+    fn song(name: &str) -> RSyncSong {
+        RSyncSong {
+            artists: String::new(),
+            url: String::new(),
+            id: name.to_string(),
+            name: name.to_string(),
+            metadata: Default::default(),
+            r#type: RSyncSongProviderData::Local,
+        }
+    }
+
+    fn song_move(range_start: usize, range_length: usize, insert_before: usize) -> SongMove {
+        SongMove {
+            range_start,
+            range_length,
+            insert_before,
+        }
+    }
+
+    #[test]
+    fn planned_moves_sort_the_playlist() {
+        // titles and the moves needed, songs outside the longest sorted run move once
+        let cases: [(&[&str], usize); 8] = [
+            (&[], 0),
+            (&["a", "b", "c"], 0),
+            (&["b", "a"], 1),
+            (&["d", "a", "b", "c"], 1),
+            (&["b", "c", "d", "a"], 1),
+            (&["c", "a", "d", "b", "e"], 2),
+            (&["b", "a", "d", "c", "f", "e"], 3),
+            (&["e", "d", "c", "b", "a"], 4),
+        ];
+        for (titles, expected) in cases {
+            let songs: Vec<RSyncSong> = titles.iter().map(|title| song(title)).collect();
+            let moves = plan_moves(&sorted_order(&songs, SortKey::Title));
+
+            let mut sorted: Vec<&str> = titles.to_vec();
+            for song_move in moves.iter() {
+                song_move.apply(&mut sorted).unwrap();
+            }
+            let mut expected_order = titles.to_vec();
+            expected_order.sort();
+            assert_eq!(sorted, expected_order, "{:?}", titles);
+            assert_eq!(moves.len(), expected, "{:?}", titles);
+        }
+    }
+
+    #[test]
+    fn longest_increasing_run() {
+        let cases: [(&[usize], &[usize]); 5] = [
+            (&[], &[]),
+            (&[0, 1, 2], &[0, 1, 2]),
+            (&[2, 1, 0], &[2]),
+            (&[2, 0, 3, 1, 4], &[1, 3, 4]),
+            (&[3, 1, 2, 0, 4], &[1, 2, 4]),
+        ];
+        for (ranks, expected) in cases {
+            assert_eq!(longest_increasing(ranks), expected, "{:?}", ranks);
+        }
+    }
+
+    #[test]
+    fn inverse_puts_the_block_back() {
+        let moves = [
+            // down
+            song_move(0, 1, 3),
+            song_move(1, 3, 6),
+            // up
+            song_move(4, 2, 1),
+            song_move(5, 1, 0),
+            // in place
+            song_move(2, 2, 2),
+        ];
+        let original: Vec<usize> = (0..6).collect();
+        for song_move in moves {
+            let mut items = original.clone();
+            song_move.apply(&mut items).unwrap();
+            let destination = song_move.destination();
+            assert_eq!(
+                items[destination..destination + song_move.range_length],
+                original[song_move.range(original.len()).unwrap()],
+                "{:?}",
+                song_move
+            );
+
+            song_move.inverse().apply(&mut items).unwrap();
+            assert_eq!(items, original, "{:?}", song_move);
+        }
+    }
+
+    #[test]
+    fn moves_outside_of_the_playlist_fail() {
+        let mut items: Vec<usize> = (0..6).collect();
+        for song_move in [song_move(0, 2, 1), song_move(5, 2, 0), song_move(0, 1, 7)] {
+            assert!(song_move.apply(&mut items).is_err(), "{:?}", song_move);
+        }
+        assert_eq!(items, (0..6).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn translate_moves_hidden_items_inside_of_the_block() {
+        // x and y are not listed, e.g. videos that are not music
+        let playlist = || {
+            PlaylistPositions::new(
+                ["a", "x", "b", "c", "y", "d"]
+                    .into_iter()
+                    .map(|item| (item, !matches!(item, "x" | "y")))
+                    .collect(),
+            )
+        };
+        let items = |positions: &PlaylistPositions<&'static str>| -> Vec<&str> {
+            (0..6)
+                .filter_map(|position| positions.get(position).copied())
+                .collect()
+        };
+        let cases = [
+            // last shown song to the front
+            (
+                song_move(3, 1, 0),
+                song_move(5, 1, 0),
+                ["d", "a", "x", "b", "c", "y"],
+            ),
+            // block with a hidden item inside to the end
+            (
+                song_move(0, 2, 4),
+                song_move(0, 3, 6),
+                ["c", "y", "d", "a", "x", "b"],
+            ),
+            // hidden item before the destination stays in front of it
+            (
+                song_move(1, 1, 3),
+                song_move(2, 1, 5),
+                ["a", "x", "c", "y", "b", "d"],
+            ),
+        ];
+        for (shown_move, expected_move, expected_items) in cases {
+            let mut positions = playlist();
+            assert_eq!(positions.translate(&shown_move).unwrap(), expected_move);
+            assert_eq!(items(&positions), expected_items);
+        }
+
+        // the positions follow the applied moves
+        let mut positions = playlist();
+        positions.translate(&song_move(3, 1, 0)).unwrap();
+        assert_eq!(
+            positions.translate(&song_move(2, 1, 1)).unwrap(),
+            song_move(3, 1, 1)
+        );
+        assert_eq!(items(&positions), ["d", "b", "a", "x", "c", "y"]);
+
+        assert!(playlist().translate(&song_move(4, 1, 0)).is_err());
+    }
+}
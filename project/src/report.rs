@@ -0,0 +1,176 @@
+use std::{fs, io, path::PathBuf};
+
+use serde::Serialize;
+
+use crate::types::music_types::RSyncSong;
+
+/// What happened to one song of a transfer.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SongStatus {
+    Added,
+    Skipped,
+    Failed,
+    NotFound,
+}
+
+impl SongStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SongStatus::Added => "added",
+            SongStatus::Skipped => "skipped",
+            SongStatus::Failed => "failed",
+            SongStatus::NotFound => "not found",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ReportEntry {
+    pub status: SongStatus,
+    /// Song selected in the source playlist.
+    pub source: RSyncSong,
+    /// Song of the target provider it was matched to.
+    pub matched: Option<RSyncSong>,
+    pub confidence: Option<f32>,
+    pub reason: String,
+}
+
+/// Per song outcome of one transfer.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct TransferReport {
+    pub entries: Vec<ReportEntry>,
+}
+
+impl TransferReport {
+    pub fn push(
+        &mut self,
+        status: SongStatus,
+        source: RSyncSong,
+        matched: Option<RSyncSong>,
+        confidence: Option<f32>,
+        reason: impl Into<String>,
+    ) {
+        self.entries.push(ReportEntry {
+            status,
+            source,
+            matched,
+            confidence,
+            reason: reason.into(),
+        });
+    }
+
+    pub fn count(&self, status: SongStatus) -> usize {
+        self.entries
+            .iter()
+            .filter(|entry| entry.status == status)
+            .count()
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "Added {}, skipped {}, failed {}, not found {}",
+            self.count(SongStatus::Added),
+            self.count(SongStatus::Skipped),
+            self.count(SongStatus::Failed),
+            self.count(SongStatus::NotFound),
+        )
+    }
+
+    /// One line per song, leftovers first so they are visible without scrolling.
+    pub fn lines(&self) -> Vec<String> {
+        let mut entries: Vec<&ReportEntry> = self.entries.iter().collect();
+        entries.sort_by_key(|entry| entry.status == SongStatus::Added);
+        entries
+            .into_iter()
+            .map(|entry| {
+                let mut line = format!(
+                    "[{}] {} ({})",
+                    entry.status.label(),
+                    entry.source.name,
+                    entry.source.artists
+                );
+                if let Some(matched) = &entry.matched {
+                    line.push_str(&format!(" -> {} ({})", matched.name, matched.artists));
+                }
+                if let Some(confidence) = entry.confidence {
+                    line.push_str(&format!(" {:.0}%", confidence * 100.0));
+                }
+                if !entry.reason.is_empty() {
+                    line.push_str(&format!(", {}", entry.reason));
+                }
+                line
+            })
+            .collect()
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "status,source_name,source_artists,source_url,matched_name,matched_artists,matched_url,confidence,reason\n",
+        );
+        for entry in self.entries.iter() {
+            let matched = entry.matched.as_ref();
+            let row = [
+                entry.status.label().to_string(),
+                entry.source.name.clone(),
+                entry.source.artists.clone(),
+                entry.source.url.clone(),
+                matched.map(|song| song.name.clone()).unwrap_or_default(),
+                matched.map(|song| song.artists.clone()).unwrap_or_default(),
+                matched.map(|song| song.url.clone()).unwrap_or_default(),
+                entry
+                    .confidence
+                    .map(|confidence| format!("{:.2}", confidence))
+                    .unwrap_or_default(),
+                entry.reason.clone(),
+            ];
+            csv.push_str(
+                &row.iter()
+                    .map(|field| csv_field(field))
+                    .collect::<Vec<String>>()
+                    .join(","),
+            );
+            csv.push('\n');
+        }
+        csv
+    }
+
+    /// Writes the report next to `tracing.log` and returns the path of the file.
+    pub fn export(&self, format: ReportFormat) -> io::Result<PathBuf> {
+        let path = PathBuf::from(format!(
+            "transfer_report_{}.{}",
+            chrono::Local::now().format("%Y%m%d_%H%M%S"),
+            format.extension()
+        ));
+        let data = match format {
+            ReportFormat::Json => serde_json::to_string_pretty(self)?,
+            ReportFormat::Csv => self.to_csv(),
+        };
+        fs::write(&path, data)?;
+        Ok(path)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum ReportFormat {
+    Json,
+    Csv,
+}
+
+impl ReportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ReportFormat::Json => "json",
+            ReportFormat::Csv => "csv",
+        }
+    }
+}
+
+/// Quotes a CSV field when it contains a separator, quote or newline.
+pub fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -0,0 +1,47 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use tokio::task::JoinHandle;
+
+static LAST_REQUEST_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Id of a request whose results come back as [`Event::DataReceived`](crate::event::Event::DataReceived),
+/// ids only grow and are never handed out twice while the app runs.
+pub fn next_request_id() -> u128 {
+    (LAST_REQUEST_ID.fetch_add(1, Ordering::Relaxed) + 1) as u128
+}
+
+/// Latest request for one kind of data, e.g. the songs of a column.
+///
+/// Starting a request aborts the one before, so a superseded listing stops paging through
+/// the service instead of running on with results nobody looks at.
+#[derive(Debug, Default)]
+pub struct RequestTracker {
+    current: u128,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl RequestTracker {
+    /// Aborts the previous request and returns the id of the new one, see [`RequestTracker::track`].
+    pub fn start(&mut self) -> u128 {
+        self.abort();
+        self.current = next_request_id();
+        self.current
+    }
+
+    /// Keeps the task of the request started last so the next one can abort it.
+    pub fn track(&mut self, handle: JoinHandle<()>) {
+        self.handle = Some(handle);
+    }
+
+    /// Stops the running request, its results that are still underway are ignored.
+    pub fn abort(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            handle.abort();
+        }
+        self.current = 0;
+    }
+
+    pub fn is_current(&self, request_id: u128) -> bool {
+        self.current != 0 && self.current == request_id
+    }
+}
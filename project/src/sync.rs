@@ -0,0 +1,621 @@
+use std::{
+    collections::{HashMap, HashSet},
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{
+    event::TransferUpdateEventData,
+    matching::SongMatch,
+    providers::{
+        provider_error::{ProviderError, ProviderResult},
+        provider_traits::APIProvider,
+        registry::ProviderId,
+    },
+    transfer::TransferReporter,
+    types::music_types::{
+        PlaylistIdWrapper, RSyncPlaylistItem, RSyncPlaylistItemProviderData, RSyncSong,
+    },
+};
+
+/// Same song on both sides of a pair, ids are in the order of [`SyncPair::sides`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SongLink {
+    pub ids: [String; 2],
+}
+
+/// One playlist of a linked pair.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SyncSide {
+    pub provider_id: ProviderId,
+    pub playlist_id: PlaylistIdWrapper,
+    pub name: String,
+    /// Snapshot of the playlist after the last sync, unchanged snapshot means the side
+    /// does not have to be fetched again. Only Spotify playlists have one.
+    #[serde(default)]
+    pub snapshot_id: Option<String>,
+}
+
+impl SyncSide {
+    pub fn new(provider_id: ProviderId, playlist_id: PlaylistIdWrapper, name: String) -> Self {
+        Self {
+            provider_id,
+            playlist_id,
+            name,
+            snapshot_id: None,
+        }
+    }
+
+    fn is_same_playlist(&self, other: &SyncSide) -> bool {
+        self.provider_id == other.provider_id && self.playlist_id == other.playlist_id
+    }
+}
+
+/// Two playlists of different providers kept in sync.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SyncPair {
+    pub sides: [SyncSide; 2],
+    /// Song mapping as it was after the last sync.
+    #[serde(default)]
+    pub links: Vec<SongLink>,
+}
+
+impl SyncPair {
+    pub fn new(first: SyncSide, second: SyncSide) -> Self {
+        Self {
+            sides: [first, second],
+            links: Vec::new(),
+        }
+    }
+}
+
+/// Persisted list of linked playlists.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SyncState {
+    pub pairs: Vec<SyncPair>,
+}
+
+impl SyncState {
+    /// Location of the sync state, `RSYNC_SYNC_STATE` or `sync_state.json` next to the binary.
+    pub fn default_path() -> PathBuf {
+        env::var_os("RSYNC_SYNC_STATE")
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("sync_state.json"))
+    }
+
+    pub fn load(path: &Path) -> Self {
+        match fs::read_to_string(path) {
+            Ok(data) => serde_json::from_str(&data).unwrap_or_else(|err| {
+                warn!("Ignoring invalid sync state {}: {}", path.display(), err);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+
+    /// Links the two playlists, an existing link of either playlist is replaced.
+    pub fn link(&mut self, pair: SyncPair) {
+        self.pairs.retain(|existing| {
+            !existing.sides.iter().any(|side| {
+                pair.sides
+                    .iter()
+                    .any(|linked| linked.is_same_playlist(side))
+            })
+        });
+        self.pairs.push(pair);
+    }
+}
+
+/// What one sync run changed.
+#[derive(Clone, Debug, Default)]
+pub struct SyncSummary {
+    pub pairs: usize,
+    pub added: usize,
+    pub removed: usize,
+    pub unmatched: usize,
+    /// Songs whose best search result was not trusted enough to be linked.
+    pub uncertain: usize,
+    /// Pairs left out because one of their providers is not registered.
+    pub skipped: usize,
+}
+
+impl SyncSummary {
+    pub fn message(&self) -> String {
+        format!(
+            "Synced {} playlist pairs\nAdded: {}\nRemoved: {}\nNot found on the other side: {}\nLow confidence, not linked: {}\nSkipped, provider not available: {}",
+            self.pairs,
+            self.added,
+            self.removed,
+            self.unmatched,
+            self.uncertain,
+            self.skipped,
+        )
+    }
+}
+
+/// Changes on one side since the last sync.
+#[derive(Default)]
+struct SideDiff {
+    added: Vec<RSyncSong>,
+    removed: Vec<SongLink>,
+}
+
+fn diff_side(current: &[RSyncSong], links: &[SongLink], side: usize) -> SideDiff {
+    let current_ids: HashSet<&String> = current.iter().map(|song| &song.id).collect();
+    let linked_ids: HashSet<&String> = links.iter().map(|link| &link.ids[side]).collect();
+    SideDiff {
+        added: current
+            .iter()
+            .filter(|song| !linked_ids.contains(&song.id))
+            .cloned()
+            .collect(),
+        removed: links
+            .iter()
+            .filter(|link| !current_ids.contains(&link.ids[side]))
+            .cloned()
+            .collect(),
+    }
+}
+
+/// Snapshot of the side's playlist in the `playlists` of its provider.
+fn playlist_snapshot(
+    playlists: &[RSyncPlaylistItem],
+    side: &SyncSide,
+) -> ProviderResult<Option<String>> {
+    let playlist = playlists
+        .iter()
+        .find(|playlist| playlist.id == side.playlist_id)
+        .ok_or_else(|| ProviderError::NotFound(format!("Linked playlist {}", side.name)))?;
+    match &playlist.r#type {
+        RSyncPlaylistItemProviderData::Spotify(data) if !data.snapshot_id.is_empty() => {
+            Ok(Some(data.snapshot_id.clone()))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Playlists of the providers with an id in `ids`, each provider lists them once.
+async fn list_playlists<P: APIProvider>(
+    providers: &mut [(ProviderId, P)],
+    ids: &HashSet<ProviderId>,
+) -> ProviderResult<HashMap<ProviderId, Vec<RSyncPlaylistItem>>> {
+    let mut playlists = HashMap::new();
+    for (id, provider) in providers.iter_mut().filter(|(id, _)| ids.contains(id)) {
+        playlists.insert(id.clone(), provider.get_playlists().await?);
+    }
+    Ok(playlists)
+}
+
+/// Finds `song` in the `target` provider, songs already in the target playlist are only linked.
+///
+/// Low confidence matches are counted and left out, syncing them unattended could add
+/// the wrong recording to both playlists.
+async fn find_counterpart<T: APIProvider>(
+    target: &mut T,
+    song: &RSyncSong,
+    summary: &mut SyncSummary,
+) -> ProviderResult<Option<RSyncSong>> {
+    let song_match = target
+        .search_list(vec![song.clone()])
+        .await?
+        .into_iter()
+        .next();
+    match song_match {
+        Some(song_match) if song_match.is_confident() => Ok(song_match.found),
+        Some(SongMatch { found: Some(_), .. }) => {
+            summary.uncertain += 1;
+            Ok(None)
+        }
+        _ => {
+            summary.unmatched += 1;
+            Ok(None)
+        }
+    }
+}
+
+/// Removes the songs of `removed` links from one side.
+///
+/// Without the fetched songs of the side every linked song is assumed to still be there,
+/// the removal id then is the song id.
+async fn remove_counterparts<T: APIProvider>(
+    target: &mut T,
+    playlist_id: &PlaylistIdWrapper,
+    target_songs: Option<&[RSyncSong]>,
+    removed: &[&String],
+) -> ProviderResult<usize> {
+    let removal_ids: Vec<String> = match target_songs {
+        Some(songs) => songs
+            .iter()
+            .filter(|song| removed.contains(&&song.id))
+            .map(|song| song.removal_id())
+            .collect(),
+        None => removed.iter().map(|id| (*id).clone()).collect(),
+    };
+    if removal_ids.is_empty() {
+        return Ok(0);
+    }
+    let count = removal_ids.len();
+    target
+        .rem_playlist_song(playlist_id.clone(), removal_ids)
+        .await?;
+    Ok(count)
+}
+
+/// Applies the changes made on both sides since the last sync of the pair.
+///
+/// `providers` serve the sides of the pair in the same order, `playlists` are their
+/// playlists listed before. Returns the sides that were changed, their snapshot is unknown
+/// until the playlists are listed again.
+pub async fn sync_pair<P: APIProvider>(
+    providers: &mut [P; 2],
+    pair: &mut SyncPair,
+    playlists: [&[RSyncPlaylistItem]; 2],
+    summary: &mut SyncSummary,
+) -> ProviderResult<[bool; 2]> {
+    // a side whose snapshot did not change since the last sync is not fetched
+    let mut songs: [Option<Vec<RSyncSong>>; 2] = [None, None];
+    let mut snapshots: [Option<String>; 2] = [None, None];
+    for side in 0..2 {
+        let snapshot = playlist_snapshot(playlists[side], &pair.sides[side])?;
+        snapshots[side] = snapshot.clone();
+        if snapshot.is_none() || snapshot != pair.sides[side].snapshot_id {
+            songs[side] = Some(
+                providers[side]
+                    .get_playlist_songs(pair.sides[side].playlist_id.clone())
+                    .await?,
+            );
+        }
+    }
+    let diffs: [SideDiff; 2] = [0, 1].map(|side| match &songs[side] {
+        Some(current) => diff_side(current, &pair.links, side),
+        None => SideDiff::default(),
+    });
+
+    // removals, a link removed on one side is removed from the other one as well
+    let mut changed = [false, false];
+    for (side, diff) in diffs.iter().enumerate() {
+        let other = 1 - side;
+        let removed: Vec<&String> = diff.removed.iter().map(|link| &link.ids[other]).collect();
+        let count = remove_counterparts(
+            &mut providers[other],
+            &pair.sides[other].playlist_id,
+            songs[other].as_deref(),
+            &removed,
+        )
+        .await?;
+        summary.removed += count;
+        changed[other] |= count > 0;
+    }
+    pair.links
+        .retain(|link| !diffs.iter().any(|diff| diff.removed.contains(link)));
+
+    // additions, new songs are searched on the other side and linked
+    let mut to_add: [Vec<String>; 2] = [Vec::new(), Vec::new()];
+    for (side, diff) in diffs.iter().enumerate() {
+        let other = 1 - side;
+        let other_ids: HashSet<&String> =
+            songs[other].iter().flatten().map(|song| &song.id).collect();
+        for song in diff.added.iter() {
+            // the song may have just been linked while adding the other side
+            if pair.links.iter().any(|link| link.ids[side] == song.id) {
+                continue;
+            }
+            if let Some(found) = find_counterpart(&mut providers[other], song, summary).await? {
+                let already_linked = pair.links.iter().any(|link| link.ids[other] == found.id);
+                if !other_ids.contains(&found.id) && !already_linked {
+                    to_add[other].push(found.id.clone());
+                }
+                let mut ids = [song.id.clone(), song.id.clone()];
+                ids[other] = found.id;
+                pair.links.push(SongLink { ids });
+            }
+        }
+    }
+
+    for (side, song_ids) in to_add.into_iter().enumerate() {
+        if !song_ids.is_empty() {
+            let outcome = providers[side]
+                .add_playlist_song(pair.sides[side].playlist_id.clone(), song_ids)
+                .await?;
+            summary.added += outcome.added.len();
+            changed[side] |= !outcome.added.is_empty();
+            outcome.into_result()?;
+        }
+    }
+
+    for ((side, snapshot), changed) in pair.sides.iter_mut().zip(snapshots).zip(changed) {
+        side.snapshot_id = match changed {
+            true => None,
+            false => snapshot,
+        };
+    }
+    summary.pairs += 1;
+    Ok(changed)
+}
+
+fn pair_providers<P: APIProvider>(
+    providers: &[(ProviderId, P)],
+    pair: &SyncPair,
+) -> Option<[P; 2]> {
+    let provider = |side: &SyncSide| {
+        providers
+            .iter()
+            .find(|(id, _)| id == &side.provider_id)
+            .map(|(_, provider)| provider.clone())
+    };
+    Some([provider(&pair.sides[0])?, provider(&pair.sides[1])?])
+}
+
+/// Syncs every linked pair, the state is saved after each pair so a failure keeps the progress.
+///
+/// Pairs with a side whose provider is not in `providers` are skipped.
+pub async fn sync_all<P: APIProvider>(
+    mut providers: Vec<(ProviderId, P)>,
+    state_path: &Path,
+    reporter: &TransferReporter,
+) -> ProviderResult<SyncSummary> {
+    let mut state = SyncState::load(state_path);
+    let mut summary = SyncSummary::default();
+    reporter.update(TransferUpdateEventData::Searching);
+    let linked: HashSet<ProviderId> = state
+        .pairs
+        .iter()
+        .flat_map(|pair| pair.sides.iter().map(|side| side.provider_id.clone()))
+        .collect();
+    let playlists = list_playlists(&mut providers, &linked).await?;
+    let listed = |side: &SyncSide| -> &[RSyncPlaylistItem] {
+        playlists
+            .get(&side.provider_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    };
+    // pair index and side of the playlists changed by the sync
+    let mut changed: Vec<(usize, usize)> = Vec::new();
+    for index in 0..state.pairs.len() {
+        let pair = &mut state.pairs[index];
+        let Some(mut pair_providers) = pair_providers(&providers, pair) else {
+            summary.skipped += 1;
+            continue;
+        };
+        let pair_playlists = [listed(&pair.sides[0]), listed(&pair.sides[1])];
+        let changed_sides =
+            sync_pair(&mut pair_providers, pair, pair_playlists, &mut summary).await?;
+        changed.extend(
+            (0..2)
+                .filter(|side| changed_sides[*side])
+                .map(|side| (index, side)),
+        );
+        if let Err(err) = state.save(state_path) {
+            warn!("Failed to save sync state: {}", err);
+        }
+    }
+
+    // the changed playlists have new snapshots, their providers list the playlists once more
+    if !changed.is_empty() {
+        let providers_changed: HashSet<ProviderId> = changed
+            .iter()
+            .map(|(index, side)| state.pairs[*index].sides[*side].provider_id.clone())
+            .collect();
+        let playlists = list_playlists(&mut providers, &providers_changed).await?;
+        for (index, side) in changed {
+            let side = &mut state.pairs[index].sides[side];
+            let listed = playlists
+                .get(&side.provider_id)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            side.snapshot_id = playlist_snapshot(listed, side)?;
+        }
+        if let Err(err) = state.save(state_path) {
+            warn!("Failed to save sync state: {}", err);
+        }
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc;
+
+    use super::*;
+    use crate::{
+        providers::mock_provider::MockProvider,
+        types::music_types::RSyncPlaylistItemProviderDataSpotify,
+    };
+
+    const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/mock_library.json");
+
+    fn playlist_id(id: &str) -> PlaylistIdWrapper {
+        PlaylistIdWrapper::Id(id.into())
+    }
+
+    /// "Road trip" of one mock provider linked with "Evening" of another, nothing synced yet.
+    fn linked() -> ([MockProvider; 2], SyncPair) {
+        let providers = [0, 1].map(|_| MockProvider::from_fixture(FIXTURE).unwrap());
+        let pair = SyncPair::new(
+            SyncSide::new("left".into(), playlist_id("road-trip"), "Road trip".into()),
+            SyncSide::new("right".into(), playlist_id("evening"), "Evening".into()),
+        );
+        (providers, pair)
+    }
+
+    fn song_ids(provider: &MockProvider, id: &str) -> Vec<String> {
+        let playlist = provider
+            .snapshot()
+            .playlists
+            .into_iter()
+            .find(|playlist| playlist.id == id)
+            .expect("playlist exists");
+        playlist.songs.into_iter().map(|song| song.id).collect()
+    }
+
+    /// Playlists of the pair as Spotify lists them, `snapshot` and the side make the snapshot id.
+    fn listed(pair: &SyncPair, snapshot: &str) -> [Vec<RSyncPlaylistItem>; 2] {
+        [0, 1].map(|side| {
+            vec![RSyncPlaylistItem {
+                collaborative: false,
+                description: None,
+                url: String::new(),
+                id: pair.sides[side].playlist_id.clone(),
+                name: pair.sides[side].name.clone(),
+                owned: true,
+                public: false,
+                tracks: 0,
+                r#type: RSyncPlaylistItemProviderData::Spotify(
+                    RSyncPlaylistItemProviderDataSpotify {
+                        snapshot_id: format!("{}-{}", snapshot, side),
+                    },
+                ),
+            }]
+        })
+    }
+
+    async fn sync(providers: &mut [MockProvider; 2], pair: &mut SyncPair) -> SyncSummary {
+        let playlists = [
+            providers[0].get_playlists().await.unwrap(),
+            providers[1].get_playlists().await.unwrap(),
+        ];
+        let mut summary = SyncSummary::default();
+        sync_pair(
+            providers,
+            pair,
+            [&playlists[0], &playlists[1]],
+            &mut summary,
+        )
+        .await
+        .unwrap();
+        summary
+    }
+
+    #[tokio::test]
+    async fn songs_added_on_either_side_are_added_to_the_other() {
+        let (mut providers, mut pair) = linked();
+
+        let summary = sync(&mut providers, &mut pair).await;
+
+        assert_eq!(summary.added, 4);
+        assert_eq!(
+            song_ids(&providers[0], "road-trip"),
+            ["song-1", "song-2", "song-3", "song-4"]
+        );
+        assert_eq!(
+            song_ids(&providers[1], "evening"),
+            ["song-4", "song-1", "song-2", "song-3"]
+        );
+        assert_eq!(pair.links.len(), 4);
+
+        // a second run finds nothing new
+        let summary = sync(&mut providers, &mut pair).await;
+        assert_eq!((summary.added, summary.removed), (0, 0));
+    }
+
+    #[tokio::test]
+    async fn songs_removed_on_either_side_are_removed_from_the_other() {
+        let (mut providers, mut pair) = linked();
+        sync(&mut providers, &mut pair).await;
+
+        providers[0]
+            .rem_playlist_song(playlist_id("road-trip"), vec!["song-2".into()])
+            .await
+            .unwrap();
+        providers[1]
+            .rem_playlist_song(playlist_id("evening"), vec!["song-4".into()])
+            .await
+            .unwrap();
+        let summary = sync(&mut providers, &mut pair).await;
+
+        assert_eq!((summary.added, summary.removed), (0, 2));
+        assert_eq!(song_ids(&providers[0], "road-trip"), ["song-1", "song-3"]);
+        assert_eq!(song_ids(&providers[1], "evening"), ["song-1", "song-3"]);
+        let linked: Vec<&str> = pair.links.iter().map(|link| link.ids[0].as_str()).collect();
+        assert_eq!(linked, ["song-1", "song-3"]);
+    }
+
+    #[tokio::test]
+    async fn side_with_unchanged_snapshot_is_not_fetched() {
+        let (mut providers, mut pair) = linked();
+        sync(&mut providers, &mut pair).await;
+        providers[0]
+            .add_playlist_song(playlist_id("road-trip"), vec!["song-6".into()])
+            .await
+            .unwrap();
+
+        pair.sides[0].snapshot_id = Some("synced-0".into());
+        pair.sides[1].snapshot_id = Some("synced-1".into());
+
+        let unchanged = listed(&pair, "synced");
+        let mut summary = SyncSummary::default();
+        let changed = sync_pair(
+            &mut providers,
+            &mut pair,
+            [&unchanged[0], &unchanged[1]],
+            &mut summary,
+        )
+        .await
+        .unwrap();
+        assert_eq!(changed, [false, false]);
+        assert_eq!(summary.added, 0);
+        assert_eq!(pair.sides[0].snapshot_id.as_deref(), Some("synced-0"));
+
+        let edited = listed(&pair, "edited");
+        let changed = sync_pair(
+            &mut providers,
+            &mut pair,
+            [&edited[0], &edited[1]],
+            &mut summary,
+        )
+        .await
+        .unwrap();
+        assert_eq!(changed, [false, true]);
+        assert_eq!(summary.added, 1);
+        assert!(song_ids(&providers[1], "evening").contains(&"song-6".to_string()));
+        // the changed side gets its new snapshot when the playlists are listed again
+        assert_eq!(pair.sides[0].snapshot_id.as_deref(), Some("edited-0"));
+        assert_eq!(pair.sides[1].snapshot_id, None);
+    }
+
+    #[tokio::test]
+    async fn synced_links_are_saved_and_loaded() {
+        let path = env::temp_dir().join(format!("sync-state-{}.json", std::process::id()));
+        let (providers, pair) = linked();
+        let mut state = SyncState::default();
+        state.link(pair);
+        state.save(&path).unwrap();
+        let (sender, _receiver) = mpsc::unbounded_channel();
+        let reporter = TransferReporter::new(sender, 0, "left".into());
+
+        let [left, right] = providers;
+        let providers = vec![("left".into(), left), ("right".into(), right)];
+        let summary = sync_all(providers, &path, &reporter).await.unwrap();
+        let loaded = SyncState::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!((summary.pairs, summary.added), (1, 4));
+        let [pair] = loaded.pairs.as_slice() else {
+            panic!("one linked pair expected");
+        };
+        assert_eq!(pair.sides[0].playlist_id, playlist_id("road-trip"));
+        assert_eq!(pair.sides[1].playlist_id, playlist_id("evening"));
+        let links: Vec<[&str; 2]> = pair
+            .links
+            .iter()
+            .map(|link| [link.ids[0].as_str(), link.ids[1].as_str()])
+            .collect();
+        assert_eq!(
+            links,
+            [
+                ["song-1", "song-1"],
+                ["song-2", "song-2"],
+                ["song-3", "song-3"],
+                ["song-4", "song-4"]
+            ]
+        );
+    }
+}
@@ -0,0 +1,386 @@
+use std::collections::HashSet;
+
+use tokio::sync::mpsc;
+
+use crate::{
+    event::{Event, GlobalEvent, GlobalGenericEventData, TransferUpdateEventData},
+    matching::{self, SongMatch},
+    providers::{
+        provider_error::ProviderResult,
+        provider_traits::{APIProvider, AddOutcome},
+        registry::ProviderId,
+    },
+    report::{SongStatus, TransferReport},
+    types::music_types::{PlaylistIdWrapper, RSyncPlaylistItem, RSyncSong},
+};
+
+/// Reports the progress of one transfer back to the app.
+#[derive(Clone, Debug)]
+pub struct TransferReporter {
+    event_sender: mpsc::UnboundedSender<Event>,
+    request_id: u128,
+    /// Provider the songs are transferred into.
+    target: ProviderId,
+}
+
+impl TransferReporter {
+    pub fn new(
+        event_sender: mpsc::UnboundedSender<Event>,
+        request_id: u128,
+        target: ProviderId,
+    ) -> Self {
+        Self {
+            event_sender,
+            request_id,
+            target,
+        }
+    }
+
+    pub fn send(&self, data: GlobalGenericEventData) {
+        let _ = self.event_sender.send(Event::DataReceived(
+            self.request_id,
+            GlobalEvent::Generic(data),
+        ));
+    }
+
+    pub fn update(&self, update: TransferUpdateEventData) {
+        self.send(GlobalGenericEventData::TransferUpdate(
+            self.target.clone(),
+            update,
+        ));
+    }
+
+    /// Sends the final state of the transfer, either finished or the error that stopped it.
+    ///
+    /// `None` means the transfer waits for the user to review the matches, nothing is sent.
+    pub fn finish(&self, result: ProviderResult<Option<TransferReport>>) {
+        match result {
+            Ok(Some(report)) => self.update(TransferUpdateEventData::Finished(report)),
+            Ok(None) => (),
+            Err(err) => self.send(GlobalGenericEventData::Error(err)),
+        }
+    }
+}
+
+fn song_key(song: &RSyncSong) -> (String, String) {
+    (
+        matching::normalise_title(&song.name),
+        matching::normalise(&song.artists),
+    )
+}
+
+/// One searched song and the candidate chosen for it, `None` skips the song.
+#[derive(Clone, Debug)]
+pub struct PlannedSong {
+    pub song_match: SongMatch,
+    pub choice: Option<RSyncSong>,
+}
+
+/// Searched songs of a transfer that were not added yet.
+#[derive(Clone, Debug)]
+pub struct TransferPlan {
+    pub playlist_id: PlaylistIdWrapper,
+    pub songs: Vec<PlannedSong>,
+    /// Ids already in the target playlist.
+    existing_ids: HashSet<String>,
+    report: TransferReport,
+}
+
+impl TransferPlan {
+    /// Low confidence matches are not chosen by default, the user has to look at them.
+    pub fn needs_review(&self) -> bool {
+        self.songs
+            .iter()
+            .any(|song| song.song_match.found.is_some() && !song.song_match.is_confident())
+    }
+
+    /// Songs settled before searching, e.g. duplicates of songs in the target playlist.
+    pub fn report(&self) -> &TransferReport {
+        &self.report
+    }
+}
+
+/// Searches the songs in the target provider, nothing is added yet.
+///
+/// Songs already in the target playlist are skipped by their title and artists before searching.
+pub async fn plan_songs<T: APIProvider>(
+    target: &mut T,
+    playlist_id: PlaylistIdWrapper,
+    songs: Vec<RSyncSong>,
+    reporter: &TransferReporter,
+) -> ProviderResult<TransferPlan> {
+    reporter.update(TransferUpdateEventData::Searching);
+    let mut report = TransferReport::default();
+    let existing = target.get_playlist_songs(playlist_id.clone()).await?;
+    let existing_keys: HashSet<(String, String)> = existing.iter().map(song_key).collect();
+    let existing_ids: HashSet<String> = existing.into_iter().map(|song| song.id).collect();
+
+    let (duplicates, songs): (Vec<RSyncSong>, Vec<RSyncSong>) = songs
+        .into_iter()
+        .partition(|song| existing_keys.contains(&song_key(song)));
+    for song in duplicates {
+        report.push(
+            SongStatus::Skipped,
+            song,
+            None,
+            None,
+            "same title and artists already in the playlist",
+        );
+    }
+    let total = songs.len();
+    let mut matches = Vec::with_capacity(total);
+    for song in songs {
+        matches.append(&mut target.search_list(vec![song]).await?);
+        reporter.update(TransferUpdateEventData::Progress {
+            done: matches.len(),
+            total,
+        });
+    }
+    let songs = matches
+        .into_iter()
+        .map(|song_match| PlannedSong {
+            choice: song_match.confident_song().cloned(),
+            song_match,
+        })
+        .collect();
+
+    Ok(TransferPlan {
+        playlist_id,
+        songs,
+        existing_ids,
+        report,
+    })
+}
+
+/// Inserts the chosen songs of the plan into the playlist.
+///
+/// Songs whose chosen id is already in the playlist are skipped as well. A failed insert
+/// is recorded in the report instead of failing the whole transfer.
+pub async fn commit_plan<T: APIProvider>(
+    target: &mut T,
+    plan: TransferPlan,
+    reporter: &TransferReporter,
+) -> ProviderResult<TransferReport> {
+    reporter.update(TransferUpdateEventData::Updating);
+    let TransferPlan {
+        playlist_id,
+        songs,
+        mut existing_ids,
+        mut report,
+    } = plan;
+
+    let mut chosen = Vec::new();
+    for planned in songs {
+        let SongMatch {
+            source,
+            found,
+            confidence,
+            ..
+        } = planned.song_match;
+        let confident = found.is_some() && confidence >= matching::MIN_CONFIDENCE;
+        let song = match (planned.choice, found) {
+            (Some(song), _) => song,
+            (None, None) => {
+                report.push(
+                    SongStatus::NotFound,
+                    source,
+                    None,
+                    None,
+                    "no search results",
+                );
+                continue;
+            }
+            (None, Some(found)) => {
+                let reason = match confident {
+                    true => "skipped during review",
+                    false => "low confidence match, not accepted",
+                };
+                report.push(
+                    SongStatus::Skipped,
+                    source,
+                    Some(found),
+                    Some(confidence),
+                    reason,
+                );
+                continue;
+            }
+        };
+        let confidence = matching::score(&source, &song);
+        // also catches the same song selected twice
+        match existing_ids.insert(song.id.clone()) {
+            true => chosen.push((source, song, confidence)),
+            false => report.push(
+                SongStatus::Skipped,
+                source,
+                Some(song),
+                Some(confidence),
+                "already in the playlist",
+            ),
+        }
+    }
+
+    if !chosen.is_empty() {
+        let total = chosen.len();
+        reporter.update(TransferUpdateEventData::Progress { done: 0, total });
+        let progress = |done| reporter.update(TransferUpdateEventData::Progress { done, total });
+        let song_ids = chosen.iter().map(|(_, song, _)| song.id.clone()).collect();
+        let outcome: AddOutcome = target
+            .add_playlist_song_with_progress(playlist_id, song_ids, &progress)
+            .await
+            .into();
+        let added: HashSet<String> = outcome.added.into_iter().collect();
+        let reason = match outcome.error {
+            Some(err) => err.to_string(),
+            None => "not inserted by the service".into(),
+        };
+        for (source, song, confidence) in chosen {
+            match added.contains(&song.id) {
+                true => report.push(SongStatus::Added, source, Some(song), Some(confidence), ""),
+                false => report.push(
+                    SongStatus::Failed,
+                    source,
+                    Some(song),
+                    Some(confidence),
+                    reason.clone(),
+                ),
+            }
+        }
+        reporter.update(TransferUpdateEventData::Progress {
+            done: added.len(),
+            total,
+        });
+    }
+    Ok(report)
+}
+
+/// Searches the songs in the target provider and inserts the found ones into the playlist.
+///
+/// When some matches are uncertain the plan is sent to the app for a review and `None`
+/// is returned, the app commits it with [`commit_plan`] once the user is done.
+pub async fn add_songs<T: APIProvider>(
+    target: &mut T,
+    playlist_id: PlaylistIdWrapper,
+    songs: Vec<RSyncSong>,
+    reporter: &TransferReporter,
+) -> ProviderResult<Option<TransferReport>> {
+    let plan = plan_songs(target, playlist_id, songs, reporter).await?;
+    if plan.needs_review() {
+        reporter.update(TransferUpdateEventData::Review(plan));
+        return Ok(None);
+    }
+    commit_plan(target, plan, reporter).await.map(Some)
+}
+
+/// Copies a whole playlist into the target provider.
+///
+/// An owned playlist with the same name is reused, otherwise a new one is created.
+pub async fn mirror_playlist<S: APIProvider, T: APIProvider>(
+    source: &mut S,
+    target: &mut T,
+    playlist: RSyncPlaylistItem,
+    reporter: &TransferReporter,
+) -> ProviderResult<Option<TransferReport>> {
+    reporter.update(TransferUpdateEventData::Searching);
+    let songs = source.get_playlist_songs(playlist.id.clone()).await?;
+
+    let existing = target
+        .get_playlists()
+        .await?
+        .into_iter()
+        .find(|item| item.owned && item.name == playlist.name);
+    let target_playlist = match existing {
+        Some(target_playlist) => target_playlist,
+        None => target.create_playlist(playlist.name.clone()).await?,
+    };
+
+    add_songs(target, target_playlist.id, songs, reporter).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::mock_provider::{MockLibrary, MockProvider};
+
+    const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/mock_library.json");
+
+    fn reporter() -> (TransferReporter, mpsc::UnboundedReceiver<Event>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        (TransferReporter::new(sender, 0, "target".into()), receiver)
+    }
+
+    fn playlist_songs(provider: &MockProvider, name: &str) -> Vec<String> {
+        let playlist = provider
+            .snapshot()
+            .playlists
+            .into_iter()
+            .find(|playlist| playlist.name == name)
+            .expect("playlist exists");
+        playlist.songs.into_iter().map(|song| song.id).collect()
+    }
+
+    #[tokio::test]
+    async fn mirror_creates_playlist_with_all_songs() {
+        let mut source = MockProvider::from_fixture(FIXTURE).unwrap();
+        let mut target = MockProvider::from_library(MockLibrary {
+            catalog: source.snapshot().playlists[0].songs.clone(),
+            ..MockLibrary::default()
+        });
+        let playlist = source
+            .get_playlists()
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|playlist| playlist.name == "Road trip")
+            .unwrap();
+        let (reporter, _receiver) = reporter();
+
+        let report = mirror_playlist(&mut source, &mut target, playlist, &reporter)
+            .await
+            .unwrap()
+            .expect("confident matches need no review");
+
+        assert_eq!(report.count(SongStatus::Added), 3);
+        assert_eq!(
+            playlist_songs(&target, "Road trip"),
+            ["song-1", "song-2", "song-3"]
+        );
+    }
+
+    #[tokio::test]
+    async fn add_skips_duplicates_and_reports_missing_songs() {
+        let mut source = MockProvider::from_fixture(FIXTURE).unwrap();
+        let mut target = MockProvider::from_fixture(FIXTURE).unwrap();
+        let africa = source
+            .get_playlist_songs(PlaylistIdWrapper::Liked)
+            .await
+            .unwrap();
+        let evening = source
+            .get_playlist_songs(PlaylistIdWrapper::Id("evening".into()))
+            .await
+            .unwrap();
+        let missing = RSyncSong {
+            name: "Unknown Song".into(),
+            artists: "Nobody".into(),
+            ..evening[0].clone()
+        };
+        let (reporter, _receiver) = reporter();
+
+        let report = add_songs(
+            &mut target,
+            PlaylistIdWrapper::Id("road-trip".into()),
+            vec![africa[0].clone(), evening[0].clone(), missing],
+            &reporter,
+        )
+        .await
+        .unwrap()
+        .expect("confident matches need no review");
+
+        assert_eq!(report.count(SongStatus::Skipped), 1);
+        assert_eq!(report.count(SongStatus::Added), 1);
+        assert_eq!(report.count(SongStatus::NotFound), 1);
+        assert_eq!(
+            playlist_songs(&target, "Road trip"),
+            ["song-1", "song-2", "song-3", "song-4"]
+        );
+    }
+}
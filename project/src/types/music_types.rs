@@ -1,35 +1,38 @@
 use ratatui::text::Text;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 
 pub struct RSyncSongProviderDataYoutube {
     pub playlist_id: Option<String>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum RSyncSongProviderData {
     Youtube(RSyncSongProviderDataYoutube),
     Spotify,
+    Local,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct RSyncPlaylistItemProviderDataSpotify {
     pub snapshot_id: String,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum RSyncPlaylistItemProviderData {
     Youtube,
     Spotify(RSyncPlaylistItemProviderDataSpotify),
+    Local,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum PlaylistIdWrapper {
     Id(String),
     Liked,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct RSyncPlaylistItem {
     pub collaborative: bool,
     pub description: Option<String>,
@@ -53,17 +56,95 @@ impl<'a> Into<Text<'a>> for RSyncPlaylistItem {
     }
 }
 
-#[derive(Clone, Debug)]
+/// Editable details of a playlist.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PlaylistDetails {
+    pub name: String,
+    pub description: Option<String>,
+    pub public: bool,
+}
+
+impl RSyncPlaylistItem {
+    pub fn details(&self) -> PlaylistDetails {
+        PlaylistDetails {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            public: self.public,
+        }
+    }
+
+    /// The playlist with `details` applied, used when a service does not return the result.
+    pub fn with_details(self, details: PlaylistDetails) -> Self {
+        Self {
+            name: details.name,
+            description: details.description,
+            public: details.public,
+            ..self
+        }
+    }
+}
+
+/// Details used for matching, providers fill in what they know.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RSyncSongMetadata {
+    pub isrc: Option<String>,
+    pub duration_ms: Option<u32>,
+    pub album: Option<String>,
+    /// Individual artists, `RSyncSong::artists` holds them joined for display.
+    pub artists: Vec<String>,
+    /// When the song was added to the playlist, as an RFC 3339 timestamp.
+    #[serde(default)]
+    pub added_at: Option<String>,
+}
+
+impl RSyncSongMetadata {
+    /// Duration formatted as `m:ss`.
+    pub fn duration_label(&self) -> Option<String> {
+        let seconds = self.duration_ms? / 1000;
+        Some(format!("{}:{:02}", seconds / 60, seconds % 60))
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct RSyncSong {
     pub artists: String,
     pub url: String,
     pub id: String,
     pub name: String,
+    #[serde(default)]
+    pub metadata: RSyncSongMetadata,
     pub r#type: RSyncSongProviderData,
 }
 
+impl RSyncSong {
+    /// First credited artist, search queries with every featured artist tend to miss.
+    pub fn primary_artist(&self) -> &str {
+        match self.metadata.artists.first() {
+            Some(artist) => artist,
+            None => self.artists.split(',').next().unwrap_or_default().trim(),
+        }
+    }
+
+    /// Id used to remove the song from its playlist, YouTube removes playlist items, not videos.
+    pub fn removal_id(&self) -> String {
+        match &self.r#type {
+            RSyncSongProviderData::Youtube(RSyncSongProviderDataYoutube {
+                playlist_id: Some(playlist_id),
+            }) => playlist_id.clone(),
+            _ => self.id.clone(),
+        }
+    }
+}
+
 impl<'a> Into<Text<'a>> for RSyncSong {
     fn into(self) -> Text<'a> {
-        format!("{} ({})", self.name, self.artists).into()
+        let mut label = format!("{} ({})", self.name, self.artists);
+        if let Some(album) = &self.metadata.album {
+            label.push_str(&format!(" · {}", album));
+        }
+        if let Some(duration) = self.metadata.duration_label() {
+            label.push_str(&format!(" · {}", duration));
+        }
+        label.into()
     }
 }
@@ -4,21 +4,49 @@ use ratatui::{
     Frame,
 };
 
-use crate::app::App;
 pub use crate::widgets;
+use crate::{app::App, providers::provider_traits::APIProvider};
 
 /// Renders the user interface widgets.
-pub fn render(app: &mut App, frame: &mut Frame) {
+pub fn render<P: APIProvider>(app: &mut App<P>, frame: &mut Frame) {
     let l = Layout::vertical([Constraint::Percentage(100), Constraint::Length(1)]);
     let [main_area, help_area] = l.areas(frame.area());
+    let main_area = match app.jobs_panel.visible {
+        true => {
+            let [main_area, jobs_area] =
+                Layout::horizontal([Constraint::Fill(1), Constraint::Length(40)]).areas(main_area);
+            app.jobs_panel.render(frame, jobs_area, &app.jobs);
+            main_area
+        }
+        false => main_area,
+    };
 
-    let columns = Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]);
-    let [spotify_column, youtube_column] = columns.areas(main_area);
-
-    app.spotify_column.render(frame, spotify_column);
-    app.youtube_column.render(frame, youtube_column);
+    let column_count = app.columns.len() as u32;
+    let columns = Layout::horizontal(
+        app.columns
+            .iter()
+            .map(|_| Constraint::Ratio(1, column_count)),
+    )
+    .split(main_area);
+    for (column, area) in app.columns.iter_mut().zip(columns.iter()) {
+        column.render(frame, *area);
+    }
 
-    let help_message = Paragraph::new("Use ↓↑ to move, [enter] to select, ←→ to transfer, [a] to add, [r] to refresh, [del] to delete, [o] to open in browser.").centered();
+    let help_text = "Use ↓↑ to move, [enter] to select, ←→ to transfer, [a] to add, [r] to refresh, [del] to delete, [/] to filter, [n/N] next/previous match, [o] to open in browser, [shift+↓↑] to move songs, [S] to sort, [l] to link playlists, [y] to sync linked, [m] to edit playlist, [e] to export, [i] to import, [d] to toggle dry run, [u] to undo, [j] for jobs.";
+    let mut status = Vec::new();
+    if app.dry_run.is_enabled() {
+        status.push("DRY RUN".to_string());
+    }
+    if let Some(summary) = app.jobs.summary() {
+        status.push(format!("JOBS {}", summary));
+    }
+    for column in app.columns.iter() {
+        if let Some(quota) = column.provider.quota() {
+            status.push(format!("QUOTA {} {}", column.name, quota.summary()));
+        }
+    }
+    status.push(help_text.to_string());
+    let help_message = Paragraph::new(status.join(" | ")).centered();
     frame.render_widget(help_message, help_area);
 
     if let Some(ref mut popup) = app.popup {
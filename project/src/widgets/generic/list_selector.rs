@@ -32,20 +32,32 @@ where
     allow_multiple: bool,
     labels: ListSelectorLabels,
     loading: bool,
+    /// Query narrowing the shown items, started with [/].
+    filter: Option<String>,
+    /// Keys are typed into the filter until [enter] or [esc].
+    typing_filter: bool,
+    /// Indices of the shown items, the cursor is a position in here while `selected` holds
+    /// indices of `items`.
+    visible: Vec<usize>,
 }
 impl<T> ListSelector<T>
 where
     T: Into<Text<'static>> + Clone + std::fmt::Debug,
 {
     pub fn new(items: Option<Vec<T>>, labels: ListSelectorLabels, allow_multiple: bool) -> Self {
-        Self {
+        let mut selector = Self {
             items,
             state: ListState::default(),
             selected: Vec::new(),
             allow_multiple,
             labels,
             loading: false,
-        }
+            filter: None,
+            typing_filter: false,
+            visible: Vec::new(),
+        };
+        selector.visible = selector.filtered_indices();
+        selector
     }
 
     pub fn get_selected_items(&mut self) -> Vec<&T> {
@@ -62,32 +74,133 @@ where
     }
 
     pub fn get_cursor_item(&mut self) -> Option<&T> {
-        match self.state.selected() {
-            Some(i) => match &self.items {
-                Some(data) => Some(&data[i]),
-                None => None,
-            },
-            None => None,
+        let index = self.get_cursor_index()?;
+        self.items.as_ref()?.get(index)
+    }
+
+    pub fn get_items(&self) -> Option<&[T]> {
+        self.items.as_deref()
+    }
+
+    /// Index of the item under the cursor, the filter does not change it.
+    pub fn get_cursor_index(&self) -> Option<usize> {
+        self.state
+            .selected()
+            .and_then(|position| self.visible.get(position).copied())
+    }
+
+    pub fn is_typing_filter(&self) -> bool {
+        self.typing_filter
+    }
+
+    fn filtered_indices(&self) -> Vec<usize> {
+        match (&self.items, &self.filter) {
+            (Some(items), Some(filter)) => items
+                .iter()
+                .enumerate()
+                .filter(|(_, item)| fuzzy_matches(&item_text(*item), filter))
+                .map(|(i, _)| i)
+                .collect(),
+            (Some(items), None) => (0..items.len()).collect(),
+            (None, _) => Vec::new(),
         }
     }
 
+    /// Puts the cursor on the item at `index`, or on the first shown item if it is hidden.
+    fn select_item(&mut self, index: Option<usize>) {
+        let position = index.and_then(|index| self.visible.iter().position(|i| *i == index));
+        self.state
+            .select(position.or((!self.visible.is_empty()).then_some(0)));
+    }
+
+    /// Updates the shown items after the items or the filter changed.
+    fn refresh_visible(&mut self) {
+        let cursor = self.get_cursor_index();
+        self.visible = self.filtered_indices();
+        if cursor.is_some() || self.filter.is_some() {
+            self.select_item(cursor);
+        }
+    }
+
+    fn clear_filter(&mut self) {
+        self.filter = None;
+        self.typing_filter = false;
+        self.refresh_visible();
+    }
+
     pub fn clear_selected(&mut self) {
         self.selected.clear();
         self.state.select(None);
     }
 
+    /// Moves the selected items, or the item under the cursor, one place up or down.
+    ///
+    /// Items at the edge and the ones right behind them stay in place. Returns the new
+    /// order as the previous positions of the items, `None` if nothing moved.
+    pub fn move_selected(&mut self, up: bool) -> Option<Vec<usize>> {
+        let cursor = self.get_cursor_index();
+        let items = self.items.as_mut()?;
+        let len = items.len();
+        let mut moving = vec![false; len];
+        match self.selected.is_empty() {
+            true => moving[cursor?] = true,
+            false => {
+                for i in self.selected.iter() {
+                    moving[*i] = true;
+                }
+            }
+        }
+
+        let mut order: Vec<usize> = (0..len).collect();
+        let positions: Vec<usize> = match up {
+            true => (1..len).collect(),
+            false => (1..len).rev().map(|i| i - 1).collect(),
+        };
+        for position in positions {
+            let other = match up {
+                true => position - 1,
+                false => position + 1,
+            };
+            if moving[position] && !moving[other] {
+                order.swap(position, other);
+                moving.swap(position, other);
+            }
+        }
+        if order.iter().enumerate().all(|(i, previous)| i == *previous) {
+            return None;
+        }
+
+        let mut new_positions = vec![0; len];
+        for (position, previous) in order.iter().enumerate() {
+            new_positions[*previous] = position;
+        }
+        *items = order
+            .iter()
+            .map(|previous| items[*previous].clone())
+            .collect();
+        for i in self.selected.iter_mut() {
+            *i = new_positions[*i];
+        }
+        self.visible = self.filtered_indices();
+        if let Some(cursor) = cursor {
+            self.select_item(Some(new_positions[cursor]));
+        }
+        Some(order)
+    }
+
     pub fn append_items(&mut self, items: Vec<T>) {
         self.loading = false;
-        if self.items.is_none() {
-            self.items = Some(items);
-            return;
+        match self.items.as_mut() {
+            Some(current) => current.append(&mut items.clone()),
+            None => self.items = Some(items),
         }
-        self.items.as_mut().unwrap().append(&mut items.clone());
+        self.refresh_visible();
     }
 
     pub fn set_items(&mut self, items: Option<Vec<T>>) {
         self.loading = false;
         self.items = items;
+        self.refresh_visible();
     }
 
     pub fn set_loading(&mut self) {
@@ -101,8 +214,22 @@ where
             Style::new().fg(Color::DarkGray)
         };
 
+        let title = match &self.filter {
+            Some(filter) => format!(
+                "{} /{}{} [{}/{}]",
+                self.labels.title,
+                filter,
+                match self.typing_filter {
+                    true => "_",
+                    false => "",
+                },
+                self.visible.len(),
+                self.items.as_ref().map(Vec::len).unwrap_or_default()
+            ),
+            None => self.labels.title.clone(),
+        };
         let block = Block::bordered()
-            .title(self.labels.title.as_str())
+            .title(title)
             .title_alignment(Alignment::Center)
             .border_type(BorderType::Rounded)
             .border_style(border_style);
@@ -124,9 +251,10 @@ where
 
         match self.items.as_ref() {
             Some(playlist_data) => {
-                let items = playlist_data
+                let items = self
+                    .visible
                     .iter()
-                    .enumerate()
+                    .map(|i| (*i, &playlist_data[*i]))
                     .map(|(i, playlist)| {
                         if self.selected.contains(&i) {
                             ListItem::from(playlist.clone())
@@ -164,7 +292,61 @@ where
     }
 
     pub fn handle_key_events(&mut self, key_event: KeyEvent) -> ListSelectorKeyResponse {
+        if self.typing_filter {
+            let filter = self.filter.get_or_insert_with(String::new);
+            match key_event.code {
+                KeyCode::Char(c) if !key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                    filter.push(c);
+                    self.refresh_visible();
+                    return ListSelectorKeyResponse::None;
+                }
+                KeyCode::Backspace => {
+                    filter.pop();
+                    self.refresh_visible();
+                    return ListSelectorKeyResponse::None;
+                }
+                KeyCode::Enter => {
+                    self.typing_filter = false;
+                    if filter.is_empty() {
+                        self.clear_filter();
+                    }
+                    return ListSelectorKeyResponse::None;
+                }
+                KeyCode::Esc => {
+                    self.clear_filter();
+                    return ListSelectorKeyResponse::None;
+                }
+                _ => (),
+            }
+        }
+
         match key_event.code {
+            KeyCode::Char('/') => {
+                self.typing_filter = true;
+                self.filter.get_or_insert_with(String::new);
+                ListSelectorKeyResponse::None
+            }
+            KeyCode::Esc if self.filter.is_some() => {
+                self.clear_filter();
+                ListSelectorKeyResponse::None
+            }
+            // jumps between the matches of the filter, wrapping around at the ends
+            KeyCode::Char('n') | KeyCode::Char('N') if self.filter.is_some() => {
+                let len = self.visible.len();
+                if len > 0 {
+                    let next = match (self.state.selected(), key_event.code) {
+                        (None, _) => 0,
+                        (Some(cursor), KeyCode::Char('n')) => (cursor + 1) % len,
+                        (Some(cursor), _) => (cursor.min(len - 1) + len - 1) % len,
+                    };
+                    self.state.select(Some(next));
+                }
+                ListSelectorKeyResponse::CursorMoved
+            }
+            // moving items is up to the owner of the list
+            KeyCode::Up | KeyCode::Down if key_event.modifiers.contains(KeyModifiers::SHIFT) => {
+                ListSelectorKeyResponse::Pass
+            }
             KeyCode::Up | KeyCode::Char('w') => {
                 self.state.select_previous();
                 ListSelectorKeyResponse::CursorMoved
@@ -175,14 +357,14 @@ where
             }
             KeyCode::Char('a') => {
                 if self.allow_multiple && key_event.modifiers == KeyModifiers::CONTROL {
-                    self.selected = (0..self.items.as_ref().unwrap().len()).collect();
+                    self.selected = self.visible.clone();
                     ListSelectorKeyResponse::Selected
                 } else {
                     ListSelectorKeyResponse::Pass
                 }
             }
             KeyCode::Enter => {
-                if let Some(val) = self.state.selected() {
+                if let Some(val) = self.get_cursor_index() {
                     match self.allow_multiple {
                         true => {
                             let pos = self.selected.iter().position(|v| *v == val);
@@ -209,3 +391,28 @@ where
         }
     }
 }
+
+/// Text of an item as it is rendered.
+fn item_text<T: Into<Text<'static>> + Clone>(item: &T) -> String {
+    let text: Text = item.clone().into();
+    text.lines
+        .iter()
+        .map(|line| {
+            line.spans
+                .iter()
+                .map(|span| span.content.as_ref())
+                .collect::<String>()
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Case-insensitive fuzzy match, the characters of `query` appear in `text` in the same order.
+fn fuzzy_matches(text: &str, query: &str) -> bool {
+    let mut text = text.chars().flat_map(char::to_lowercase);
+    query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .all(|c| text.any(|t| t == c))
+}
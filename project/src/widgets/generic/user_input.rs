@@ -18,6 +18,7 @@ pub struct UserInput {
     character_index: usize,
     input: String,
     require_enter: bool,
+    title: String,
 }
 impl UserInput {
     pub fn new(require_enter: bool) -> Self {
@@ -25,9 +26,26 @@ impl UserInput {
             character_index: 0,
             input: String::new(),
             require_enter,
+            title: "Input song same".into(),
         }
     }
 
+    pub fn with_title(mut self, title: &str) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// Prefills the input, the cursor is placed at its end.
+    pub fn with_value(mut self, value: &str) -> Self {
+        self.input = value.into();
+        self.character_index = self.input.chars().count();
+        self
+    }
+
+    pub fn value(&self) -> &str {
+        &self.input
+    }
+
     fn move_cursor_left(&mut self) {
         let cursor_moved_left = self.character_index.saturating_sub(1);
         self.character_index = self.clamp_cursor(cursor_moved_left);
@@ -96,7 +114,7 @@ impl UserInput {
     pub fn render(&mut self, frame: &mut Frame, area: Rect) {
         let input = Paragraph::new(self.input.as_str())
             .style(Style::default().fg(Color::Yellow))
-            .block(Block::bordered().title("Input song same")); // Block::bordered().title(self.input.clone());
+            .block(Block::bordered().title(self.title.as_str())); // Block::bordered().title(self.input.clone());
         frame.render_widget(input, area);
         frame.set_cursor_position(Position::new(
             // Draw the cursor at the current position in the input field.
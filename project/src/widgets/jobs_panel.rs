@@ -0,0 +1,90 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    text::Text,
+    widgets::Paragraph,
+    Frame,
+};
+
+use crate::jobs::{JobId, JobManager};
+
+use super::generic::list_selector::{ListSelector, ListSelectorKeyResponse, ListSelectorLabels};
+
+/// Job as it is listed in the panel.
+#[derive(Clone, Debug)]
+pub struct JobRow {
+    pub id: JobId,
+    text: String,
+}
+
+impl From<JobRow> for Text<'static> {
+    fn from(row: JobRow) -> Self {
+        row.text.into()
+    }
+}
+
+/// Side panel with the transfer jobs, shown next to the columns so browsing goes on.
+#[derive(Debug)]
+pub struct JobsPanel {
+    pub visible: bool,
+    /// Keys go to the panel instead of the active column.
+    pub active: bool,
+    selector: ListSelector<JobRow>,
+}
+impl Default for JobsPanel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JobsPanel {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            active: false,
+            selector: ListSelector::new(
+                None,
+                ListSelectorLabels {
+                    empty: "No transfers yet".into(),
+                    title: "Jobs".into(),
+                },
+                false,
+            ),
+        }
+    }
+
+    pub fn cursor_job(&mut self) -> Option<JobId> {
+        self.selector.get_cursor_item().map(|row| row.id)
+    }
+
+    /// Lists the current state of `jobs`, the panel never keeps its own copy.
+    pub fn render(&mut self, frame: &mut Frame, area: Rect, jobs: &JobManager) {
+        let rows: Vec<JobRow> = jobs
+            .jobs()
+            .iter()
+            .map(|job| JobRow {
+                id: job.id,
+                text: format!("{}\n  {}", job.title, job.status()),
+            })
+            .collect();
+        self.selector.set_items((!rows.is_empty()).then_some(rows));
+
+        let [list_area, help_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(2)]).areas(area);
+        self.selector.render(frame, list_area, self.active);
+        let help_message =
+            Paragraph::new("[enter] open, [c] cancel, [r] retry\n[x] remove, [j] hide").centered();
+        frame.render_widget(help_message, help_area);
+    }
+
+    /// Handles a key of the list, returns `false` if the key is left to the app.
+    pub fn handle_key_events(&mut self, key_event: KeyEvent) -> bool {
+        if key_event.code == KeyCode::Enter && !self.selector.is_typing_filter() {
+            return false;
+        }
+        !matches!(
+            self.selector.handle_key_events(key_event),
+            ListSelectorKeyResponse::Pass
+        )
+    }
+}
@@ -1,8 +1,8 @@
+pub mod jobs_panel;
 pub mod playlist_selector;
 pub mod song_selector;
 
-pub mod spotify_column;
-pub mod youtube_column;
+pub mod provider_column;
 
 pub mod generic;
 pub mod popups;
@@ -0,0 +1,96 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Flex, Layout, Rect},
+    widgets::{Block, Clear, Paragraph},
+    Frame,
+};
+
+use crate::{
+    providers::registry::ProviderId,
+    types::music_types::{PlaylistIdWrapper, RSyncPlaylistItem, RSyncSong},
+};
+
+use super::popup::PopupEvent;
+
+/// Number of affected items listed by name, the rest is only counted.
+const PREVIEW_ITEMS: usize = 5;
+
+/// Action waiting for the confirmation, it runs on the column of the popup.
+#[derive(Debug)]
+pub enum ConfirmAction {
+    RemoveSongs {
+        playlist_id: PlaylistIdWrapper,
+        song_ids: Vec<String>,
+    },
+    AddSongs {
+        playlist_id: PlaylistIdWrapper,
+        songs: Vec<RSyncSong>,
+    },
+    MirrorPlaylist {
+        source: ProviderId,
+        playlist: RSyncPlaylistItem,
+    },
+    DeletePlaylist(RSyncPlaylistItem),
+}
+
+/// Asks before running a destructive or large action, [y] confirms and [n] cancels.
+#[derive(Debug)]
+pub struct ConfirmPopup {
+    title: String,
+    lines: Vec<String>,
+    pub action: ConfirmAction,
+}
+impl ConfirmPopup {
+    pub fn new(title: &str, message: String, action: ConfirmAction) -> Self {
+        Self {
+            title: title.to_string(),
+            lines: vec![message],
+            action,
+        }
+    }
+
+    /// Lists the first affected items under the message.
+    pub fn with_items(mut self, items: &[String]) -> Self {
+        for item in items.iter().take(PREVIEW_ITEMS) {
+            self.lines.push(format!("  {}", item));
+        }
+        if items.len() > PREVIEW_ITEMS {
+            self.lines
+                .push(format!("  ... and {} more", items.len() - PREVIEW_ITEMS));
+        }
+        self
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        /// helper function to create a centered rect using up certain percentage of the available rect `r`
+        fn popup_area(area: Rect, percent_x: u16, size_y: u16) -> Rect {
+            let vertical = Layout::vertical([Constraint::Length(size_y)]).flex(Flex::Center);
+            let horizontal =
+                Layout::horizontal([Constraint::Percentage(percent_x)]).flex(Flex::Center);
+            let [area] = vertical.areas(area);
+            let [area] = horizontal.areas(area);
+            area
+        }
+
+        let area = popup_area(area, 60, self.lines.len() as u16 + 3);
+        let [message_area, help_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(area);
+        frame.render_widget(Clear, area); //this clears out the background
+
+        let message = Paragraph::new(self.lines.join("\n"))
+            .block(Block::bordered().title(self.title.as_str()));
+        frame.render_widget(message, message_area);
+        frame.render_widget(
+            Paragraph::new("[y] to confirm, [n] to cancel").centered(),
+            help_area,
+        );
+    }
+
+    pub fn handle_key_events(&mut self, key_event: KeyEvent) -> PopupEvent {
+        match key_event.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => PopupEvent::Confirmed,
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => PopupEvent::Cancelled,
+            _ => PopupEvent::None,
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Flex, Layout, Rect},
+    widgets::{Block, Clear, Paragraph},
+    Frame,
+};
+
+use crate::providers::dry_run::{self, Mutation};
+
+use super::popup::PopupEvent;
+
+/// Scrollable diff of the writes recorded during a dry run.
+#[derive(Debug)]
+pub struct DryRunPopup {
+    lines: Vec<String>,
+    scroll: u16,
+}
+impl DryRunPopup {
+    pub fn new(mutations: &[Mutation]) -> Self {
+        Self {
+            lines: dry_run::diff_lines(mutations),
+            scroll: 0,
+        }
+    }
+
+    /// Adds `notes` below the diff, e.g. the quota the searches of the preview used.
+    pub fn with_notes(mut self, notes: Vec<String>) -> Self {
+        if !notes.is_empty() {
+            self.lines.push(String::new());
+            self.lines.extend(notes);
+        }
+        self
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        /// helper function to create a centered rect using up certain percentage of the available rect `r`
+        fn popup_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
+            let vertical = Layout::vertical([Constraint::Percentage(percent_y)]).flex(Flex::Center);
+            let horizontal =
+                Layout::horizontal([Constraint::Percentage(percent_x)]).flex(Flex::Center);
+            let [area] = vertical.areas(area);
+            let [area] = horizontal.areas(area);
+            area
+        }
+
+        let area = popup_area(area, 80, 60);
+        let [diff_area, help_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(area);
+        frame.render_widget(Clear, area); //this clears out the background
+
+        let diff = Paragraph::new(self.lines.join("\n"))
+            .block(Block::bordered().title("Dry run, nothing was changed"))
+            .scroll((self.scroll, 0));
+        frame.render_widget(diff, diff_area);
+        frame.render_widget(
+            Paragraph::new("Use ↓↑ to scroll, [enter] to close").centered(),
+            help_area,
+        );
+    }
+
+    pub fn handle_key_events(&mut self, key_event: KeyEvent) -> PopupEvent {
+        match key_event.code {
+            KeyCode::Up | KeyCode::Char('w') => {
+                self.scroll = self.scroll.saturating_sub(1);
+                PopupEvent::None
+            }
+            KeyCode::Down | KeyCode::Char('s') => {
+                let last_line = self.lines.len().saturating_sub(1) as u16;
+                self.scroll = (self.scroll + 1).min(last_line);
+                PopupEvent::None
+            }
+            KeyCode::Esc | KeyCode::Enter => PopupEvent::PopupClose,
+            _ => PopupEvent::None,
+        }
+    }
+}
@@ -1,22 +1,26 @@
+use crossterm::event::{KeyCode, KeyEvent};
 use ratatui::{
     layout::{Constraint, Flex, Layout, Rect},
-    widgets::{Block, Clear},
+    widgets::{Block, Clear, Paragraph},
     Frame,
 };
 
+use super::popup::PopupEvent;
+
+/// Shown while a background action runs, its result replaces the popup.
 #[derive(Debug)]
 pub struct LoadingPopup {
-    pub active: bool,
+    pub message: String,
 }
 impl LoadingPopup {
-    pub fn new() -> Self {
-        Self { active: true }
+    pub fn new(message: String) -> Self {
+        Self { message }
     }
 
     pub fn render(&mut self, frame: &mut Frame, area: Rect) {
         /// helper function to create a centered rect using up certain percentage of the available rect `r`
-        fn popup_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
-            let vertical = Layout::vertical([Constraint::Percentage(percent_y)]).flex(Flex::Center);
+        fn popup_area(area: Rect, percent_x: u16, size_y: u16) -> Rect {
+            let vertical = Layout::vertical([Constraint::Length(size_y)]).flex(Flex::Center);
             let horizontal =
                 Layout::horizontal([Constraint::Percentage(percent_x)]).flex(Flex::Center);
             let [area] = vertical.areas(area);
@@ -24,11 +28,19 @@ impl LoadingPopup {
             area
         }
 
-        if self.active {
-            let block = Block::bordered().title("Loading");
-            let area = popup_area(area, 60, 20);
-            frame.render_widget(Clear, area); //this clears out the background
-            frame.render_widget(block, area);
+        let block = Paragraph::new(self.message.as_str())
+            .block(Block::bordered().title("Loading"))
+            .centered();
+        let area = popup_area(area, 60, 3);
+        frame.render_widget(Clear, area); //this clears out the background
+        frame.render_widget(block, area);
+    }
+
+    /// [esc] only hides the popup, the action keeps running.
+    pub fn handle_key_events(&mut self, key_event: KeyEvent) -> PopupEvent {
+        match key_event.code {
+            KeyCode::Esc => PopupEvent::PopupClose,
+            _ => PopupEvent::None,
         }
     }
 }
@@ -1,6 +1,13 @@
 pub mod add_playlist;
 pub mod add_song;
 pub mod add_song_selection;
+pub mod confirm;
+pub mod dry_run;
 pub mod loading_popup;
 pub mod message_popup;
+pub mod playlist_details;
+pub mod playlist_file;
 pub mod popup;
+pub mod sort_songs;
+pub mod transfer_report;
+pub mod transfer_review;
@@ -0,0 +1,142 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Flex, Layout, Rect},
+    widgets::{Clear, Paragraph},
+    Frame,
+};
+
+use crate::{
+    types::music_types::{PlaylistDetails, RSyncPlaylistItem},
+    widgets::generic::user_input::{UserInput, UserInputKeyEvent},
+};
+
+use super::popup::PopupEvent;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DetailsField {
+    Name,
+    Description,
+    Public,
+}
+
+/// Form for the name, description and visibility of a playlist.
+///
+/// [tab] moves between the fields, [space] toggles the visibility and [enter] applies the form.
+#[derive(Debug)]
+pub struct PlaylistDetailsPopup {
+    pub playlist: RSyncPlaylistItem,
+    name: UserInput,
+    description: UserInput,
+    public: bool,
+    focus: DetailsField,
+}
+impl PlaylistDetailsPopup {
+    pub fn new(playlist: RSyncPlaylistItem) -> Self {
+        Self {
+            name: UserInput::new(true)
+                .with_title("Name")
+                .with_value(&playlist.name),
+            description: UserInput::new(true)
+                .with_title("Description")
+                .with_value(playlist.description.as_deref().unwrap_or_default()),
+            public: playlist.public,
+            focus: DetailsField::Name,
+            playlist,
+        }
+    }
+
+    /// Details as entered, an empty description removes it.
+    pub fn details(&self) -> PlaylistDetails {
+        let description = self.description.value().trim();
+        PlaylistDetails {
+            name: self.name.value().trim().to_string(),
+            description: match description.is_empty() {
+                true => None,
+                false => Some(description.to_string()),
+            },
+            public: self.public,
+        }
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        /// helper function to create a centered rect using up certain percentage of the available rect `r`
+        fn popup_area(area: Rect, percent_x: u16, size_y: u16) -> Rect {
+            let vertical = Layout::vertical([Constraint::Length(size_y)]).flex(Flex::Center);
+            let horizontal =
+                Layout::horizontal([Constraint::Percentage(percent_x)]).flex(Flex::Center);
+            let [area] = vertical.areas(area);
+            let [area] = horizontal.areas(area);
+            area
+        }
+
+        let area = popup_area(area, 60, 8);
+        let [name_area, description_area, public_area] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Length(2),
+        ])
+        .areas(area);
+        frame.render_widget(Clear, area); //this clears out the background
+
+        // the focused input is drawn last, it places the cursor
+        match self.focus {
+            DetailsField::Description => {
+                self.name.render(frame, name_area);
+                self.description.render(frame, description_area);
+            }
+            _ => {
+                self.description.render(frame, description_area);
+                self.name.render(frame, name_area);
+            }
+        }
+        let public = format!(
+            "{} [{}] Public\n[tab] next field, [enter] to save, [esc] to cancel",
+            match self.focus {
+                DetailsField::Public => ">",
+                _ => " ",
+            },
+            match self.public {
+                true => "x",
+                false => " ",
+            }
+        );
+        frame.render_widget(Paragraph::new(public), public_area);
+    }
+
+    pub fn handle_key_events(&mut self, key_event: KeyEvent) -> PopupEvent {
+        match key_event.code {
+            KeyCode::Enter if self.name.value().trim().is_empty() => return PopupEvent::None,
+            KeyCode::Enter => return PopupEvent::Submit,
+            KeyCode::Tab => {
+                self.focus = match self.focus {
+                    DetailsField::Name => DetailsField::Description,
+                    DetailsField::Description => DetailsField::Public,
+                    DetailsField::Public => DetailsField::Name,
+                };
+                return PopupEvent::None;
+            }
+            KeyCode::Char(' ') if self.focus == DetailsField::Public => {
+                self.public = !self.public;
+                return PopupEvent::None;
+            }
+            _ => {}
+        }
+
+        let input = match self.focus {
+            DetailsField::Name => Some(&mut self.name),
+            DetailsField::Description => Some(&mut self.description),
+            DetailsField::Public => None,
+        };
+        if let Some(input) = input {
+            match input.handle_key_events(key_event) {
+                UserInputKeyEvent::Pass => {} //pass keypress to next parser
+                _ => return PopupEvent::None,
+            }
+        }
+
+        match key_event.code {
+            KeyCode::Esc => PopupEvent::PopupClose,
+            _ => PopupEvent::None,
+        }
+    }
+}
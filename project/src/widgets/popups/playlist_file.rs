@@ -0,0 +1,83 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Flex, Layout, Rect},
+    widgets::Clear,
+    Frame,
+};
+
+use crate::{
+    playlist_file::{self, PlaylistFormat},
+    types::music_types::{PlaylistIdWrapper, RSyncPlaylistItem},
+    widgets::generic::user_input::{UserInput, UserInputKeyEvent},
+};
+
+use super::popup::PopupEvent;
+
+/// What to do with the entered file path.
+#[derive(Debug)]
+pub enum PlaylistFileAction {
+    /// Write the songs of the playlist to the file.
+    Export(RSyncPlaylistItem),
+    /// Transfer the songs of the file into the playlist.
+    Import(PlaylistIdWrapper),
+}
+
+/// Prompt for the path of a playlist file to export to or import from.
+#[derive(Debug)]
+pub struct PlaylistFilePopup {
+    pub user_input: UserInput,
+    pub action: PlaylistFileAction,
+}
+impl PlaylistFilePopup {
+    pub fn export(playlist: RSyncPlaylistItem) -> Self {
+        let path = playlist_file::default_path(&playlist.name, PlaylistFormat::M3u8);
+        Self {
+            user_input: UserInput::new(true)
+                .with_title("Export to (.m3u8, .xspf, .csv, .json)")
+                .with_value(&path.to_string_lossy()),
+            action: PlaylistFileAction::Export(playlist),
+        }
+    }
+
+    pub fn import(playlist_id: PlaylistIdWrapper) -> Self {
+        Self {
+            user_input: UserInput::new(true).with_title("Import from (.m3u8, .xspf, .csv, .json)"),
+            action: PlaylistFileAction::Import(playlist_id),
+        }
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        /// helper function to create a centered rect using up certain percentage of the available rect `r`
+        fn popup_area(area: Rect, percent_x: u16, _percent_y: u16) -> Rect {
+            let vertical = Layout::vertical([Constraint::Length(3)]).flex(Flex::Center);
+            let horizontal =
+                Layout::horizontal([Constraint::Percentage(percent_x)]).flex(Flex::Center);
+            let [area] = vertical.areas(area);
+            let [area] = horizontal.areas(area);
+            area
+        }
+
+        let area = popup_area(area, 60, 20);
+        frame.render_widget(Clear, area); //this clears out the background
+        self.user_input.render(frame, area);
+    }
+
+    pub fn handle_key_events(&mut self, key_event: KeyEvent) -> PopupEvent {
+        match self.user_input.handle_key_events(key_event) {
+            UserInputKeyEvent::None => return PopupEvent::None,
+            UserInputKeyEvent::Pass => {} //pass keypress to next parser
+            UserInputKeyEvent::Data(path) => {
+                if !path.trim().is_empty() {
+                    return PopupEvent::PopupCloseData(path.trim().to_string());
+                } else {
+                    return PopupEvent::None;
+                }
+            }
+        }
+
+        match key_event.code {
+            KeyCode::Esc => PopupEvent::PopupClose,
+            _ => PopupEvent::None,
+        }
+    }
+}
@@ -1,8 +1,15 @@
 use crossterm::event::KeyEvent;
 
-use crate::widgets::popups::{
-    add_playlist::AddPlaylistPopup, add_song::AddSongPopup,
-    add_song_selection::AddSongSelectionPopup, message_popup::MessagePopup,
+use crate::{
+    providers::registry::ProviderId,
+    widgets::popups::{
+        add_playlist::AddPlaylistPopup, add_song::AddSongPopup,
+        add_song_selection::AddSongSelectionPopup, confirm::ConfirmPopup, dry_run::DryRunPopup,
+        loading_popup::LoadingPopup, message_popup::MessagePopup,
+        playlist_details::PlaylistDetailsPopup, playlist_file::PlaylistFilePopup,
+        sort_songs::SortSongsPopup, transfer_report::TransferReportPopup,
+        transfer_review::TransferReviewPopup,
+    },
 };
 
 #[derive(Debug)]
@@ -10,6 +17,11 @@ pub enum PopupEvent {
     PopupClose,
     PopupCloseRefresh,
     PopupCloseData(String),
+    /// The popup is done and its content should be applied.
+    Submit,
+    /// The user agreed to the action of a [`ConfirmPopup`].
+    Confirmed,
+    Cancelled,
     None,
     Pass,
 }
@@ -17,18 +29,27 @@ pub enum PopupEvent {
 #[derive(Debug)]
 pub enum GenericPopup {
     Message(MessagePopup),
+    TransferReport(Box<TransferReportPopup>),
+    DryRun(DryRunPopup),
+    Loading(LoadingPopup),
 }
 
 impl GenericPopup {
     pub fn render(&mut self, frame: &mut ratatui::Frame<'_>, area: ratatui::prelude::Rect) {
         match self {
             GenericPopup::Message(message_popup) => message_popup.render(frame, area),
+            GenericPopup::TransferReport(popup) => popup.render(frame, area),
+            GenericPopup::DryRun(popup) => popup.render(frame, area),
+            GenericPopup::Loading(popup) => popup.render(frame, area),
         }
     }
 
     pub fn handle_key_events(&mut self, key_event: KeyEvent) -> PopupEvent {
         match self {
             GenericPopup::Message(message_popup) => message_popup.handle_key_events(key_event),
+            GenericPopup::TransferReport(popup) => popup.handle_key_events(key_event),
+            GenericPopup::DryRun(popup) => popup.handle_key_events(key_event),
+            GenericPopup::Loading(popup) => popup.handle_key_events(key_event),
         }
     }
 }
@@ -38,6 +59,11 @@ pub enum PlatformPopup {
     AddSong(AddSongPopup),
     AddSongSelect(AddSongSelectionPopup),
     AddPlaylist(AddPlaylistPopup),
+    TransferReview(Box<TransferReviewPopup>),
+    PlaylistFile(PlaylistFilePopup),
+    Confirm(ConfirmPopup),
+    PlaylistDetails(Box<PlaylistDetailsPopup>),
+    SortSongs(SortSongsPopup),
 }
 
 impl PlatformPopup {
@@ -46,6 +72,11 @@ impl PlatformPopup {
             PlatformPopup::AddSong(popup) => popup.render(frame, area),
             PlatformPopup::AddSongSelect(popup) => popup.render(frame, area),
             PlatformPopup::AddPlaylist(popup) => popup.render(frame, area),
+            PlatformPopup::TransferReview(popup) => popup.render(frame, area),
+            PlatformPopup::PlaylistFile(popup) => popup.render(frame, area),
+            PlatformPopup::Confirm(popup) => popup.render(frame, area),
+            PlatformPopup::PlaylistDetails(popup) => popup.render(frame, area),
+            PlatformPopup::SortSongs(popup) => popup.render(frame, area),
         }
     }
 
@@ -54,29 +85,32 @@ impl PlatformPopup {
             PlatformPopup::AddSong(popup) => popup.handle_key_events(key_event),
             PlatformPopup::AddSongSelect(popup) => popup.handle_key_events(key_event),
             PlatformPopup::AddPlaylist(popup) => popup.handle_key_events(key_event),
+            PlatformPopup::TransferReview(popup) => popup.handle_key_events(key_event),
+            PlatformPopup::PlaylistFile(popup) => popup.handle_key_events(key_event),
+            PlatformPopup::Confirm(popup) => popup.handle_key_events(key_event),
+            PlatformPopup::PlaylistDetails(popup) => popup.handle_key_events(key_event),
+            PlatformPopup::SortSongs(popup) => popup.handle_key_events(key_event),
         }
     }
 }
 
 #[derive(Debug)]
 pub enum PopupTyped {
-    Spotify(PlatformPopup),
-    Youtube(PlatformPopup),
+    /// Popup acting on the column of the provider.
+    Platform(ProviderId, PlatformPopup),
     Generic(GenericPopup),
 }
 impl PopupTyped {
     pub fn render(&mut self, frame: &mut ratatui::Frame<'_>, area: ratatui::prelude::Rect) {
         match self {
-            PopupTyped::Youtube(popup) | PopupTyped::Spotify(popup) => popup.render(frame, area),
+            PopupTyped::Platform(_, popup) => popup.render(frame, area),
             PopupTyped::Generic(popup) => popup.render(frame, area),
         }
     }
 
     pub fn handle_key_events(&mut self, key_event: KeyEvent) -> PopupEvent {
         match self {
-            PopupTyped::Youtube(popup) | PopupTyped::Spotify(popup) => {
-                popup.handle_key_events(key_event)
-            }
+            PopupTyped::Platform(_, popup) => popup.handle_key_events(key_event),
             PopupTyped::Generic(popup) => popup.handle_key_events(key_event),
         }
     }
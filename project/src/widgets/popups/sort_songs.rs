@@ -0,0 +1,68 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Flex, Layout, Rect},
+    widgets::Clear,
+    Frame,
+};
+
+use crate::{
+    reorder::SortKey,
+    types::music_types::PlaylistIdWrapper,
+    widgets::generic::list_selector::{ListSelector, ListSelectorKeyResponse, ListSelectorLabels},
+};
+
+use super::popup::PopupEvent;
+
+/// Asks by what the songs of a playlist are sorted, [enter] applies the order remotely.
+#[derive(Debug)]
+pub struct SortSongsPopup {
+    pub playlist_id: PlaylistIdWrapper,
+    selector: ListSelector<SortKey>,
+}
+impl SortSongsPopup {
+    pub fn new(playlist_id: PlaylistIdWrapper) -> Self {
+        Self {
+            playlist_id,
+            selector: ListSelector::new(
+                Some(SortKey::ALL.to_vec()),
+                ListSelectorLabels {
+                    empty: "".into(),
+                    title: "Sort songs by".into(),
+                },
+                false,
+            ),
+        }
+    }
+
+    pub fn key(&mut self) -> Option<SortKey> {
+        self.selector.get_selected_items().first().map(|key| **key)
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        /// helper function to create a centered rect using up certain percentage of the available rect `r`
+        fn popup_area(area: Rect, percent_x: u16, size_y: u16) -> Rect {
+            let vertical = Layout::vertical([Constraint::Length(size_y)]).flex(Flex::Center);
+            let horizontal =
+                Layout::horizontal([Constraint::Percentage(percent_x)]).flex(Flex::Center);
+            let [area] = vertical.areas(area);
+            let [area] = horizontal.areas(area);
+            area
+        }
+
+        let area = popup_area(area, 30, SortKey::ALL.len() as u16 + 2);
+        frame.render_widget(Clear, area); //this clears out the background
+        self.selector.render(frame, area, true);
+    }
+
+    pub fn handle_key_events(&mut self, key_event: KeyEvent) -> PopupEvent {
+        match self.selector.handle_key_events(key_event) {
+            ListSelectorKeyResponse::Selected => PopupEvent::Submit,
+            ListSelectorKeyResponse::CursorMoved => PopupEvent::None,
+            ListSelectorKeyResponse::None => PopupEvent::None,
+            ListSelectorKeyResponse::Pass => match key_event.code {
+                KeyCode::Esc => PopupEvent::PopupClose,
+                _ => PopupEvent::Pass,
+            },
+        }
+    }
+}
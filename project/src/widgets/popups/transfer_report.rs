@@ -0,0 +1,93 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Flex, Layout, Rect},
+    widgets::{Block, Clear, Paragraph},
+    Frame,
+};
+
+use crate::report::{ReportFormat, TransferReport};
+
+use super::popup::PopupEvent;
+
+/// Scrollable list of what happened to every song of a finished transfer.
+#[derive(Debug)]
+pub struct TransferReportPopup {
+    report: TransferReport,
+    lines: Vec<String>,
+    scroll: u16,
+    /// Result of the last export, shown under the report.
+    status: String,
+}
+impl TransferReportPopup {
+    pub fn new(report: TransferReport) -> Self {
+        Self {
+            lines: report.lines(),
+            status: report.summary(),
+            report,
+            scroll: 0,
+        }
+    }
+
+    fn export(&mut self, format: ReportFormat) {
+        self.status = match self.report.export(format) {
+            Ok(path) => format!("Report written to {}", path.display()),
+            Err(err) => format!("Failed to write report: {}", err),
+        };
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        /// helper function to create a centered rect using up certain percentage of the available rect `r`
+        fn popup_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
+            let vertical = Layout::vertical([Constraint::Percentage(percent_y)]).flex(Flex::Center);
+            let horizontal =
+                Layout::horizontal([Constraint::Percentage(percent_x)]).flex(Flex::Center);
+            let [area] = vertical.areas(area);
+            let [area] = horizontal.areas(area);
+            area
+        }
+
+        let area = popup_area(area, 80, 60);
+        let [report_area, status_area, help_area] = Layout::vertical([
+            Constraint::Fill(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(area);
+        frame.render_widget(Clear, area); //this clears out the background
+
+        let report = Paragraph::new(self.lines.join("\n"))
+            .block(Block::bordered().title("Transfer report"))
+            .scroll((self.scroll, 0));
+        frame.render_widget(report, report_area);
+        frame.render_widget(Paragraph::new(self.status.as_str()).centered(), status_area);
+        frame.render_widget(
+            Paragraph::new("Use ↓↑ to scroll, [j] export JSON, [c] export CSV, [enter] to close")
+                .centered(),
+            help_area,
+        );
+    }
+
+    pub fn handle_key_events(&mut self, key_event: KeyEvent) -> PopupEvent {
+        match key_event.code {
+            KeyCode::Up | KeyCode::Char('w') => {
+                self.scroll = self.scroll.saturating_sub(1);
+                PopupEvent::None
+            }
+            KeyCode::Down | KeyCode::Char('s') => {
+                let last_line = self.lines.len().saturating_sub(1) as u16;
+                self.scroll = (self.scroll + 1).min(last_line);
+                PopupEvent::None
+            }
+            KeyCode::Char('j') => {
+                self.export(ReportFormat::Json);
+                PopupEvent::None
+            }
+            KeyCode::Char('c') => {
+                self.export(ReportFormat::Csv);
+                PopupEvent::None
+            }
+            KeyCode::Esc | KeyCode::Enter => PopupEvent::PopupClose,
+            _ => PopupEvent::None,
+        }
+    }
+}
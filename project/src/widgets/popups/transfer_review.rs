@@ -0,0 +1,172 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Flex, Layout, Rect},
+    text::Text,
+    widgets::{Clear, Paragraph},
+    Frame,
+};
+
+use crate::{
+    jobs::JobId,
+    matching,
+    transfer::{PlannedSong, TransferPlan},
+    widgets::generic::list_selector::{ListSelector, ListSelectorLabels},
+};
+
+use super::{add_song_selection::AddSongSelectionPopup, popup::PopupEvent};
+
+impl<'a> From<PlannedSong> for Text<'a> {
+    fn from(planned: PlannedSong) -> Self {
+        let source = &planned.song_match.source;
+        match (&planned.choice, &planned.song_match.found) {
+            (Some(choice), _) => format!(
+                "✔ {} ({}) -> {} ({}) {:.0}%",
+                source.name,
+                source.artists,
+                choice.name,
+                choice.artists,
+                matching::score(source, choice) * 100.0
+            ),
+            (None, Some(found)) => format!(
+                "✘ {} ({}) -> skip, best: {} ({}) {:.0}%",
+                source.name,
+                source.artists,
+                found.name,
+                found.artists,
+                planned.song_match.confidence * 100.0
+            ),
+            (None, None) => format!("✘ {} ({}) -> not found", source.name, source.artists),
+        }
+        .into()
+    }
+}
+
+/// Lets the user accept, replace or skip every match of a transfer before it is added.
+#[derive(Debug)]
+pub struct TransferReviewPopup {
+    /// Job waiting for the review.
+    pub job: JobId,
+    plan: TransferPlan,
+    selector: ListSelector<PlannedSong>,
+    /// Candidate list of the row under the cursor, opened with [c].
+    candidate_picker: Option<(usize, AddSongSelectionPopup)>,
+}
+impl TransferReviewPopup {
+    pub fn new(job: JobId, plan: TransferPlan) -> Self {
+        Self {
+            job,
+            selector: ListSelector::new(
+                Some(plan.songs.clone()),
+                ListSelectorLabels {
+                    empty: "".into(),
+                    title: "Review matches".into(),
+                },
+                false,
+            ),
+            plan,
+            candidate_picker: None,
+        }
+    }
+
+    /// The plan with the choices made by the user.
+    pub fn into_plan(self) -> TransferPlan {
+        self.plan
+    }
+
+    fn set_choice(&mut self, index: usize, choice_id: Option<String>) {
+        if let Some(planned) = self.plan.songs.get_mut(index) {
+            planned.choice = choice_id.and_then(|id| {
+                planned
+                    .song_match
+                    .candidates
+                    .iter()
+                    .find(|candidate| candidate.id == id)
+                    .cloned()
+            });
+        }
+        self.selector.set_items(Some(self.plan.songs.clone()));
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        /// helper function to create a centered rect using up certain percentage of the available rect `r`
+        fn popup_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
+            let vertical = Layout::vertical([Constraint::Percentage(percent_y)]).flex(Flex::Center);
+            let horizontal =
+                Layout::horizontal([Constraint::Percentage(percent_x)]).flex(Flex::Center);
+            let [area] = vertical.areas(area);
+            let [area] = horizontal.areas(area);
+            area
+        }
+
+        let area = popup_area(area, 80, 60);
+        let [list_area, help_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(area);
+        frame.render_widget(Clear, area); //this clears out the background
+
+        self.selector.render(frame, list_area, true);
+        let help_message = Paragraph::new(
+            "[enter] accept best, [c] choose candidate, [x] skip, [y] add accepted songs",
+        )
+        .centered();
+        frame.render_widget(help_message, help_area);
+
+        if let Some((_, picker)) = self.candidate_picker.as_mut() {
+            picker.render(frame, area);
+        }
+    }
+
+    pub fn handle_key_events(&mut self, key_event: KeyEvent) -> PopupEvent {
+        if let Some((index, picker)) = self.candidate_picker.as_mut() {
+            let index = *index;
+            match picker.handle_key_events(key_event) {
+                PopupEvent::PopupCloseData(song_id) => {
+                    self.candidate_picker = None;
+                    self.set_choice(index, Some(song_id));
+                }
+                PopupEvent::Pass if key_event.code == KeyCode::Esc => {
+                    self.candidate_picker = None;
+                }
+                _ => (),
+            }
+            return PopupEvent::None;
+        }
+
+        if self.selector.is_typing_filter() {
+            self.selector.handle_key_events(key_event);
+            return PopupEvent::None;
+        }
+
+        let cursor = self.selector.get_cursor_index();
+        match (key_event.code, cursor) {
+            (KeyCode::Enter, Some(index)) => {
+                let best = self.plan.songs[index]
+                    .song_match
+                    .found
+                    .as_ref()
+                    .map(|song| song.id.clone());
+                self.set_choice(index, best);
+                PopupEvent::None
+            }
+            (KeyCode::Char('x') | KeyCode::Delete, Some(index)) => {
+                self.set_choice(index, None);
+                PopupEvent::None
+            }
+            (KeyCode::Char('c'), Some(index)) => {
+                let candidates = self.plan.songs[index].song_match.candidates.clone();
+                if !candidates.is_empty() {
+                    self.candidate_picker = Some((
+                        index,
+                        AddSongSelectionPopup::new(candidates, self.plan.playlist_id.clone()),
+                    ));
+                }
+                PopupEvent::None
+            }
+            (KeyCode::Char('y'), _) => PopupEvent::Submit,
+            (KeyCode::Esc, _) => PopupEvent::PopupClose,
+            _ => {
+                self.selector.handle_key_events(key_event);
+                PopupEvent::None
+            }
+        }
+    }
+}
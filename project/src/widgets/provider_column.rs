@@ -0,0 +1,194 @@
+use crossterm::event::KeyEvent;
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    Frame,
+};
+use tokio::sync::mpsc;
+
+use crate::{
+    action::{Action, ActionExecutor},
+    app::ColumnBlock,
+    event::{Event, GlobalEventData, GlobalEventDataFullfilness, PartialDataSender},
+    providers::{provider_traits::APIProvider, registry::ProviderId},
+    request::RequestTracker,
+    types::{
+        music_types::{RSyncPlaylistItem, RSyncSong},
+        playlist_selector_key_event_response::SelectorKeyEventResponse,
+    },
+};
+
+use super::{playlist_selector::PlaylistSelector, song_selector::SongSelector};
+
+/// Playlists and songs of one provider.
+#[derive(Debug)]
+pub struct ProviderColumn<P: APIProvider> {
+    pub id: ProviderId,
+    pub name: String,
+    pub provider: P,
+    /// Runs the provider calls of key handlers in the background.
+    executor: ActionExecutor,
+    pub playlist_selector: PlaylistSelector,
+    pub song_selector: SongSelector,
+    render_rows: Layout,
+    global_event_sender: mpsc::UnboundedSender<Event>,
+    songs_request: RequestTracker,
+    playlists_request: RequestTracker,
+}
+impl<P: APIProvider> ProviderColumn<P> {
+    pub fn new(
+        id: ProviderId,
+        name: String,
+        provider: P,
+        executor: ActionExecutor,
+        global_event_sender: mpsc::UnboundedSender<Event>,
+    ) -> Self {
+        let mut s = Self {
+            playlist_selector: PlaylistSelector::new(format!("{} playlists", name)),
+            id,
+            name,
+            song_selector: SongSelector::new("Playlist songs".into()),
+            provider,
+            executor,
+            render_rows: Layout::vertical([Constraint::Percentage(40), Constraint::Percentage(60)]),
+            global_event_sender,
+            playlists_request: RequestTracker::default(),
+            songs_request: RequestTracker::default(),
+        };
+        s.refresh_playlists();
+        s
+    }
+
+    fn data_sender(&self, request_id: u128) -> PartialDataSender {
+        PartialDataSender {
+            sender: self.global_event_sender.clone(),
+            request_id,
+            provider_id: self.id.clone(),
+        }
+    }
+
+    pub fn refresh_songs(&mut self) {
+        self.set_songs(None);
+        if let Some(playlist) = self.playlist_selector.get_selected().first() {
+            self.song_selector.set_loading();
+            let p_id = playlist.id.clone();
+            let mut provider_clone = self.provider.clone();
+            let request_id = self.songs_request.start();
+            let event_sender = self.data_sender(request_id);
+            let handle = tokio::spawn(async move {
+                let pages = provider_clone.playlist_song_pages(p_id);
+                event_sender
+                    .forward_pages(pages, GlobalEventData::Songs)
+                    .await
+            });
+            self.songs_request.track(handle);
+        } else {
+            self.songs_request.abort();
+        }
+    }
+
+    pub fn append_songs(&mut self, items: Vec<RSyncSong>) {
+        self.song_selector.append_items(items);
+    }
+
+    pub fn set_songs(&mut self, items: Option<Vec<RSyncSong>>) {
+        self.song_selector.set_items(items);
+        self.song_selector.clear_selected();
+    }
+
+    pub fn refresh_playlists(&mut self) {
+        self.set_playlists(None);
+        self.playlist_selector.set_loading();
+        let mut provider_clone = self.provider.clone();
+        let request_id = self.playlists_request.start();
+        let event_sender = self.data_sender(request_id);
+        let handle = tokio::spawn(async move {
+            let pages = provider_clone.playlist_pages();
+            event_sender
+                .forward_pages(pages, GlobalEventData::Playlists)
+                .await
+        });
+        self.playlists_request.track(handle);
+    }
+
+    pub fn append_playlists(&mut self, items: Vec<RSyncPlaylistItem>) {
+        self.playlist_selector.append_items(items);
+    }
+
+    pub fn set_playlists(&mut self, items: Option<Vec<RSyncPlaylistItem>>) {
+        self.playlist_selector.set_items(items);
+        self.playlist_selector.clear_selected();
+    }
+
+    /// Runs `action` in the background, the block it changes shows a loading state meanwhile.
+    pub fn dispatch(&mut self, action: Action) {
+        match action.block() {
+            Some(ColumnBlock::PlaylistSelector) => self.playlist_selector.set_loading(),
+            Some(ColumnBlock::SongSelector) => self.song_selector.set_loading(),
+            None => (),
+        }
+        self.executor.dispatch(action);
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        let [playlist_selection_area, song_selection_area] = self.render_rows.areas(area);
+        self.playlist_selector
+            .render(frame, playlist_selection_area);
+        self.song_selector.render(frame, song_selection_area);
+    }
+
+    pub fn handle_received_data(&mut self, request_id: u128, data: GlobalEventData) {
+        match data {
+            GlobalEventData::Playlists(event_data) => {
+                if self.playlists_request.is_current(request_id) {
+                    match event_data {
+                        GlobalEventDataFullfilness::Partial(vec) => self.append_playlists(vec),
+                        // ends the loading state of a listing without pages
+                        GlobalEventDataFullfilness::Complete => self.append_playlists(Vec::new()),
+                    }
+                }
+            }
+
+            GlobalEventData::Songs(event_data) => {
+                if self.songs_request.is_current(request_id) {
+                    match event_data {
+                        GlobalEventDataFullfilness::Partial(vec) => self.append_songs(vec),
+                        GlobalEventDataFullfilness::Complete => self.append_songs(Vec::new()),
+                    }
+                }
+            }
+
+            GlobalEventData::Error(_) => {
+                // the error itself is shown by the app, just stop the loading indicator
+                if self.playlists_request.is_current(request_id) {
+                    self.set_playlists(None);
+                }
+                if self.songs_request.is_current(request_id) {
+                    self.set_songs(None);
+                }
+            }
+        }
+    }
+
+    /// Handles a key in the active block, returns `false` if the key is left to the app.
+    pub fn handle_key_events(&mut self, key_event: KeyEvent, active_block: ColumnBlock) -> bool {
+        let response = match active_block {
+            ColumnBlock::PlaylistSelector => self.playlist_selector.handle_key_events(key_event),
+            ColumnBlock::SongSelector => self.song_selector.handle_key_events(key_event),
+        };
+        match (response, active_block) {
+            (SelectorKeyEventResponse::Selected(_), ColumnBlock::PlaylistSelector) => {
+                self.refresh_songs();
+            }
+            (SelectorKeyEventResponse::Selected(_), ColumnBlock::SongSelector) => (),
+            (SelectorKeyEventResponse::Refresh, ColumnBlock::PlaylistSelector) => {
+                self.refresh_playlists();
+            }
+            (SelectorKeyEventResponse::Refresh, ColumnBlock::SongSelector) => {
+                self.refresh_songs();
+            }
+            (SelectorKeyEventResponse::None, _) => (),
+            (SelectorKeyEventResponse::Pass, _) => return false,
+        };
+        true
+    }
+}
@@ -33,6 +33,18 @@ impl SongSelector {
         self.selector.get_selected_items()
     }
 
+    pub fn get_songs(&self) -> Vec<RSyncSong> {
+        self.selector
+            .get_items()
+            .map(<[RSyncSong]>::to_vec)
+            .unwrap_or_default()
+    }
+
+    /// See [`ListSelector::move_selected`].
+    pub fn move_selected(&mut self, up: bool) -> Option<Vec<usize>> {
+        self.selector.move_selected(up)
+    }
+
     pub fn clear_selected(&mut self) {
         self.selector.clear_selected();
     }